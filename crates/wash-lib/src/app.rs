@@ -0,0 +1,542 @@
+//! A client for wadm, the wasmCloud application deployment manager, which manages declarative
+//! application specifications (models) and their deployments in a lattice
+
+use std::{fmt, time::Duration};
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use thiserror::Error;
+
+use crate::config::DEFAULT_LATTICE_PREFIX;
+
+/// Errors that can occur when making requests of wadm
+#[derive(Debug, Error)]
+pub enum AppError {
+    /// The request could not be sent, or no reply was received
+    #[error("Error making message request: {0}")]
+    Request(String),
+    /// Nothing is listening for wadm requests on the given subject, which usually means wadm
+    /// isn't running for the lattice prefix in use
+    #[error("No wadm instance responded on {0}, is wadm running for this lattice prefix?")]
+    NoResponders(String),
+    /// No reply was received before the request timed out
+    #[error("Timed out waiting for a reply from wadm")]
+    Timeout,
+    /// wadm received the request but refused it, with the given reason
+    #[error("{0}")]
+    Rejected(String),
+    /// wadm replied with a payload that could not be decoded
+    #[error("Invalid response from wadm: {0}")]
+    InvalidResponse(#[from] serde_json::Error),
+    /// wadm accepted the request but did not reply with the expected data
+    #[error("Failed to obtain reply from wadm")]
+    EmptyResponse,
+}
+
+impl AppError {
+    /// Whether the request may succeed if it is sent again, e.g. once wadm has started
+    pub fn is_transient(&self) -> bool {
+        matches!(
+            self,
+            AppError::Request(_) | AppError::NoResponders(_) | AppError::Timeout
+        )
+    }
+
+    /// Whether the request is known to have never reached wadm, so sending it again can't apply
+    /// it twice. A timed out request may still have been handled, just too late to reply
+    pub fn is_undelivered(&self) -> bool {
+        matches!(self, AppError::NoResponders(_))
+    }
+
+    /// A short, stable name for the kind of error, for use in machine readable output
+    pub fn kind(&self) -> &'static str {
+        match self {
+            AppError::Request(_) => "request",
+            AppError::NoResponders(_) => "no_responders",
+            AppError::Timeout => "timeout",
+            AppError::Rejected(_) => "rejected",
+            AppError::InvalidResponse(_) => "invalid_response",
+            AppError::EmptyResponse => "empty_response",
+        }
+    }
+}
+
+/// Sends requests to wadm and returns its replies. [NatsTransport] is used to talk to a real
+/// wadm instance, but other implementations can be swapped in, e.g. in tests
+#[async_trait]
+pub trait WadmTransport: Send + Sync {
+    /// Sends `payload` as a request on `subject` and returns the payload of the reply
+    async fn request(&self, subject: String, payload: Vec<u8>) -> Result<Vec<u8>, AppError>;
+}
+
+/// A [WadmTransport] that sends requests over a NATS connection
+pub struct NatsTransport {
+    client: async_nats::Client,
+    timeout: Duration,
+}
+
+impl NatsTransport {
+    pub fn new(client: async_nats::Client, timeout: Duration) -> Self {
+        NatsTransport { client, timeout }
+    }
+}
+
+#[async_trait]
+impl WadmTransport for NatsTransport {
+    async fn request(&self, subject: String, payload: Vec<u8>) -> Result<Vec<u8>, AppError> {
+        match tokio::time::timeout(
+            self.timeout,
+            self.client.request(subject.clone(), payload.into()),
+        )
+        .await
+        {
+            Ok(Ok(res)) => Ok(res.payload.to_vec()),
+            // async-nats reports a missing responder and its own timeout as io errors
+            Ok(Err(e)) => match e.downcast_ref::<std::io::Error>().map(|e| e.kind()) {
+                Some(std::io::ErrorKind::NotFound) => Err(AppError::NoResponders(subject)),
+                Some(std::io::ErrorKind::TimedOut) => Err(AppError::Timeout),
+                _ => Err(AppError::Request(e.to_string())),
+            },
+            Err(_) => Err(AppError::Timeout),
+        }
+    }
+}
+
+/// The envelope wadm wraps around each of its replies
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct WadmEnvelope {
+    pub result: String,
+    pub message: Option<String>,
+    pub data: Option<serde_json::Value>,
+}
+
+/// A summary of an app specification, as listed by wadm
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ModelSummary {
+    pub name: String,
+    pub version: String,
+    pub description: String,
+    pub deployment_status: String,
+}
+
+/// A single version in the history of an app specification
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ModelRevision {
+    pub version: String,
+    pub created: String,
+    pub deployed: bool,
+}
+
+/// A version of an app specification, both as it was submitted (`raw`) and as wadm
+/// understood it (`vetted`)
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ModelDetails {
+    pub raw: String,
+    pub vetted: serde_json::Value,
+}
+
+/// The reply to storing a new version of an app specification
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct PutReply {
+    pub current_version: String,
+    pub name: String,
+}
+
+/// The reply to deploying a version of an app
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct DeployReply {
+    #[serde(default)]
+    pub acknowledged: bool,
+    /// Why wadm didn't acknowledge the deployment, if it said
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum StatusType {
+    Undeployed,
+    Reconciling,
+    Deployed,
+    Failed,
+    #[default]
+    #[serde(other)]
+    Unknown,
+}
+
+impl fmt::Display for StatusType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let status = match self {
+            StatusType::Undeployed => "undeployed",
+            StatusType::Reconciling => "reconciling",
+            StatusType::Deployed => "deployed",
+            StatusType::Failed => "failed",
+            StatusType::Unknown => "unknown",
+        };
+        write!(f, "{}", status)
+    }
+}
+
+/// The deployment status of an app, as reported by wadm
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct ModelStatus {
+    #[serde(default)]
+    pub version: String,
+    #[serde(default)]
+    pub status: StatusType,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    #[serde(default)]
+    pub components: Vec<ComponentStatus>,
+}
+
+/// The deployment status of a single component of an app
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct ComponentStatus {
+    pub name: String,
+    #[serde(rename = "type", default)]
+    pub component_type: String,
+    #[serde(default)]
+    pub status: StatusType,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+/// A client for the wadm API of a lattice
+pub struct WadmClient<T = NatsTransport> {
+    transport: T,
+    lattice_prefix: String,
+    retries: u32,
+    retry_backoff: Duration,
+}
+
+impl WadmClient<NatsTransport> {
+    /// Creates a client that talks to wadm over the given NATS connection. Requests will fail
+    /// with [AppError::Timeout] if wadm hasn't replied within `timeout`
+    pub fn new(
+        client: async_nats::Client,
+        lattice_prefix: Option<String>,
+        timeout: Duration,
+    ) -> Self {
+        WadmClient::with_transport(NatsTransport::new(client, timeout), lattice_prefix)
+    }
+}
+
+impl<T: WadmTransport> WadmClient<T> {
+    /// Creates a client that sends its requests with the given transport
+    pub fn with_transport(transport: T, lattice_prefix: Option<String>) -> Self {
+        WadmClient {
+            transport,
+            lattice_prefix: lattice_prefix.unwrap_or_else(|| DEFAULT_LATTICE_PREFIX.to_string()),
+            retries: 0,
+            retry_backoff: Duration::ZERO,
+        }
+    }
+
+    /// Retries requests that fail with a [transient](AppError::is_transient) error up to
+    /// `retries` times, waiting `backoff` before the first retry and doubling the wait after
+    /// each one. Requests that change the lattice (put, del, deploy and undeploy) are only
+    /// retried when they [never reached wadm](AppError::is_undelivered)
+    pub fn with_retries(mut self, retries: u32, backoff: Duration) -> Self {
+        self.retries = retries;
+        self.retry_backoff = backoff;
+        self
+    }
+
+    /// Lists the app specifications stored in the lattice
+    pub async fn list(&self) -> Result<Vec<ModelSummary>, AppError> {
+        let res = self.query(&["list"], json!({})).await?;
+        Ok(serde_json::from_value(res.ok_or(AppError::EmptyResponse)?)?)
+    }
+
+    /// Retrieves a version of an app specification
+    pub async fn get(&self, model_name: &str, version: &str) -> Result<ModelDetails, AppError> {
+        let res = self
+            .query(&["get", model_name], json!({ "version": version }))
+            .await?;
+        Ok(serde_json::from_value(res.ok_or(AppError::EmptyResponse)?)?)
+    }
+
+    /// Retrieves the version history of an app specification
+    pub async fn history(&self, model_name: &str) -> Result<Vec<ModelRevision>, AppError> {
+        let res = self.query(&["versions", model_name], json!({})).await?;
+        Ok(serde_json::from_value(res.ok_or(AppError::EmptyResponse)?)?)
+    }
+
+    /// Stores a new version of an app specification, given as the JSON or YAML contents of a
+    /// specification file
+    pub async fn put(&self, model: &[u8]) -> Result<PutReply, AppError> {
+        let res = self.raw_request(&["put"], model, false).await?;
+        Ok(serde_json::from_value(res.ok_or(AppError::EmptyResponse)?)?)
+    }
+
+    /// Deletes a version of an app specification, returning whether it was deleted
+    pub async fn del(&self, model_name: &str, version: &str) -> Result<bool, AppError> {
+        let res = self
+            .mutation(&["del", model_name], json!({ "version": version }))
+            .await?;
+        Ok(res.is_some())
+    }
+
+    /// Deploys a version of an app, returning whether wadm acknowledged the deployment
+    pub async fn deploy(&self, model_name: &str, version: &str) -> Result<DeployReply, AppError> {
+        let res = self
+            .mutation(&["deploy", model_name], json!({ "version": version }))
+            .await?;
+        Ok(serde_json::from_value(res.ok_or(AppError::EmptyResponse)?)?)
+    }
+
+    /// Undeploys an app, returning whether wadm acknowledged the request
+    pub async fn undeploy(&self, model_name: &str) -> Result<bool, AppError> {
+        let res = self.mutation(&["undeploy", model_name], json!({})).await?;
+        Ok(res.is_some())
+    }
+
+    /// Retrieves the deployment status of an app
+    pub async fn status(&self, model_name: &str) -> Result<ModelStatus, AppError> {
+        let res = self.query(&["status", model_name], json!({})).await?;
+        Ok(serde_json::from_value(res.ok_or(AppError::EmptyResponse)?)?)
+    }
+
+    /// Sends a request, retrying any transient failure when `idempotent` and only undelivered
+    /// requests otherwise
+    async fn raw_request(
+        &self,
+        elements: &[&str],
+        req: &[u8],
+        idempotent: bool,
+    ) -> Result<Option<serde_json::Value>, AppError> {
+        let topic = generate_topic(&self.lattice_prefix, elements);
+        let mut backoff = self.retry_backoff;
+        let mut attempt = 0;
+        let res = loop {
+            match self.transport.request(topic.clone(), req.to_vec()).await {
+                Err(e)
+                    if (e.is_undelivered() || (idempotent && e.is_transient()))
+                        && attempt < self.retries =>
+                {
+                    attempt += 1;
+                    tokio::time::sleep(backoff).await;
+                    backoff *= 2;
+                }
+                res => break res?,
+            }
+        };
+        let env: WadmEnvelope = serde_json::from_slice(&res)?;
+        if env.result == "success" {
+            Ok(env.data)
+        } else {
+            Err(AppError::Rejected(env.message.unwrap_or_default()))
+        }
+    }
+
+    async fn query(
+        &self,
+        elements: &[&str],
+        req: serde_json::Value,
+    ) -> Result<Option<serde_json::Value>, AppError> {
+        let msg = serde_json::to_vec(&req)?;
+        self.raw_request(elements, &msg, true).await
+    }
+
+    async fn mutation(
+        &self,
+        elements: &[&str],
+        req: serde_json::Value,
+    ) -> Result<Option<serde_json::Value>, AppError> {
+        let msg = serde_json::to_vec(&req)?;
+        self.raw_request(elements, &msg, false).await
+    }
+}
+
+fn generate_topic(prefix: &str, elements: &[&str]) -> String {
+    format!("wadm.api.{}.model.{}", prefix, elements.join("."))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::sync::Mutex;
+
+    /// Replies to every request with a canned envelope, recording the requests it receives.
+    /// The first `failures` requests fail with the given error instead
+    struct MockTransport {
+        reply: serde_json::Value,
+        requests: Mutex<Vec<(String, Vec<u8>)>>,
+        failures: Mutex<Vec<AppError>>,
+    }
+
+    impl MockTransport {
+        fn new(reply: serde_json::Value) -> Self {
+            MockTransport {
+                reply,
+                requests: Mutex::new(Vec::new()),
+                failures: Mutex::new(Vec::new()),
+            }
+        }
+
+        fn failing(reply: serde_json::Value, failures: Vec<AppError>) -> Self {
+            MockTransport {
+                failures: Mutex::new(failures),
+                ..MockTransport::new(reply)
+            }
+        }
+    }
+
+    #[async_trait]
+    impl WadmTransport for MockTransport {
+        async fn request(&self, subject: String, payload: Vec<u8>) -> Result<Vec<u8>, AppError> {
+            self.requests.lock().unwrap().push((subject, payload));
+            let mut failures = self.failures.lock().unwrap();
+            if !failures.is_empty() {
+                return Err(failures.remove(0));
+            }
+            Ok(serde_json::to_vec(&self.reply)?)
+        }
+    }
+
+    #[tokio::test]
+    async fn deploy_sends_version_to_model_topic() {
+        let client = WadmClient::with_transport(
+            MockTransport::new(json!({
+                "result": "success",
+                "data": { "acknowledged": true }
+            })),
+            Some("prod".to_string()),
+        );
+
+        assert!(client.deploy("echo", "v0.0.1").await.unwrap().acknowledged);
+
+        let requests = client.transport.requests.lock().unwrap();
+        assert_eq!(requests[0].0, "wadm.api.prod.model.deploy.echo");
+        assert_eq!(
+            serde_json::from_slice::<serde_json::Value>(&requests[0].1).unwrap(),
+            json!({ "version": "v0.0.1" })
+        );
+    }
+
+    #[tokio::test]
+    async fn deploy_returns_why_it_was_not_acknowledged() {
+        let client = WadmClient::with_transport(
+            MockTransport::new(json!({
+                "result": "success",
+                "data": { "acknowledged": false, "message": "no hosts with enough capacity" }
+            })),
+            None,
+        );
+        assert_eq!(
+            client.deploy("echo", "v0.0.1").await.unwrap(),
+            DeployReply {
+                acknowledged: false,
+                message: Some("no hosts with enough capacity".to_string()),
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn list_decodes_models() {
+        let client = WadmClient::with_transport(
+            MockTransport::new(json!({
+                "result": "success",
+                "data": [{
+                    "name": "echo",
+                    "version": "v0.0.1",
+                    "description": "echo example",
+                    "deployment_status": "deployed"
+                }]
+            })),
+            None,
+        );
+
+        let models = client.list().await.unwrap();
+        assert_eq!(models[0].name, "echo");
+        assert_eq!(
+            client.transport.requests.lock().unwrap()[0].0,
+            "wadm.api.default.model.list"
+        );
+    }
+
+    #[tokio::test]
+    async fn rejected_requests_carry_wadm_message() {
+        let client = WadmClient::with_transport(
+            MockTransport::new(json!({
+                "result": "error",
+                "message": "model echo v0.0.1 not found"
+            })),
+            None,
+        );
+
+        match client.get("echo", "v0.0.1").await {
+            Err(AppError::Rejected(message)) => assert_eq!(message, "model echo v0.0.1 not found"),
+            other => panic!("expected a rejection, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn transient_failures_are_retried() {
+        let reply = json!({ "result": "success", "data": [] });
+        let client = WadmClient::with_transport(
+            MockTransport::failing(
+                reply.clone(),
+                vec![
+                    AppError::NoResponders("wadm.api.default.model.list".to_string()),
+                    AppError::Timeout,
+                ],
+            ),
+            None,
+        )
+        .with_retries(2, Duration::from_millis(1));
+        assert!(client.list().await.unwrap().is_empty());
+        assert_eq!(client.transport.requests.lock().unwrap().len(), 3);
+
+        let client = WadmClient::with_transport(
+            MockTransport::failing(reply, vec![AppError::Timeout, AppError::Timeout]),
+            None,
+        )
+        .with_retries(1, Duration::from_millis(1));
+        assert!(matches!(client.list().await, Err(AppError::Timeout)));
+        assert_eq!(client.transport.requests.lock().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn mutations_only_retry_undelivered_requests() {
+        let reply = json!({ "result": "success", "data": { "acknowledged": true } });
+        let client = WadmClient::with_transport(
+            MockTransport::failing(
+                reply.clone(),
+                vec![AppError::NoResponders(
+                    "wadm.api.default.model.deploy.echo".to_string(),
+                )],
+            ),
+            None,
+        )
+        .with_retries(2, Duration::from_millis(1));
+        assert!(client.deploy("echo", "v0.0.1").await.unwrap().acknowledged);
+        assert_eq!(client.transport.requests.lock().unwrap().len(), 2);
+
+        for failure in [AppError::Timeout, AppError::Request("reset".to_string())] {
+            let client = WadmClient::with_transport(
+                MockTransport::failing(reply.clone(), vec![failure]),
+                None,
+            )
+            .with_retries(2, Duration::from_millis(1));
+            assert!(client.deploy("echo", "v0.0.1").await.is_err());
+            assert_eq!(client.transport.requests.lock().unwrap().len(), 1);
+        }
+    }
+
+    #[tokio::test]
+    async fn rejections_are_not_retried() {
+        let client = WadmClient::with_transport(
+            MockTransport::new(json!({ "result": "error", "message": "no such model" })),
+            None,
+        )
+        .with_retries(3, Duration::from_millis(1));
+        assert!(matches!(
+            client.deploy("echo", "v0.0.1").await,
+            Err(AppError::Rejected(_))
+        ));
+        assert_eq!(client.transport.requests.lock().unwrap().len(), 1);
+    }
+}
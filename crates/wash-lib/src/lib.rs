@@ -9,6 +9,9 @@ pub mod start;
 #[cfg(feature = "parser")]
 pub mod parser;
 
+#[cfg(feature = "app")]
+pub mod app;
+
 #[cfg(feature = "cli")]
 pub mod build;
 #[cfg(feature = "cli")]
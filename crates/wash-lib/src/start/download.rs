@@ -0,0 +1,430 @@
+use std::future::Future;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+use reqwest::{header::RANGE, StatusCode};
+use sha2::{Digest, Sha256};
+use tokio::fs::{create_dir_all, metadata, remove_dir_all, remove_file, rename, File, OpenOptions};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio_stream::StreamExt;
+
+/// Directory, within the download directory, holding downloads that haven't been installed yet, so
+/// that an interrupted download can be resumed
+pub const PARTIAL_DOWNLOADS_DIR: &str = ".partial";
+
+/// Progress of a download, reported each time a chunk of it has been written to disk
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DownloadProgress {
+    /// Bytes downloaded so far, including those of an earlier attempt that is being resumed
+    pub downloaded: u64,
+    /// Size of the download, if the server reported it
+    pub total: Option<u64>,
+}
+
+/// What a download is verified against before it's installed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Checksum<'a> {
+    /// The URL of a file of SHA-256 checksums (as written by `sha256sum`) published with the
+    /// release. The download fails if the file is missing or doesn't list the asset
+    Published(&'a str),
+    /// The SHA-256 checksum of the asset as a hex string, known ahead of time
+    Pinned(&'a str),
+    /// Nothing, the download is installed without verification
+    Unverified,
+}
+
+/// Downloads a release asset to `path`, streaming it to disk and reporting progress as it goes. If
+/// `path` holds part of the asset from an interrupted attempt, the download resumes from there.
+/// The download is then verified against `checksum`, and a mismatch is an error
+pub(crate) async fn download_verified<F>(
+    url: &str,
+    checksum: Checksum<'_>,
+    path: &Path,
+    progress: F,
+) -> Result<()>
+where
+    F: Fn(DownloadProgress),
+{
+    let asset = asset_name(url);
+    let expected = match checksum {
+        Checksum::Published(checksums_url) => Some(published_checksum(checksums_url, asset).await?),
+        Checksum::Pinned(checksum) => Some(checksum.to_string()),
+        Checksum::Unverified => None,
+    };
+    if let Some(parent) = path.parent() {
+        create_dir_all(parent).await?;
+    }
+
+    let resumed = metadata(path).await.map(|m| m.len()).unwrap_or(0);
+    let mut request = reqwest::Client::new().get(url);
+    if resumed > 0 {
+        request = request.header(RANGE, format!("bytes={}-", resumed));
+    }
+    let resp = request
+        .send()
+        .await
+        .map_err(|e| anyhow!("Failed to request {}: {}", url, e))?;
+    if resp.status() == StatusCode::RANGE_NOT_SATISFIABLE && resumed > 0 {
+        // The earlier attempt already downloaded all of it
+        progress(DownloadProgress {
+            downloaded: resumed,
+            total: Some(resumed),
+        });
+    } else {
+        if !resp.status().is_success() {
+            return Err(anyhow!("Failed to download {}: {}", url, resp.status()));
+        }
+        // Servers that don't support ranges send all of it again
+        let append = resp.status() == StatusCode::PARTIAL_CONTENT;
+        let mut downloaded = if append { resumed } else { 0 };
+        let total = resp.content_length().map(|len| len + downloaded);
+        let mut file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .append(append)
+            .truncate(!append)
+            .open(path)
+            .await?;
+        progress(DownloadProgress { downloaded, total });
+        let mut stream = resp.bytes_stream();
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk.map_err(|e| {
+                anyhow!(
+                    "Download of {} was interrupted, run the command again to resume it: {}",
+                    url,
+                    e
+                )
+            })?;
+            file.write_all(&chunk).await?;
+            downloaded += chunk.len() as u64;
+            progress(DownloadProgress { downloaded, total });
+        }
+        file.flush().await?;
+    }
+
+    match expected {
+        Some(expected) => {
+            if let Err(e) = verify_checksum(path, &expected, asset).await {
+                // Resuming a corrupted download would only corrupt it further
+                let _ = remove_file(path).await;
+                return Err(e);
+            }
+        }
+        None => log::warn!(
+            "No checksum is known for {}, it will be installed without verification",
+            asset
+        ),
+    }
+    Ok(())
+}
+
+/// Fails unless the SHA-256 checksum of the file at `path` is `expected`, given as a hex string
+pub(crate) async fn verify_checksum(path: &Path, expected: &str, name: &str) -> Result<()> {
+    let mut file = File::open(path).await?;
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0; 64 * 1024];
+    loop {
+        let read = file.read(&mut buf).await?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buf[..read]);
+    }
+    let actual = format!("{:x}", hasher.finalize());
+    if actual.eq_ignore_ascii_case(expected.trim()) {
+        Ok(())
+    } else {
+        Err(anyhow!(
+            "Checksum mismatch for {}: expected SHA-256 {} but got {}. The download may be truncated or corrupted, nothing was installed",
+            name,
+            expected.trim(),
+            actual
+        ))
+    }
+}
+
+/// Installs to the `target` directory by letting `extract` unpack into a temporary directory next
+/// to it, which is then renamed into place. Nothing is left behind if extracting fails, and an
+/// existing `target` (e.g. from an interrupted install) is replaced
+pub(crate) async fn install_atomically<F, Fut>(target: &Path, extract: F) -> Result<()>
+where
+    F: FnOnce(PathBuf) -> Fut,
+    Fut: Future<Output = Result<()>>,
+{
+    let parent = target
+        .parent()
+        .ok_or_else(|| anyhow!("{} has no parent directory", target.display()))?;
+    create_dir_all(parent).await?;
+    let temp_dir = tempfile::Builder::new()
+        .prefix(".download-")
+        .tempdir_in(parent)?;
+
+    extract(temp_dir.path().to_path_buf()).await?;
+
+    if metadata(target).await.is_ok() {
+        remove_dir_all(target).await?;
+    }
+    rename(temp_dir.path(), target).await?;
+    // The temporary directory now lives at `target`, so it mustn't be cleaned up
+    let _ = temp_dir.into_path();
+    Ok(())
+}
+
+/// Looks up the checksum of `asset` in a published checksums file. A release (or a mirror of it)
+/// without one, or one that doesn't list the asset, is an error rather than a reason to skip
+/// verification
+async fn published_checksum(checksums_url: &str, asset: &str) -> Result<String> {
+    let resp = reqwest::get(checksums_url)
+        .await
+        .map_err(|e| anyhow!("Failed to request checksums {}: {}", checksums_url, e))?;
+    if resp.status() == StatusCode::NOT_FOUND {
+        return Err(anyhow!(
+            "No checksums found at {}, a mirror must serve the checksums published with each release next to its assets",
+            checksums_url
+        ));
+    }
+    if !resp.status().is_success() {
+        return Err(anyhow!(
+            "Failed to download checksums {}: {}",
+            checksums_url,
+            resp.status()
+        ));
+    }
+    let contents = resp.text().await?;
+    parse_checksums(&contents, asset)
+        .ok_or_else(|| anyhow!("{} doesn't list a checksum for {}", checksums_url, asset))
+}
+
+/// Finds the checksum of a file in the output of `sha256sum`, i.e. lines of `<checksum>  <file>`
+fn parse_checksums(contents: &str, file_name: &str) -> Option<String> {
+    contents.lines().find_map(|line| {
+        let mut parts = line.split_whitespace();
+        let checksum = parts.next()?;
+        // sha256sum marks files it read in binary mode with a leading `*`
+        let name = parts.next()?.trim_start_matches('*');
+        (name == file_name).then(|| checksum.to_lowercase())
+    })
+}
+
+fn asset_name(url: &str) -> &str {
+    url.rsplit('/').next().unwrap_or(url)
+}
+
+/// Builds a gzipped tarball holding `files`, to stand in for a release in tests
+#[cfg(test)]
+pub(crate) async fn test_tarball(files: &[(&str, &[u8])]) -> Vec<u8> {
+    use async_compression::tokio::write::GzipEncoder;
+    use tokio::io::AsyncWriteExt;
+
+    let mut builder = tokio_tar::Builder::new(GzipEncoder::new(Vec::new()));
+    for (path, contents) in files {
+        let mut header = tokio_tar::Header::new_gnu();
+        header.set_size(contents.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        builder
+            .append_data(&mut header, path, *contents)
+            .await
+            .unwrap();
+    }
+    let mut encoder = builder.into_inner().await.unwrap();
+    encoder.shutdown().await.unwrap();
+    encoder.into_inner()
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        download_verified, install_atomically, parse_checksums, verify_checksum, Checksum,
+        DownloadProgress,
+    };
+    use anyhow::anyhow;
+    use std::cell::RefCell;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    // SHA-256 of "hello world"
+    const HELLO_SHA256: &str = "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9";
+
+    #[test]
+    fn can_parse_checksums() {
+        let checksums = format!(
+            "{}  nats-server-v2.9.14-linux-amd64.tar.gz\n0000  nats-server-v2.9.14-darwin-arm64.tar.gz\n{} *wasmcloud.tar.gz\n",
+            HELLO_SHA256,
+            HELLO_SHA256.to_uppercase()
+        );
+        assert_eq!(
+            parse_checksums(&checksums, "nats-server-v2.9.14-linux-amd64.tar.gz").as_deref(),
+            Some(HELLO_SHA256)
+        );
+        assert_eq!(
+            parse_checksums(&checksums, "wasmcloud.tar.gz").as_deref(),
+            Some(HELLO_SHA256)
+        );
+        assert_eq!(parse_checksums(&checksums, "nats-server.zip"), None);
+    }
+
+    #[tokio::test]
+    async fn can_verify_checksums() {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        std::fs::write(file.path(), "hello world").unwrap();
+        assert!(verify_checksum(file.path(), HELLO_SHA256, "hello")
+            .await
+            .is_ok());
+
+        std::fs::write(file.path(), "hello wor").unwrap();
+        let err = verify_checksum(file.path(), HELLO_SHA256, "hello")
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("Checksum mismatch for hello"));
+    }
+
+    #[tokio::test]
+    async fn can_install_atomically() {
+        let dir = tempfile::tempdir().unwrap();
+        let target = dir.path().join("v1.0.0");
+
+        // A failed extraction leaves nothing behind
+        let res = install_atomically(&target, |temp_dir| async move {
+            std::fs::write(temp_dir.join("partial"), "partial")?;
+            Err(anyhow!("truncated archive"))
+        })
+        .await;
+        assert!(res.is_err());
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+
+        // A half installed version is replaced
+        std::fs::create_dir_all(&target).unwrap();
+        std::fs::write(target.join("partial"), "partial").unwrap();
+        install_atomically(&target, |temp_dir| async move {
+            std::fs::write(temp_dir.join("binary"), "binary")?;
+            Ok(())
+        })
+        .await
+        .unwrap();
+        assert!(target.join("binary").is_file());
+        assert!(!target.join("partial").exists());
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    /// Serves `contents` over HTTP on a local port, honouring `Range` requests, and returns its URL
+    async fn serve(contents: &'static [u8]) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            loop {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut buf = vec![0; 4096];
+                let read = socket.read(&mut buf).await.unwrap();
+                let request = String::from_utf8_lossy(&buf[..read]).to_lowercase();
+                let start = request
+                    .lines()
+                    .find_map(|line| line.strip_prefix("range: bytes="))
+                    .and_then(|range| range.trim().trim_end_matches('-').parse::<usize>().ok());
+                let response = match start {
+                    Some(start) if start >= contents.len() => {
+                        "HTTP/1.1 416 Range Not Satisfiable\r\ncontent-length: 0\r\nconnection: close\r\n\r\n"
+                            .as_bytes()
+                            .to_vec()
+                    }
+                    Some(start) => [
+                        format!(
+                            "HTTP/1.1 206 Partial Content\r\ncontent-length: {}\r\ncontent-range: bytes {}-{}/{}\r\nconnection: close\r\n\r\n",
+                            contents.len() - start,
+                            start,
+                            contents.len() - 1,
+                            contents.len()
+                        )
+                        .as_bytes(),
+                        &contents[start..],
+                    ]
+                    .concat(),
+                    None => [
+                        format!(
+                            "HTTP/1.1 200 OK\r\ncontent-length: {}\r\nconnection: close\r\n\r\n",
+                            contents.len()
+                        )
+                        .as_bytes(),
+                        contents,
+                    ]
+                    .concat(),
+                };
+                socket.write_all(&response).await.unwrap();
+            }
+        });
+        format!("http://{}/v1.0.0/hello.tar.gz", addr)
+    }
+
+    #[tokio::test]
+    async fn can_resume_partial_downloads() {
+        let url = serve(b"hello world").await;
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(".partial").join("hello.tar.gz");
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, "hello ").unwrap();
+
+        let progress = RefCell::new(Vec::new());
+        download_verified(&url, Checksum::Unverified, &path, |p| {
+            progress.borrow_mut().push(p)
+        })
+        .await
+        .unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), b"hello world");
+        let progress = progress.into_inner();
+        assert_eq!(
+            progress.first(),
+            Some(&DownloadProgress {
+                downloaded: 6,
+                total: Some(11)
+            })
+        );
+        assert_eq!(
+            progress.last(),
+            Some(&DownloadProgress {
+                downloaded: 11,
+                total: Some(11)
+            })
+        );
+
+        // A download that had already finished is only verified
+        download_verified(&url, Checksum::Unverified, &path, |_| {})
+            .await
+            .unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), b"hello world");
+
+        // As is a fresh one
+        std::fs::remove_file(&path).unwrap();
+        download_verified(&url, Checksum::Unverified, &path, |_| {})
+            .await
+            .unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), b"hello world");
+    }
+
+    #[tokio::test]
+    async fn can_verify_downloads() {
+        let url = serve(b"hello world").await;
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("hello.tar.gz");
+
+        download_verified(&url, Checksum::Pinned(HELLO_SHA256), &path, |_| {})
+            .await
+            .unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), b"hello world");
+
+        // A mismatch leaves nothing behind to be resumed
+        std::fs::remove_file(&path).unwrap();
+        let wrong = "0".repeat(64);
+        let mismatch = download_verified(&url, Checksum::Pinned(&wrong), &path, |_| {});
+        assert!(mismatch.await.is_err());
+        assert!(!path.exists());
+
+        // As does a checksums file that doesn't list the asset, which is all the test server
+        // serves at any other path
+        let checksums_url = url.replace("hello.tar.gz", "SHA256SUMS");
+        let unlisted = download_verified(&url, Checksum::Published(&checksums_url), &path, |_| {});
+        assert!(unlisted
+            .await
+            .unwrap_err()
+            .to_string()
+            .contains("doesn't list a checksum for hello.tar.gz"));
+    }
+}
@@ -0,0 +1,39 @@
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+
+const GITHUB_API_URL: &str = "https://api.github.com";
+
+#[derive(Deserialize)]
+struct Release {
+    tag_name: String,
+    #[serde(default)]
+    draft: bool,
+    #[serde(default)]
+    prerelease: bool,
+}
+
+/// Lists the tags of the most recent published, non-prerelease releases of a GitHub repository
+/// (e.g. `nats-io/nats-server`), newest first
+pub(crate) async fn github_release_tags(repo: &str) -> Result<Vec<String>> {
+    let url = format!("{}/repos/{}/releases", GITHUB_API_URL, repo);
+    let resp = reqwest::Client::new()
+        .get(&url)
+        // The GitHub API rejects requests without a user agent
+        .header(reqwest::header::USER_AGENT, "wash")
+        .send()
+        .await
+        .map_err(|e| anyhow!("Failed to request releases of {}: {}", repo, e))?;
+    if !resp.status().is_success() {
+        return Err(anyhow!(
+            "Failed to request releases of {}: {}",
+            repo,
+            resp.status()
+        ));
+    }
+    let releases: Vec<Release> = resp.json().await?;
+    Ok(releases
+        .into_iter()
+        .filter(|r| !r.draft && !r.prerelease)
+        .map(|r| r.tag_name)
+        .collect())
+}
@@ -52,6 +52,11 @@
 //!     Ok(())
 //! }
 //! ```
+mod download;
+use download::{download_verified, install_atomically, Checksum};
+pub use download::{DownloadProgress, PARTIAL_DOWNLOADS_DIR};
+mod github;
+use github::github_release_tags;
 mod nats;
 pub use nats::*;
 mod wasmcloud;
@@ -1,16 +1,30 @@
 use anyhow::{anyhow, Result};
 use async_compression::tokio::bufread::GzipDecoder;
+use std::ffi::OsStr;
 #[cfg(target_family = "unix")]
 use std::os::unix::prelude::PermissionsExt;
 use std::path::{Path, PathBuf};
 use std::process::Stdio;
-use std::{ffi::OsStr, io::Cursor};
-use tokio::fs::{create_dir_all, metadata, write, File};
+use tokio::fs::{metadata, read_dir, remove_dir_all, remove_file, write, File};
+use tokio::io::BufReader;
 use tokio::process::{Child, Command};
 use tokio_stream::StreamExt;
 use tokio_tar::Archive;
 
-const NATS_GITHUB_RELEASE_URL: &str = "https://github.com/nats-io/nats-server/releases/download";
+use super::{
+    download_verified, github_release_tags, install_atomically, Checksum, DownloadProgress,
+    PARTIAL_DOWNLOADS_DIR,
+};
+
+/// Where NATS releases are downloaded from, unless a mirror is used
+pub const NATS_GITHUB_RELEASE_URL: &str =
+    "https://github.com/nats-io/nats-server/releases/download";
+const NATS_GITHUB_REPO: &str = "nats-io/nats-server";
+/// File published with each NATS release listing the SHA-256 checksums of its assets
+const NATS_CHECKSUMS_FILE: &str = "SHA256SUMS";
+/// Directory, within the download directory, that holds a directory for each installed version of
+/// the NATS server
+pub const NATS_SERVER_DIR: &str = "nats";
 pub const NATS_SERVER_CONF: &str = "nats.conf";
 pub const NATS_SERVER_PID: &str = "nats.pid";
 #[cfg(target_family = "unix")]
@@ -33,7 +47,7 @@ pub const NATS_SERVER_BINARY: &str = "nats-server.exe";
 /// use wash_lib::start::ensure_nats_server;
 /// let res = ensure_nats_server("v2.8.4", "/tmp/").await;
 /// assert!(res.is_ok());
-/// assert!(res.unwrap().to_string_lossy() == "/tmp/nats-server");
+/// assert!(res.unwrap().to_string_lossy() == "/tmp/nats/v2.8.4/nats-server");
 /// # }
 /// ```
 pub async fn ensure_nats_server<P>(version: &str, dir: P) -> Result<PathBuf>
@@ -44,9 +58,12 @@ where
         .await
 }
 
-/// Ensures the `nats-server` binary is installed, returning the path to the executable early if it exists or
-/// downloading the specified GitHub release version of nats-server from <https://github.com/nats-io/nats-server/releases/>
+/// Ensures the `nats-server` binary is installed, returning the path to the executable early if the requested
+/// version exists or downloading the specified GitHub release version of nats-server from <https://github.com/nats-io/nats-server/releases/>
 /// and unpacking the binary for a specified OS/ARCH pair to a directory. Returns the path to the NATS executable.
+///
+/// Each version is installed to its own directory (e.g. `/tmp/nats/v2.8.4`). A `nats-server` binary at the top level
+/// of the directory, as installed by earlier versions of this function, is used if it is the requested version.
 /// # Arguments
 ///
 /// * `os` - Specifies the operating system of the binary to download, e.g. `linux`
@@ -63,7 +80,7 @@ where
 /// let arch = std::env::consts::ARCH;
 /// let res = ensure_nats_server_for_os_arch_pair(os, arch, "v2.8.4", "/tmp/").await;
 /// assert!(res.is_ok());
-/// assert!(res.unwrap().to_string_lossy() == "/tmp/nats-server");
+/// assert!(res.unwrap().to_string_lossy() == "/tmp/nats/v2.8.4/nats-server");
 /// # }
 /// ```
 pub async fn ensure_nats_server_for_os_arch_pair<P>(
@@ -75,8 +92,7 @@ pub async fn ensure_nats_server_for_os_arch_pair<P>(
 where
     P: AsRef<Path>,
 {
-    let nats_bin_path = dir.as_ref().join(NATS_SERVER_BINARY);
-    if let Ok(_md) = metadata(&nats_bin_path).await {
+    if let Some(nats_bin_path) = find_nats_server_binary(&dir, version).await {
         // NATS already exists, return early
         return Ok(nats_bin_path);
     }
@@ -99,7 +115,7 @@ where
 /// use wash_lib::start::download_nats_server;
 /// let res = download_nats_server("v2.8.4", "/tmp/").await;
 /// assert!(res.is_ok());
-/// assert!(res.unwrap().to_string_lossy() == "/tmp/nats-server");
+/// assert!(res.unwrap().to_string_lossy() == "/tmp/nats/v2.8.4/nats-server");
 /// # }
 /// ```
 pub async fn download_nats_server<P>(version: &str, dir: P) -> Result<PathBuf>
@@ -116,7 +132,8 @@ where
 }
 
 /// Downloads the specified GitHub release version of nats-server from <https://github.com/nats-io/nats-server/releases/>
-/// and unpacking the binary for a specified OS/ARCH pair to a directory. Returns the path to the NATS executable.
+/// and unpacking the binary for a specified OS/ARCH pair to the version's directory (e.g. `/tmp/nats/v2.8.4`).
+/// Returns the path to the NATS executable.
 /// # Arguments
 ///
 /// * `os` - Specifies the operating system of the binary to download, e.g. `linux`
@@ -133,7 +150,7 @@ where
 /// let arch = std::env::consts::ARCH;
 /// let res = download_nats_server_for_os_arch_pair(os, arch, "v2.8.4", "/tmp/").await;
 /// assert!(res.is_ok());
-/// assert!(res.unwrap().to_string_lossy() == "/tmp/nats-server");
+/// assert!(res.unwrap().to_string_lossy() == "/tmp/nats/v2.8.4/nats-server");
 /// # }
 /// ```
 pub async fn download_nats_server_for_os_arch_pair<P>(
@@ -145,15 +162,138 @@ pub async fn download_nats_server_for_os_arch_pair<P>(
 where
     P: AsRef<Path>,
 {
-    let nats_bin_path = dir.as_ref().join(NATS_SERVER_BINARY);
-    // Download NATS tarball
-    let url = nats_url(os, arch, version);
-    let body = match reqwest::get(url).await {
-        Ok(resp) => resp.bytes().await?,
-        Err(e) => return Err(anyhow!("Failed to request NATS release: {:?}", e)),
-    };
-    let cursor = Cursor::new(body);
-    let mut nats_server = Archive::new(Box::new(GzipDecoder::new(cursor)));
+    download_nats_server_from_mirror(NATS_GITHUB_RELEASE_URL, os, arch, version, dir, |_| {}).await
+}
+
+/// Ensures the `nats-server` application is installed, downloading it from a mirror of the NATS
+/// GitHub releases for the OS/ARCH pair of the current host machine if it isn't. Returns the path
+/// to the NATS executable.
+///
+/// # Arguments
+///
+/// * `mirror` - Base URL of the mirror, which lays out releases like
+///   <https://github.com/nats-io/nats-server/releases/download>, i.e. `<mirror>/<version>/<tarball>`
+/// * `version` - Specifies the version of the binary to download in the form of `vX.Y.Z`
+/// * `dir` - Where to download the `nats-server` binary to
+/// * `progress` - Called with the progress of the download each time a chunk of it arrives
+pub async fn ensure_nats_server_from_mirror<P, F>(
+    mirror: &str,
+    version: &str,
+    dir: P,
+    progress: F,
+) -> Result<PathBuf>
+where
+    P: AsRef<Path>,
+    F: Fn(DownloadProgress),
+{
+    if let Some(nats_bin_path) = find_nats_server_binary(&dir, version).await {
+        return Ok(nats_bin_path);
+    }
+    download_nats_server_from_mirror(
+        mirror,
+        std::env::consts::OS,
+        std::env::consts::ARCH,
+        version,
+        dir,
+        progress,
+    )
+    .await
+}
+
+/// Downloads the specified version of nats-server for an OS/ARCH pair from a mirror of the NATS
+/// GitHub releases, verifying it against the `SHA256SUMS` file of the release, which the mirror
+/// must serve too, and unpacks the binary to the version's directory (e.g. `/tmp/nats/v2.8.4`). Returns the
+/// path to the NATS executable. The tarball is kept in [PARTIAL_DOWNLOADS_DIR] until it's
+/// installed, so that an interrupted download resumes where it left off.
+///
+/// # Arguments
+///
+/// * `mirror` - Base URL of the mirror, e.g. [NATS_GITHUB_RELEASE_URL]. Tarballs are downloaded
+///   from `<mirror>/<version>/<tarball>`
+/// * `os` - Specifies the operating system of the binary to download, e.g. `linux`
+/// * `arch` - Specifies the architecture of the binary to download, e.g. `amd64`
+/// * `version` - Specifies the version of the binary to download in the form of `vX.Y.Z`
+/// * `dir` - Where to download the `nats-server` binary to
+/// * `progress` - Called with the progress of the download each time a chunk of it arrives
+pub async fn download_nats_server_from_mirror<P, F>(
+    mirror: &str,
+    os: &str,
+    arch: &str,
+    version: &str,
+    dir: P,
+    progress: F,
+) -> Result<PathBuf>
+where
+    P: AsRef<Path>,
+    F: Fn(DownloadProgress),
+{
+    let mirror = mirror.trim_end_matches('/');
+    // Download NATS tarball, verifying it against the checksums published with the release
+    let url = nats_url(mirror, os, arch, version);
+    let checksums_url = format!("{}/{}/{}", mirror, version, NATS_CHECKSUMS_FILE);
+    let tarball = dir
+        .as_ref()
+        .join(PARTIAL_DOWNLOADS_DIR)
+        .join(format!("nats-server-{}-{}-{}.tar.gz", version, os, arch));
+    download_verified(
+        &url,
+        Checksum::Published(&checksums_url),
+        &tarball,
+        progress,
+    )
+    .await
+    .map_err(|e| anyhow!("Failed to download NATS server {}: {}", version, e))?;
+    let res = install_nats_server_tarball(&tarball, version, dir).await;
+    // Once installed the tarball is of no further use, and one that can't be installed shouldn't
+    // be resumed either
+    let _ = remove_file(&tarball).await;
+    res
+}
+
+/// Installs a version of nats-server from a release tarball on disk, e.g. one downloaded from
+/// <https://github.com/nats-io/nats-server/releases/> ahead of time, for machines without internet
+/// access. Returns the path to the NATS executable.
+///
+/// # Arguments
+///
+/// * `tarball` - Path to the `.tar.gz` release of NATS for the current OS/ARCH pair
+/// * `version` - The version of NATS in the tarball in the form of `vX.Y.Z`, which it's installed as
+/// * `dir` - Where to unpack the `nats-server` binary to
+pub async fn install_nats_server_from_tarball<T, P>(
+    tarball: T,
+    version: &str,
+    dir: P,
+) -> Result<PathBuf>
+where
+    T: AsRef<Path>,
+    P: AsRef<Path>,
+{
+    if let Err(e) = metadata(tarball.as_ref()).await {
+        return Err(anyhow!(
+            "Failed to read NATS tarball {}: {}",
+            tarball.as_ref().display(),
+            e
+        ));
+    }
+    install_nats_server_tarball(tarball.as_ref(), version, dir).await
+}
+
+async fn install_nats_server_tarball<P>(tarball: &Path, version: &str, dir: P) -> Result<PathBuf>
+where
+    P: AsRef<Path>,
+{
+    let version_dir = nats_server_dir(&dir, version);
+    install_atomically(&version_dir, |temp_dir| {
+        extract_nats_server(tarball, temp_dir, version)
+    })
+    .await?;
+    Ok(version_dir.join(NATS_SERVER_BINARY))
+}
+
+/// Extracts the `nats-server` binary from a NATS release tarball into a directory
+async fn extract_nats_server(tarball: &Path, dir: PathBuf, version: &str) -> Result<()> {
+    let reader = BufReader::new(File::open(tarball).await?);
+    let mut nats_server = Archive::new(Box::new(GzipDecoder::new(reader)));
 
     // Look for nats-server binary and only extract that
     let mut entries = nats_server.entries()?;
@@ -167,9 +307,7 @@ where
         if let Ok(tar_path) = entry.path() {
             match tar_path.file_name() {
                 Some(name) if name == OsStr::new(NATS_SERVER_BINARY) => {
-                    // Ensure target directory exists
-                    create_dir_all(&dir).await?;
-                    let mut nats_server = File::create(&nats_bin_path).await?;
+                    let mut nats_server = File::create(dir.join(NATS_SERVER_BINARY)).await?;
                     // Make nats-server executable
                     #[cfg(target_family = "unix")]
                     {
@@ -180,7 +318,7 @@ where
                     }
 
                     tokio::io::copy(&mut entry, &mut nats_server).await?;
-                    return Ok(nats_bin_path);
+                    return Ok(());
                 }
                 // Ignore LICENSE and README in the NATS tarball
                 _ => (),
@@ -202,6 +340,9 @@ pub struct NatsConfig {
     pub js_domain: Option<String>,
     pub remote_url: Option<String>,
     pub credentials: Option<PathBuf>,
+    /// Directory JetStream stores its data in, NATS picks a temporary directory when `None`.
+    /// Servers running side by side need one each
+    pub store_dir: Option<PathBuf>,
 }
 
 /// Returns a standalone NATS config with the following values:
@@ -210,6 +351,7 @@ pub struct NatsConfig {
 /// * `js_domain`: `Some("core")`
 /// * `remote_url`: `None`
 /// * `credentials`: `None`
+/// * `store_dir`: `None`
 impl Default for NatsConfig {
     fn default() -> Self {
         NatsConfig {
@@ -218,6 +360,7 @@ impl Default for NatsConfig {
             js_domain: Some("core".to_string()),
             remote_url: None,
             credentials: None,
+            store_dir: None,
         }
     }
 }
@@ -247,6 +390,7 @@ impl NatsConfig {
             js_domain,
             remote_url: Some(remote_url),
             credentials: Some(credentials),
+            store_dir: None,
         }
     }
     /// Instantiates config for a standalone NATS server. Unless you're looking to extend
@@ -268,6 +412,12 @@ impl NatsConfig {
         }
     }
 
+    /// Stores JetStream data in `store_dir` rather than in a temporary directory picked by NATS
+    pub fn with_store_dir(mut self, store_dir: impl Into<PathBuf>) -> Self {
+        self.store_dir = Some(store_dir.into());
+        self
+    }
+
     async fn write_to_path<P>(self, path: P) -> Result<()>
     where
         P: AsRef<Path>,
@@ -289,14 +439,19 @@ leafnodes {{
             ),
             _ => "".to_owned(),
         };
+        let store_dir = match self.store_dir {
+            Some(dir) => format!("\n    store_dir: {:?}", dir.to_string_lossy()),
+            None => "".to_owned(),
+        };
         let config = format!(
             r#"
 jetstream {{
-    domain={}
+    domain={}{}
 }}
 {}
 "#,
             self.js_domain.unwrap_or_else(|| "core".to_string()),
+            store_dir,
             leafnode_section
         );
         write(path, config).await.map_err(anyhow::Error::from)
@@ -313,6 +468,36 @@ pub async fn start_nats_server<P, T>(bin_path: P, stderr: T, config: NatsConfig)
 where
     P: AsRef<Path>,
     T: Into<Stdio>,
+{
+    match bin_path.as_ref().parent() {
+        Some(parent_path) => {
+            start_nats_server_in_dir(bin_path.as_ref(), parent_path, stderr, config).await
+        }
+        None => Err(anyhow!(
+            "Could not write config to disk, couldn't find download directory"
+        )),
+    }
+}
+
+/// Helper function to execute a NATS server binary like [start_nats_server], but writing its
+/// `nats.conf` and pid file to `dir` instead of alongside the binary, so that several servers can
+/// run from the same binary
+/// # Arguments
+///
+/// * `bin_path` - Path to the nats-server binary to execute
+/// * `dir` - Directory to write the `nats.conf` and pid file of the server to
+/// * `stderr` - Specify where NATS stderr logs should be written to. If logs aren't important, use std::process::Stdio::null()
+/// * `config` - Configuration for the NATS server, see [NatsConfig] for options
+pub async fn start_nats_server_in_dir<P, D, T>(
+    bin_path: P,
+    dir: D,
+    stderr: T,
+    config: NatsConfig,
+) -> Result<Child>
+where
+    P: AsRef<Path>,
+    D: AsRef<Path>,
+    T: Into<Stdio>,
 {
     // If we can connect to the local port, NATS won't be able to listen on that port
     if tokio::net::TcpStream::connect(format!("{}:{}", config.host, config.port))
@@ -325,45 +510,132 @@ where
             config.port
         ));
     }
-    if let Some(parent_path) = bin_path.as_ref().parent() {
-        let config_path = parent_path.join(NATS_SERVER_CONF);
-        let host = config.host.to_owned();
-        let port = config.port;
-        config.write_to_path(&config_path).await?;
-        Command::new(bin_path.as_ref())
-            .stderr(stderr)
-            .stdin(Stdio::null())
-            .arg("-js")
-            .arg("--config")
-            .arg(config_path)
-            .arg("--addr")
-            .arg(host)
-            .arg("--port")
-            .arg(port.to_string())
-            .arg("--pid")
-            .arg(parent_path.join(NATS_SERVER_PID))
-            .spawn()
-            .map_err(anyhow::Error::from)
-    } else {
-        Err(anyhow!(
-            "Could not write config to disk, couldn't find download directory"
-        ))
-    }
+    let config_path = dir.as_ref().join(NATS_SERVER_CONF);
+    let host = config.host.to_owned();
+    let port = config.port;
+    config.write_to_path(&config_path).await?;
+    Command::new(bin_path.as_ref())
+        .stderr(stderr)
+        .stdin(Stdio::null())
+        .arg("-js")
+        .arg("--config")
+        .arg(config_path)
+        .arg("--addr")
+        .arg(host)
+        .arg("--port")
+        .arg(port.to_string())
+        .arg("--pid")
+        .arg(dir.as_ref().join(NATS_SERVER_PID))
+        .spawn()
+        .map_err(anyhow::Error::from)
 }
 
-/// Helper function to indicate if the NATS server binary is successfully
+/// Helper function to indicate if any version of the NATS server binary is successfully
 /// installed in a directory
 pub async fn is_nats_installed<P>(dir: P) -> bool
 where
     P: AsRef<Path>,
 {
-    metadata(dir.as_ref().join(NATS_SERVER_BINARY))
-        .await
-        .map_or(false, |m| m.is_file())
+    !installed_nats_servers(dir).await.is_empty()
+}
+
+/// Helper function to find the `nats-server` binary of a version in a directory, checking that the
+/// installed binary reports the requested version. Returns the path to the binary if it exists
+pub async fn find_nats_server_binary<P>(dir: P, version: &str) -> Option<PathBuf>
+where
+    P: AsRef<Path>,
+{
+    let candidates = [
+        nats_server_dir(&dir, version).join(NATS_SERVER_BINARY),
+        dir.as_ref().join(NATS_SERVER_BINARY),
+    ];
+    for bin_path in candidates {
+        if !matches!(metadata(&bin_path).await, Ok(m) if m.is_file()) {
+            continue;
+        }
+        match nats_server_version(&bin_path).await {
+            Ok(installed) if same_version(&installed, version) => return Some(bin_path),
+            _ => (),
+        }
+    }
+    None
+}
+
+/// Returns the version and path of every `nats-server` binary installed in a directory, sorted by
+/// path. Binaries that fail to report their version are skipped
+pub async fn installed_nats_servers<P>(dir: P) -> Vec<(String, PathBuf)>
+where
+    P: AsRef<Path>,
+{
+    let mut candidates = vec![dir.as_ref().join(NATS_SERVER_BINARY)];
+    if let Ok(mut entries) = read_dir(dir.as_ref().join(NATS_SERVER_DIR)).await {
+        while let Ok(Some(entry)) = entries.next_entry().await {
+            candidates.push(entry.path().join(NATS_SERVER_BINARY));
+        }
+    }
+    candidates.sort();
+
+    let mut installed = Vec::new();
+    for bin_path in candidates {
+        if !matches!(metadata(&bin_path).await, Ok(m) if m.is_file()) {
+            continue;
+        }
+        if let Ok(version) = nats_server_version(&bin_path).await {
+            installed.push((version, bin_path));
+        }
+    }
+    installed
+}
+
+/// Removes an installed `nats-server` binary, along with its version's directory if it has one
+pub async fn remove_nats_server<P>(dir: P, bin_path: &Path) -> Result<()>
+where
+    P: AsRef<Path>,
+{
+    match bin_path.parent() {
+        Some(parent) if parent.starts_with(dir.as_ref().join(NATS_SERVER_DIR)) => {
+            remove_dir_all(parent).await?
+        }
+        _ => remove_file(bin_path).await?,
+    }
+    Ok(())
+}
+
+/// Asks a `nats-server` binary for its version, e.g. `v2.9.14`
+pub async fn nats_server_version(bin_path: &Path) -> Result<String> {
+    let output = Command::new(bin_path)
+        .arg("--version")
+        .stdin(Stdio::null())
+        .output()
+        .await?;
+    // The output looks like `nats-server: v2.9.14`
+    String::from_utf8_lossy(&output.stdout)
+        .split_whitespace()
+        .last()
+        .map(|v| format!("v{}", v.trim_start_matches('v')))
+        .ok_or_else(|| anyhow!("{} did not report its version", bin_path.display()))
+}
+
+/// Lists the versions of the NATS server published on GitHub, newest first
+pub async fn available_nats_versions() -> Result<Vec<String>> {
+    github_release_tags(NATS_GITHUB_REPO).await
+}
+
+/// The directory a version of the NATS server is installed to
+fn nats_server_dir<P>(dir: P, version: &str) -> PathBuf
+where
+    P: AsRef<Path>,
+{
+    dir.as_ref().join(NATS_SERVER_DIR).join(version)
+}
+
+/// Compares versions regardless of a leading `v`
+fn same_version(a: &str, b: &str) -> bool {
+    a.trim_start_matches('v') == b.trim_start_matches('v')
 }
 
 /// Helper function to determine the NATS server release path given an os/arch and version
-fn nats_url(os: &str, arch: &str, version: &str) -> String {
+fn nats_url(mirror: &str, os: &str, arch: &str, version: &str) -> String {
     // Replace "macos" with "darwin" to match NATS release scheme
     let os = if os == "macos" { "darwin" } else { os };
     // Replace architecture to match NATS release naming scheme
@@ -374,14 +646,18 @@ fn nats_url(os: &str, arch: &str, version: &str) -> String {
     };
     format!(
         "{}/{}/nats-server-{}-{}-{}.tar.gz",
-        NATS_GITHUB_RELEASE_URL, version, version, os, arch
+        mirror, version, version, os, arch
     )
 }
 
 #[cfg(test)]
 mod test {
+    use super::nats_url;
+    use crate::start::download::test_tarball;
     use crate::start::{
-        ensure_nats_server, is_nats_installed, start_nats_server, NatsConfig, NATS_SERVER_BINARY,
+        ensure_nats_server, find_nats_server_binary, install_nats_server_from_tarball,
+        installed_nats_servers, is_nats_installed, remove_nats_server, start_nats_server,
+        NatsConfig, NATS_SERVER_BINARY, NATS_SERVER_DIR,
     };
     use anyhow::Result;
     use std::env::temp_dir;
@@ -392,6 +668,64 @@ mod test {
 
     const NATS_SERVER_VERSION: &str = "v2.8.4";
 
+    #[tokio::test]
+    async fn can_install_nats_from_tarball() -> Result<()> {
+        let install_dir = tempfile::tempdir()?;
+        let tarball = install_dir
+            .path()
+            .join("nats-server-v2.9.14-linux-amd64.tar.gz");
+        tokio::fs::write(
+            &tarball,
+            test_tarball(&[
+                ("nats-server-v2.9.14-linux-amd64/README.md", b"readme"),
+                (
+                    &format!("nats-server-v2.9.14-linux-amd64/{}", NATS_SERVER_BINARY),
+                    b"nats",
+                ),
+            ])
+            .await,
+        )
+        .await?;
+
+        let bin_path =
+            install_nats_server_from_tarball(&tarball, "v2.9.14", install_dir.path()).await?;
+        assert_eq!(
+            bin_path,
+            install_dir
+                .path()
+                .join(NATS_SERVER_DIR)
+                .join("v2.9.14")
+                .join(NATS_SERVER_BINARY)
+        );
+        assert_eq!(tokio::fs::read(&bin_path).await?, b"nats");
+        // Only the binary is extracted
+        assert!(!bin_path.with_file_name("README.md").exists());
+
+        // An archive without a NATS server doesn't leave a version behind
+        tokio::fs::write(&tarball, test_tarball(&[("README.md", b"readme")]).await).await?;
+        assert!(
+            install_nats_server_from_tarball(&tarball, "v2.9.15", install_dir.path())
+                .await
+                .is_err()
+        );
+        assert!(!install_dir
+            .path()
+            .join(NATS_SERVER_DIR)
+            .join("v2.9.15")
+            .exists());
+
+        assert_eq!(
+            nats_url(
+                "https://mirror.example.com/nats",
+                "macos",
+                "aarch64",
+                "v2.9.14"
+            ),
+            "https://mirror.example.com/nats/v2.9.14/nats-server-v2.9.14-darwin-arm64.tar.gz"
+        );
+        Ok(())
+    }
+
     #[tokio::test]
     async fn can_handle_missing_nats_version() -> Result<()> {
         let install_dir = temp_dir().join("can_handle_missing_nats_version");
@@ -413,15 +747,14 @@ mod test {
         create_dir_all(&install_dir).await?;
         assert!(!is_nats_installed(&install_dir).await);
 
-        let res = ensure_nats_server(NATS_SERVER_VERSION, &install_dir).await;
-        assert!(res.is_ok());
+        let nats_binary = ensure_nats_server(NATS_SERVER_VERSION, &install_dir).await?;
+        assert!(is_nats_installed(&install_dir).await);
 
         let log_path = install_dir.join("nats.log");
         let log_file = tokio::fs::File::create(&log_path).await?.into_std().await;
 
         let config = NatsConfig::new_standalone("127.0.0.1", 10000, None);
-        let child_res =
-            start_nats_server(&install_dir.join(NATS_SERVER_BINARY), log_file, config).await;
+        let child_res = start_nats_server(&nats_binary, log_file, config).await;
         assert!(child_res.is_ok());
 
         // Give NATS max 5 seconds to start up
@@ -453,23 +786,18 @@ mod test {
         create_dir_all(&install_dir).await?;
         assert!(!is_nats_installed(&install_dir).await);
 
-        let res = ensure_nats_server(NATS_SERVER_VERSION, &install_dir).await;
-        assert!(res.is_ok());
+        let nats_binary = ensure_nats_server(NATS_SERVER_VERSION, &install_dir).await?;
 
         let config = NatsConfig::new_standalone("127.0.0.1", 10003, Some("extender".to_string()));
-        let nats_one = start_nats_server(
-            &install_dir.join(NATS_SERVER_BINARY),
-            std::process::Stdio::null(),
-            config.clone(),
-        )
-        .await;
+        let nats_one =
+            start_nats_server(&nats_binary, std::process::Stdio::null(), config.clone()).await;
         assert!(nats_one.is_ok());
 
         // Give NATS a few seconds to start up and listen
         tokio::time::sleep(std::time::Duration::from_millis(5000)).await;
         let log_path = install_dir.join("nats.log");
         let log = std::fs::File::create(&log_path)?;
-        let nats_two = start_nats_server(&install_dir.join(NATS_SERVER_BINARY), log, config).await;
+        let nats_two = start_nats_server(&nats_binary, log, config).await;
         assert!(nats_two.is_err());
 
         nats_one.unwrap().kill().await?;
@@ -514,4 +842,82 @@ mod test {
         let _ = remove_dir_all(install_dir).await;
         Ok(())
     }
+
+    #[tokio::test]
+    async fn can_write_jetstream_store_dir() -> Result<()> {
+        let dir = temp_dir().join("can_write_jetstream_store_dir");
+        let _ = remove_dir_all(&dir).await;
+        create_dir_all(&dir).await?;
+
+        let store_dir = dir.join("jetstream");
+        let config = NatsConfig::new_standalone("127.0.0.1", 4222, Some("isolated".to_string()))
+            .with_store_dir(&store_dir);
+        config.write_to_path(dir.join("nats.conf")).await?;
+
+        let contents = tokio::fs::read_to_string(dir.join("nats.conf")).await?;
+        assert_eq!(
+            contents,
+            format!(
+                "\njetstream {{\n    domain=isolated\n    store_dir: {:?}\n}}\n\n",
+                store_dir.to_string_lossy()
+            )
+        );
+
+        let _ = remove_dir_all(dir).await;
+        Ok(())
+    }
+
+    /// Installs a stand-in for `nats-server` that only reports a version
+    #[cfg(target_family = "unix")]
+    async fn fake_nats_server(path: &std::path::Path, version: &str) -> Result<()> {
+        use std::os::unix::prelude::PermissionsExt;
+        create_dir_all(path.parent().unwrap()).await?;
+        tokio::fs::write(
+            path,
+            format!("#!/bin/sh\necho \"nats-server: {}\"\n", version),
+        )
+        .await?;
+        tokio::fs::set_permissions(path, std::fs::Permissions::from_mode(0o755)).await?;
+        Ok(())
+    }
+
+    #[cfg(target_family = "unix")]
+    #[tokio::test]
+    async fn can_find_installed_nats_versions() -> Result<()> {
+        let install_dir = temp_dir().join("can_find_installed_nats_versions");
+        let _ = remove_dir_all(&install_dir).await;
+        let versioned = install_dir
+            .join(NATS_SERVER_DIR)
+            .join("v2.9.14")
+            .join(NATS_SERVER_BINARY);
+        let legacy = install_dir.join(NATS_SERVER_BINARY);
+        fake_nats_server(&versioned, "v2.9.14").await?;
+        fake_nats_server(&legacy, "v2.8.4").await?;
+
+        assert_eq!(
+            find_nats_server_binary(&install_dir, "v2.9.14").await,
+            Some(versioned.clone())
+        );
+        // A binary installed before versions had their own directory is still found
+        assert_eq!(
+            find_nats_server_binary(&install_dir, "2.8.4").await,
+            Some(legacy.clone())
+        );
+        assert_eq!(find_nats_server_binary(&install_dir, "v2.9.15").await, None);
+        assert_eq!(
+            installed_nats_servers(&install_dir).await,
+            vec![
+                ("v2.9.14".to_string(), versioned.clone()),
+                ("v2.8.4".to_string(), legacy.clone()),
+            ]
+        );
+
+        remove_nats_server(&install_dir, &versioned).await?;
+        remove_nats_server(&install_dir, &legacy).await?;
+        assert!(!is_nats_installed(&install_dir).await);
+        assert!(!versioned.parent().unwrap().exists());
+
+        let _ = remove_dir_all(install_dir).await;
+        Ok(())
+    }
 }
@@ -1,5 +1,4 @@
 use std::collections::HashMap;
-use std::io::Cursor;
 #[cfg(target_family = "unix")]
 use std::os::unix::prelude::PermissionsExt;
 use std::path::{Path, PathBuf};
@@ -11,13 +10,25 @@ use async_compression::tokio::bufread::GzipDecoder;
 use command_group::AsyncCommandGroup;
 use futures::future::join_all;
 use log::warn;
-use tokio::fs::{create_dir_all, metadata, File};
+use tokio::fs::{create_dir_all, metadata, read_dir, remove_dir_all, remove_file, File};
+use tokio::io::BufReader;
 use tokio::process::{Child, Command};
 use tokio_stream::StreamExt;
 use tokio_tar::Archive;
 
-const WASMCLOUD_GITHUB_RELEASE_URL: &str =
+use super::{
+    download_verified, github_release_tags, install_atomically, Checksum, DownloadProgress,
+    PARTIAL_DOWNLOADS_DIR,
+};
+
+/// Where wasmCloud host releases are downloaded from, unless a mirror is used
+pub const WASMCLOUD_GITHUB_RELEASE_URL: &str =
     "https://github.com/wasmCloud/wasmcloud-otp/releases/download";
+const WASMCLOUD_GITHUB_REPO: &str = "wasmCloud/wasmcloud-otp";
+/// SHA-256 checksums of wasmCloud host release tarballs as `(version, os, arch, checksum)`, with
+/// the OS and architecture named as in [std::env::consts]. Entries must come from `sha256sum` of
+/// the assets of the release itself
+const PINNED_WASMCLOUD_CHECKSUMS: &[(&str, &str, &str, &str)] = &[];
 #[cfg(target_family = "unix")]
 pub const WASMCLOUD_HOST_BIN: &str = "bin/wasmcloud_host";
 #[cfg(target_family = "windows")]
@@ -157,12 +168,151 @@ pub async fn download_wasmcloud_for_os_arch_pair<P>(
 where
     P: AsRef<Path>,
 {
-    let url = wasmcloud_url(os, arch, version);
-    let body = reqwest::get(url).await?.bytes().await?;
-    let cursor = Cursor::new(body);
-    let mut wasmcloud_host = Archive::new(Box::new(GzipDecoder::new(cursor)));
-    let mut entries = wasmcloud_host.entries()?;
+    download_wasmcloud_from_mirror(WASMCLOUD_GITHUB_RELEASE_URL, os, arch, version, dir, |_| {})
+        .await
+}
+
+/// Ensures the `wasmcloud_host` application is installed, downloading it from a mirror of the
+/// wasmCloud GitHub releases for the OS/ARCH pair of the current host machine if it isn't. Returns
+/// the path to the Elixir executable.
+///
+/// # Arguments
+///
+/// * `mirror` - Base URL of the mirror, which lays out releases like
+///   <https://github.com/wasmCloud/wasmcloud-otp/releases/download>, i.e. `<mirror>/<version>/<tarball>`
+/// * `version` - Specifies the version of the binary to download in the form of `vX.Y.Z`. Must be
+///   at least v0.57.0.
+/// * `dir` - Where to unpack the wasmCloud host contents into. Each host will be stored in a
+///   directory maching its version
+/// * `progress` - Called with the progress of the download each time a chunk of it arrives
+pub async fn ensure_wasmcloud_from_mirror<P, F>(
+    mirror: &str,
+    version: &str,
+    dir: P,
+    progress: F,
+) -> Result<PathBuf>
+where
+    P: AsRef<Path>,
+    F: Fn(DownloadProgress),
+{
+    check_version(version)?;
+    if let Some(dir) = find_wasmcloud_binary(&dir, version).await {
+        return Ok(dir);
+    }
+    download_wasmcloud_from_mirror(
+        mirror,
+        std::env::consts::OS,
+        std::env::consts::ARCH,
+        version,
+        dir,
+        progress,
+    )
+    .await
+}
+
+/// Downloads the specified version of the wasmCloud host for an OS/ARCH pair from a mirror of the
+/// wasmCloud GitHub releases and unpacks the contents to a directory. Returns the path to the
+/// Elixir executable. The tarball is kept in [PARTIAL_DOWNLOADS_DIR] until it's installed, so that
+/// an interrupted download resumes where it left off.
+///
+/// # Arguments
+///
+/// * `mirror` - Base URL of the mirror, e.g. [WASMCLOUD_GITHUB_RELEASE_URL]. Tarballs are
+///   downloaded from `<mirror>/<version>/<tarball>`
+/// * `os` - Specifies the operating system of the binary to download, e.g. `linux`
+/// * `arch` - Specifies the architecture of the binary to download, e.g. `amd64`
+/// * `version` - Specifies the version of the binary to download in the form of `vX.Y.Z`
+/// * `dir` - Where to unpack the wasmCloud host contents into. Each host will be stored in a
+///   directory maching its version
+/// * `progress` - Called with the progress of the download each time a chunk of it arrives
+pub async fn download_wasmcloud_from_mirror<P, F>(
+    mirror: &str,
+    os: &str,
+    arch: &str,
+    version: &str,
+    dir: P,
+    progress: F,
+) -> Result<PathBuf>
+where
+    P: AsRef<Path>,
+    F: Fn(DownloadProgress),
+{
+    let url = wasmcloud_url(mirror.trim_end_matches('/'), os, arch, version);
+    let tarball = dir
+        .as_ref()
+        .join(PARTIAL_DOWNLOADS_DIR)
+        .join(format!("wasmcloud-{}-{}-{}.tar.gz", version, arch, os));
+    // wasmcloud-otp releases don't publish checksums, so only pinned versions can be verified. The
+    // tarball is still unpacked to a temporary directory so a truncated download can't leave a
+    // half installed host behind
+    let checksum = match pinned_wasmcloud_checksum(version, os, arch) {
+        Some(checksum) => Checksum::Pinned(checksum),
+        None => Checksum::Unverified,
+    };
+    download_verified(&url, checksum, &tarball, progress)
+        .await
+        .map_err(|e| anyhow!("Failed to download wasmCloud {}: {}", version, e))?;
+    let res = install_wasmcloud_tarball(&tarball, version, dir).await;
+    // Once installed the tarball is of no further use, and one that can't be installed shouldn't
+    // be resumed either
+    let _ = remove_file(&tarball).await;
+    res
+}
+
+/// Installs a version of the wasmCloud host from a release tarball on disk, e.g. one downloaded
+/// from <https://github.com/wasmCloud/wasmcloud-otp/releases/> ahead of time, for machines without
+/// internet access. Returns the path to the Elixir executable.
+///
+/// # Arguments
+///
+/// * `tarball` - Path to the `.tar.gz` release of the wasmCloud host for the current OS/ARCH pair
+/// * `version` - The version of wasmCloud in the tarball in the form of `vX.Y.Z`, which it's
+///   installed as
+/// * `dir` - Where to unpack the wasmCloud host contents into. Each host will be stored in a
+///   directory maching its version
+pub async fn install_wasmcloud_from_tarball<T, P>(
+    tarball: T,
+    version: &str,
+    dir: P,
+) -> Result<PathBuf>
+where
+    T: AsRef<Path>,
+    P: AsRef<Path>,
+{
+    if let Err(e) = metadata(tarball.as_ref()).await {
+        return Err(anyhow!(
+            "Failed to read wasmCloud tarball {}: {}",
+            tarball.as_ref().display(),
+            e
+        ));
+    }
+    install_wasmcloud_tarball(tarball.as_ref(), version, dir).await
+}
+
+async fn install_wasmcloud_tarball<P>(tarball: &Path, version: &str, dir: P) -> Result<PathBuf>
+where
+    P: AsRef<Path>,
+{
     let version_dir = dir.as_ref().join(version);
+    install_atomically(&version_dir, |temp_dir| {
+        extract_wasmcloud(tarball, temp_dir, version)
+    })
+    .await?;
+
+    // Return success if wasmCloud components exist, error otherwise
+    match find_wasmcloud_binary(&dir, version).await {
+        Some(path) => Ok(path),
+        None => Err(anyhow!(
+            "wasmCloud was not installed successfully, please see logs"
+        )),
+    }
+}
+
+/// Extracts all of the files in a wasmCloud host release tarball into a directory
+async fn extract_wasmcloud(tarball: &Path, version_dir: PathBuf, version: &str) -> Result<()> {
+    let reader = BufReader::new(File::open(tarball).await?);
+    let mut wasmcloud_host = Archive::new(Box::new(GzipDecoder::new(reader)));
+    let mut entries = wasmcloud_host.entries()?;
     // Copy all of the files out of the tarball into the bin directory
     while let Some(res) = entries.next().await {
         let mut entry = res.map_err(|_e| {
@@ -203,15 +353,20 @@ where
             }
         }
     }
-
-    // Return success if wasmCloud components exist, error otherwise
-    match find_wasmcloud_binary(&dir, version).await {
-        Some(path) => Ok(path),
-        None => Err(anyhow!(
-            "wasmCloud was not installed successfully, please see logs"
-        )),
+    // Don't install an archive that isn't a wasmCloud host release
+    if metadata(version_dir.join(WASMCLOUD_HOST_BIN))
+        .await
+        .is_err()
+    {
+        return Err(anyhow!(
+            "The tarball for wasmCloud {} does not contain {}",
+            version,
+            WASMCLOUD_HOST_BIN
+        ));
     }
+    Ok(())
 }
+
 /// Helper function to start a wasmCloud host given the path to the elixir release script
 /// /// # Arguments
 ///
@@ -307,12 +462,61 @@ where
         .then_some(bin_file)
 }
 
+/// Returns the version and path to the binary of every wasmCloud host installed in a directory,
+/// sorted by version directory
+pub async fn installed_wasmcloud_versions<P>(dir: P) -> Vec<(String, PathBuf)>
+where
+    P: AsRef<Path>,
+{
+    let mut versions = Vec::new();
+    if let Ok(mut entries) = read_dir(dir.as_ref()).await {
+        while let Ok(Some(entry)) = entries.next_entry().await {
+            let version = entry.file_name().to_string_lossy().to_string();
+            if let Some(bin_path) = find_wasmcloud_binary(&dir, &version).await {
+                versions.push((version, bin_path));
+            }
+        }
+    }
+    versions.sort();
+    versions
+}
+
+/// Removes an installed version of the wasmCloud host from a directory
+pub async fn remove_wasmcloud<P>(dir: P, version: &str) -> Result<()>
+where
+    P: AsRef<Path>,
+{
+    if find_wasmcloud_binary(&dir, version).await.is_none() {
+        return Err(anyhow!("wasmCloud {} is not installed", version));
+    }
+    remove_dir_all(dir.as_ref().join(version))
+        .await
+        .map_err(anyhow::Error::from)
+}
+
+/// Lists the versions of the wasmCloud host published on GitHub that can be downloaded, newest
+/// first
+pub async fn available_wasmcloud_versions() -> Result<Vec<String>> {
+    Ok(github_release_tags(WASMCLOUD_GITHUB_REPO)
+        .await?
+        .into_iter()
+        .filter(|version| check_version(version).is_ok())
+        .collect())
+}
+
+/// Returns the SHA-256 checksum that the release tarball of a wasmCloud host version for an OS/ARCH
+/// pair is verified against before it's unpacked, if that version is pinned. Versions that aren't
+/// are installed without verification, as wasmcloud-otp releases don't publish checksums
+pub fn pinned_wasmcloud_checksum(version: &str, os: &str, arch: &str) -> Option<&'static str> {
+    PINNED_WASMCLOUD_CHECKSUMS
+        .iter()
+        .find(|(v, o, a, _)| *v == version && *o == os && *a == arch)
+        .map(|(_, _, _, checksum)| *checksum)
+}
+
 /// Helper function to determine the wasmCloud host release path given an os/arch and version
-fn wasmcloud_url(os: &str, arch: &str, version: &str) -> String {
-    format!(
-        "{}/{}/{}-{}.tar.gz",
-        WASMCLOUD_GITHUB_RELEASE_URL, version, arch, os
-    )
+fn wasmcloud_url(mirror: &str, os: &str, arch: &str, version: &str) -> String {
+    format!("{}/{}/{}-{}.tar.gz", mirror, version, arch, os)
 }
 
 /// Helper function to ensure the version of wasmCloud is above the minimum
@@ -341,23 +545,88 @@ fn check_version(version: &str) -> Result<()> {
 #[cfg(test)]
 mod test {
     use super::{check_version, ensure_wasmcloud, wasmcloud_url};
+    use crate::start::download::test_tarball;
     use crate::start::{
         ensure_nats_server, ensure_wasmcloud_for_os_arch_pair, find_wasmcloud_binary,
-        is_nats_installed, start_nats_server, start_wasmcloud_host, NatsConfig, NATS_SERVER_BINARY,
+        install_wasmcloud_from_tarball, installed_wasmcloud_versions, is_nats_installed,
+        remove_wasmcloud, start_nats_server, start_wasmcloud_host, NatsConfig,
+        WASMCLOUD_GITHUB_RELEASE_URL, WASMCLOUD_HOST_BIN,
     };
     use reqwest::StatusCode;
     use std::{collections::HashMap, env::temp_dir};
     use tokio::fs::{create_dir_all, remove_dir_all};
     const WASMCLOUD_VERSION: &str = "v0.60.0";
 
+    #[tokio::test]
+    async fn can_install_wasmcloud_from_tarball() {
+        let install_dir = tempfile::tempdir().unwrap();
+        let tarball = install_dir.path().join("x86_64-linux.tar.gz");
+        tokio::fs::write(
+            &tarball,
+            test_tarball(&[
+                (WASMCLOUD_HOST_BIN, b"#!/bin/sh"),
+                ("lib/host_core.app", b"app"),
+                ("releases/start_erl.data", b"data"),
+            ])
+            .await,
+        )
+        .await
+        .unwrap();
+
+        let bin_path =
+            install_wasmcloud_from_tarball(&tarball, WASMCLOUD_VERSION, install_dir.path())
+                .await
+                .unwrap();
+        assert_eq!(
+            Some(bin_path),
+            find_wasmcloud_binary(install_dir.path(), WASMCLOUD_VERSION).await
+        );
+
+        // An archive that isn't a wasmCloud release isn't installed
+        tokio::fs::write(&tarball, test_tarball(&[("README.md", b"readme")]).await)
+            .await
+            .unwrap();
+        assert!(
+            install_wasmcloud_from_tarball(&tarball, "v0.61.0", install_dir.path())
+                .await
+                .is_err()
+        );
+        assert!(!install_dir.path().join("v0.61.0").exists());
+    }
+
     #[tokio::test]
     async fn can_request_supported_wasmcloud_urls() {
         let host_tarballs = vec![
-            wasmcloud_url("linux", "aarch64", WASMCLOUD_VERSION),
-            wasmcloud_url("linux", "x86_64", WASMCLOUD_VERSION),
-            wasmcloud_url("macos", "aarch64", WASMCLOUD_VERSION),
-            wasmcloud_url("macos", "x86_64", WASMCLOUD_VERSION),
-            wasmcloud_url("windows", "x86_64", WASMCLOUD_VERSION),
+            wasmcloud_url(
+                WASMCLOUD_GITHUB_RELEASE_URL,
+                "linux",
+                "aarch64",
+                WASMCLOUD_VERSION,
+            ),
+            wasmcloud_url(
+                WASMCLOUD_GITHUB_RELEASE_URL,
+                "linux",
+                "x86_64",
+                WASMCLOUD_VERSION,
+            ),
+            wasmcloud_url(
+                WASMCLOUD_GITHUB_RELEASE_URL,
+                "macos",
+                "aarch64",
+                WASMCLOUD_VERSION,
+            ),
+            wasmcloud_url(
+                WASMCLOUD_GITHUB_RELEASE_URL,
+                "macos",
+                "x86_64",
+                WASMCLOUD_VERSION,
+            ),
+            wasmcloud_url(
+                WASMCLOUD_GITHUB_RELEASE_URL,
+                "windows",
+                "x86_64",
+                WASMCLOUD_VERSION,
+            ),
         ];
         for tarball_url in host_tarballs {
             assert_eq!(
@@ -483,18 +752,14 @@ mod test {
 
         // Install and start NATS server for this test
         let nats_port = 10004;
-        assert!(ensure_nats_server(NATS_SERVER_VERSION, &install_dir)
+        let nats_binary = ensure_nats_server(NATS_SERVER_VERSION, &install_dir)
             .await
-            .is_ok());
+            .expect("Unable to ensure NATS");
         assert!(is_nats_installed(&install_dir).await);
         let config = NatsConfig::new_standalone("127.0.0.1", nats_port, None);
-        let mut nats_child = start_nats_server(
-            install_dir.join(NATS_SERVER_BINARY),
-            std::process::Stdio::null(),
-            config,
-        )
-        .await
-        .expect("Unable to start nats process");
+        let mut nats_child = start_nats_server(nats_binary, std::process::Stdio::null(), config)
+            .await
+            .expect("Unable to start nats process");
 
         let wasmcloud_binary = ensure_wasmcloud(WASMCLOUD_HOST_VERSION, &install_dir)
             .await
@@ -591,6 +856,41 @@ mod test {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn can_find_installed_wasmcloud_versions() -> anyhow::Result<()> {
+        let install_dir = temp_dir().join("can_find_installed_wasmcloud_versions");
+        let _ = remove_dir_all(&install_dir).await;
+        for version in ["v0.60.0", "v0.59.0"] {
+            let version_dir = install_dir.join(version);
+            create_dir_all(version_dir.join("bin")).await?;
+            create_dir_all(version_dir.join("lib")).await?;
+            create_dir_all(version_dir.join("releases")).await?;
+            tokio::fs::write(version_dir.join(WASMCLOUD_HOST_BIN), "").await?;
+        }
+        // Directories that don't hold a host release are skipped
+        create_dir_all(install_dir.join("nats").join("v2.9.14")).await?;
+
+        let installed = installed_wasmcloud_versions(&install_dir).await;
+        assert_eq!(
+            installed
+                .iter()
+                .map(|(version, _)| version.as_str())
+                .collect::<Vec<_>>(),
+            vec!["v0.59.0", "v0.60.0"]
+        );
+        assert_eq!(
+            installed[1].1,
+            install_dir.join("v0.60.0").join(WASMCLOUD_HOST_BIN)
+        );
+
+        remove_wasmcloud(&install_dir, "v0.59.0").await?;
+        assert!(remove_wasmcloud(&install_dir, "nats").await.is_err());
+        assert_eq!(installed_wasmcloud_versions(&install_dir).await.len(), 1);
+
+        let _ = remove_dir_all(install_dir).await;
+        Ok(())
+    }
+
     #[tokio::test]
     async fn can_properly_deny_distillery_release_hosts() -> anyhow::Result<()> {
         // Ensure we allow versions >= 0.57.0
@@ -0,0 +1,378 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use anyhow::{bail, Result};
+use serde::Serialize;
+
+use crate::ctl::manifest::HostManifest;
+
+const OAM_API_VERSION: &str = "core.oam.dev/v1beta1";
+const DEFAULT_LINK_NAME: &str = "default";
+
+/// An OAM application, as produced by converting a host manifest
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct Application {
+    pub api_version: String,
+    pub kind: String,
+    pub metadata: Metadata,
+    pub spec: Spec,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(crate) struct Metadata {
+    pub name: String,
+    pub annotations: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(crate) struct Spec {
+    pub components: Vec<Component>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(crate) struct Component {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub component_type: String,
+    pub properties: Properties,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub traits: Vec<Trait>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(crate) struct Properties {
+    pub image: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub contract: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub link_name: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", content = "properties", rename_all = "lowercase")]
+pub(crate) enum Trait {
+    Spreadscaler {
+        replicas: usize,
+    },
+    Linkdef {
+        target: String,
+        #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+        values: BTreeMap<String, String>,
+    },
+}
+
+/// Converts the actors, capabilities and links of a host manifest into an OAM application.
+///
+/// Links in a host manifest refer to actors and providers by public key, which can't be known
+/// from their image references alone. `images` maps public keys to image references, e.g. as
+/// found in the inventories of running hosts. Keys that aren't in it are resolved to the only
+/// actor in the manifest, or to the only capability with the link's link name
+pub(crate) fn convert_manifest(
+    manifest: &HostManifest,
+    name: &str,
+    version: &str,
+    images: &HashMap<String, String>,
+) -> Result<Application> {
+    let mut names = HashSet::new();
+    let mut components = Vec::new();
+
+    // Actors listed more than once were started once per entry, which becomes their replica count
+    let mut actors: Vec<(String, usize)> = Vec::new();
+    for actor in &manifest.actors {
+        match actors.iter_mut().find(|(image, _)| image == actor) {
+            Some((_, count)) => *count += 1,
+            None => actors.push((actor.clone(), 1)),
+        }
+    }
+    for (image, replicas) in actors {
+        components.push(Component {
+            name: unique_name(&mut names, component_name(&image)),
+            component_type: "actor".to_string(),
+            properties: Properties {
+                image,
+                contract: None,
+                link_name: None,
+            },
+            traits: vec![Trait::Spreadscaler { replicas }],
+        });
+    }
+    for cap in &manifest.capabilities {
+        let base = component_name(&cap.image_ref);
+        let link_name = cap
+            .link_name
+            .clone()
+            .filter(|link_name| link_name != DEFAULT_LINK_NAME);
+        let base = match &link_name {
+            Some(link_name) => format!("{}-{}", base, sanitize(link_name)),
+            None => base,
+        };
+        components.push(Component {
+            name: unique_name(&mut names, base),
+            component_type: "capability".to_string(),
+            properties: Properties {
+                image: cap.image_ref.clone(),
+                contract: None,
+                link_name,
+            },
+            traits: vec![Trait::Spreadscaler { replicas: 1 }],
+        });
+    }
+
+    for link in &manifest.links {
+        let link_name = link.link_name.as_deref().unwrap_or(DEFAULT_LINK_NAME);
+        let actor = resolve_component(&components, images, &link.actor, "actor", |_| true)?;
+        let provider =
+            resolve_component(&components, images, &link.provider_id, "capability", |c| {
+                c.properties
+                    .link_name
+                    .as_deref()
+                    .unwrap_or(DEFAULT_LINK_NAME)
+                    == link_name
+            })?;
+
+        let target = components[provider].name.clone();
+        match &components[provider].properties.contract {
+            Some(contract) if contract != &link.contract_id => bail!(
+                "Capability {} is linked with both contract {} and {}",
+                target,
+                contract,
+                link.contract_id
+            ),
+            _ => components[provider].properties.contract = Some(link.contract_id.clone()),
+        }
+        components[actor].traits.push(Trait::Linkdef {
+            target,
+            values: link
+                .values
+                .clone()
+                .unwrap_or_default()
+                .into_iter()
+                .collect(),
+        });
+    }
+
+    if let Some(cap) = components
+        .iter()
+        .find(|c| c.component_type == "capability" && c.properties.contract.is_none())
+    {
+        bail!(
+            "Could not determine the contract of capability {} ({}), as no link refers to it",
+            cap.name,
+            cap.properties.image
+        );
+    }
+
+    Ok(Application {
+        api_version: OAM_API_VERSION.to_string(),
+        kind: "Application".to_string(),
+        metadata: Metadata {
+            name: name.to_string(),
+            annotations: BTreeMap::from([("version".to_string(), version.to_string())]),
+        },
+        spec: Spec { components },
+    })
+}
+
+/// Finds the index of the component of the given type that a public key in a link refers to
+fn resolve_component(
+    components: &[Component],
+    images: &HashMap<String, String>,
+    public_key: &str,
+    component_type: &str,
+    fallback: impl Fn(&Component) -> bool,
+) -> Result<usize> {
+    let candidates = components
+        .iter()
+        .enumerate()
+        .filter(|(_, c)| c.component_type == component_type)
+        .collect::<Vec<_>>();
+    if let Some(image) = images.get(public_key) {
+        return match candidates
+            .iter()
+            .find(|(_, c)| &c.properties.image == image)
+        {
+            Some((idx, _)) => Ok(*idx),
+            None => bail!(
+                "Link refers to {} {} ({}), which is not in the manifest",
+                component_type,
+                public_key,
+                image
+            ),
+        };
+    }
+    match candidates
+        .iter()
+        .filter(|(_, c)| fallback(c))
+        .collect::<Vec<_>>()
+        .as_slice()
+    {
+        [(idx, _)] => Ok(*idx),
+        _ => bail!(
+            "Could not determine which {} in the manifest has public key {}. Use --resolve to look it up in a lattice where the manifest is running",
+            component_type,
+            public_key
+        ),
+    }
+}
+
+/// Derives a component name from an OCI reference or file path, e.g. `echo` for
+/// `wasmcloud.azurecr.io/echo:0.3.7` or `./build/echo_s.wasm`
+fn component_name(image_ref: &str) -> String {
+    let image_ref = image_ref.split('@').next().unwrap_or_default();
+    let last = image_ref.rsplit('/').next().unwrap_or_default();
+    let last = last.split(':').next().unwrap_or_default();
+    let last = last
+        .strip_suffix(".par.gz")
+        .or_else(|| last.strip_suffix(".wasm"))
+        .unwrap_or(last);
+    let last = last.strip_suffix("_s").unwrap_or(last);
+    sanitize(last)
+}
+
+fn sanitize(name: &str) -> String {
+    let name = name
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect::<String>();
+    let name = name.trim_matches('-');
+    if name.is_empty() {
+        "component".to_string()
+    } else {
+        name.to_string()
+    }
+}
+
+fn unique_name(names: &mut HashSet<String>, base: String) -> String {
+    let mut name = base.clone();
+    let mut suffix = 2;
+    while names.contains(&name) {
+        name = format!("{}-{}", base, suffix);
+        suffix += 1;
+    }
+    names.insert(name.clone());
+    name
+}
+
+#[cfg(test)]
+mod test {
+    use super::{component_name, convert_manifest};
+    use crate::app::validate::validate_manifest;
+    use crate::ctl::manifest::HostManifest;
+    use std::collections::HashMap;
+
+    const ECHO: &str = "MBCFOPM6JW2APJLXJD3Z5O4CN7CPYJ2B4FTKLJUR5YR5MITIU7HD3WD5";
+    const HTTPSERVER: &str = "VAG3QITQQ2ODAOWB5TTQSDJ53XK3SHBEIFNK4AYJ5RKAX2UNSCAPHA5M";
+    const KVREDIS: &str = "VAZVC4RX54J2NVCMCW7BPCAHGGG5XZXDBXFUMDUXGESTMQEJLC3YVZWB";
+
+    fn manifest(yaml: &str) -> HostManifest {
+        serde_yaml::from_str(yaml).unwrap()
+    }
+
+    #[test]
+    fn test_convert_manifest() {
+        let hm = manifest(&format!(
+            r#"actors:
+  - wasmcloud.azurecr.io/echo:0.3.7
+  - wasmcloud.azurecr.io/echo:0.3.7
+capabilities:
+  - image_ref: wasmcloud.azurecr.io/httpserver:0.17.0
+    link_name: default
+links:
+  - actor: {}
+    provider_id: {}
+    contract_id: "wasmcloud:httpserver"
+    values:
+      PORT: "8080"
+"#,
+            ECHO, HTTPSERVER
+        ));
+
+        let app = convert_manifest(&hm, "echo", "v0.0.1", &HashMap::new()).unwrap();
+        let yaml = serde_yaml::to_string(&app).unwrap();
+        assert_eq!(validate_manifest(&yaml), vec![]);
+        assert_eq!(
+            yaml,
+            r#"apiVersion: core.oam.dev/v1beta1
+kind: Application
+metadata:
+  name: echo
+  annotations:
+    version: v0.0.1
+spec:
+  components:
+  - name: echo
+    type: actor
+    properties:
+      image: wasmcloud.azurecr.io/echo:0.3.7
+    traits:
+    - type: spreadscaler
+      properties:
+        replicas: 2
+    - type: linkdef
+      properties:
+        target: httpserver
+        values:
+          PORT: '8080'
+  - name: httpserver
+    type: capability
+    properties:
+      image: wasmcloud.azurecr.io/httpserver:0.17.0
+      contract: wasmcloud:httpserver
+    traits:
+    - type: spreadscaler
+      properties:
+        replicas: 1
+"#
+        );
+    }
+
+    #[test]
+    fn test_convert_manifest_resolves_keys() {
+        let hm = manifest(&format!(
+            r#"actors:
+  - wasmcloud.azurecr.io/echo:0.3.7
+  - wasmcloud.azurecr.io/kvcounter:0.3.0
+capabilities:
+  - image_ref: wasmcloud.azurecr.io/httpserver:0.17.0
+  - image_ref: wasmcloud.azurecr.io/kvredis:0.19.0
+links:
+  - actor: {}
+    provider_id: {}
+    contract_id: "wasmcloud:httpserver"
+  - actor: {}
+    provider_id: {}
+    contract_id: "wasmcloud:keyvalue"
+"#,
+            ECHO, HTTPSERVER, ECHO, KVREDIS
+        ));
+
+        // Without knowing which actor has which key, the links are ambiguous
+        assert!(convert_manifest(&hm, "kv", "v0.0.1", &HashMap::new()).is_err());
+
+        let images = HashMap::from([
+            (ECHO.to_string(), hm.actors[0].clone()),
+            (HTTPSERVER.to_string(), hm.capabilities[0].image_ref.clone()),
+            (KVREDIS.to_string(), hm.capabilities[1].image_ref.clone()),
+        ]);
+        let app = convert_manifest(&hm, "kv", "v0.0.1", &images).unwrap();
+        let yaml = serde_yaml::to_string(&app).unwrap();
+        assert_eq!(validate_manifest(&yaml), vec![]);
+        assert_eq!(app.spec.components[0].traits.len(), 3);
+        assert_eq!(
+            app.spec.components[3].properties.contract.as_deref(),
+            Some("wasmcloud:keyvalue")
+        );
+    }
+
+    #[test]
+    fn test_component_name() {
+        assert_eq!(component_name("wasmcloud.azurecr.io/echo:0.3.7"), "echo");
+        assert_eq!(
+            component_name("localhost:5000/my_actor@sha256:abcd"),
+            "my-actor"
+        );
+        assert_eq!(component_name("./build/echo_s.wasm"), "echo");
+        assert_eq!(component_name("provider.par.gz"), "provider");
+    }
+}
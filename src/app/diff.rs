@@ -0,0 +1,356 @@
+use std::collections::BTreeMap;
+
+use anyhow::{bail, Context, Result};
+use serde::Serialize;
+use serde_yaml::Value;
+
+/// The link name wasmCloud uses for links that don't set one
+const DEFAULT_LINK_NAME: &str = "default";
+
+/// The differences between two versions of an app specification, by component
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub(crate) struct ModelDiff {
+    pub name: String,
+    pub from: String,
+    pub to: String,
+    pub components: Vec<ComponentDiff>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "change", rename_all = "snake_case")]
+pub(crate) enum ComponentDiff {
+    Added {
+        name: String,
+        #[serde(rename = "type")]
+        component_type: String,
+        image: String,
+    },
+    Removed {
+        name: String,
+        #[serde(rename = "type")]
+        component_type: String,
+        image: String,
+    },
+    Changed {
+        name: String,
+        changes: Vec<FieldChange>,
+    },
+}
+
+/// A changed field of a component. Fields that only exist on one side have no old or new value
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub(crate) struct FieldChange {
+    pub field: String,
+    pub old: Option<String>,
+    pub new: Option<String>,
+}
+
+/// Parses the raw OAM text of an app specification, as stored by wadm or read from a file
+pub(crate) fn parse_model(raw: &str) -> Result<Value> {
+    serde_yaml::from_str(raw).context("Failed to parse app specification")
+}
+
+/// Compares two OAM app specifications, labelling them `from` and `to` in the result
+pub(crate) fn diff_models(old: &Value, new: &Value, from: &str, to: &str) -> Result<ModelDiff> {
+    let old_components = components(old)?;
+    let mut new_components = components(new)?;
+
+    let mut diffs = Vec::new();
+    for (name, old_fields) in old_components {
+        match new_components.remove(&name) {
+            None => diffs.push(ComponentDiff::Removed {
+                component_type: field(&old_fields, "type"),
+                image: field(&old_fields, "image"),
+                name,
+            }),
+            Some(new_fields) => {
+                let changes = diff_fields(old_fields, new_fields);
+                if !changes.is_empty() {
+                    diffs.push(ComponentDiff::Changed { name, changes });
+                }
+            }
+        }
+    }
+    diffs.extend(
+        new_components
+            .into_iter()
+            .map(|(name, fields)| ComponentDiff::Added {
+                component_type: field(&fields, "type"),
+                image: field(&fields, "image"),
+                name,
+            }),
+    );
+
+    Ok(ModelDiff {
+        name: new
+            .get("metadata")
+            .and_then(|m| m.get("name"))
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string(),
+        from: from.to_string(),
+        to: to.to_string(),
+        components: diffs,
+    })
+}
+
+fn field(fields: &BTreeMap<String, String>, name: &str) -> String {
+    fields.get(name).cloned().unwrap_or_default()
+}
+
+fn diff_fields(
+    mut old: BTreeMap<String, String>,
+    new: BTreeMap<String, String>,
+) -> Vec<FieldChange> {
+    let mut changes = Vec::new();
+    for (name, new_value) in new {
+        match old.remove(&name) {
+            Some(old_value) if old_value == new_value => {}
+            old_value => changes.push(FieldChange {
+                field: name,
+                old: old_value,
+                new: Some(new_value),
+            }),
+        }
+    }
+    changes.extend(old.into_iter().map(|(name, old_value)| FieldChange {
+        field: name,
+        old: Some(old_value),
+        new: None,
+    }));
+    changes.sort_by(|a, b| a.field.cmp(&b.field));
+    changes
+}
+
+/// Flattens each component of a specification into the fields that are compared: its type,
+/// properties (like `image`), and its traits. Trait settings are prefixed by the trait type
+/// (like `spreadscaler.replicas`), and the values of each link are keyed by its target, contract
+/// and link name, so that none of them can collide. A missing target or contract is left empty,
+/// which keeps every link key the same shape
+fn components(model: &Value) -> Result<BTreeMap<String, BTreeMap<String, String>>> {
+    let components = match model.get("spec").and_then(|s| s.get("components")) {
+        Some(Value::Sequence(components)) => components,
+        _ => bail!("App specification has no list of components"),
+    };
+
+    let mut flattened = BTreeMap::new();
+    for component in components {
+        let name = component
+            .get("name")
+            .and_then(Value::as_str)
+            .context("App specification contains a component without a name")?;
+        let mut fields = BTreeMap::new();
+        if let Some(component_type) = component.get("type") {
+            fields.insert("type".to_string(), display_value(component_type));
+        }
+        if let Some(Value::Mapping(properties)) = component.get("properties") {
+            for (key, value) in properties {
+                fields.insert(display_value(key), display_value(value));
+            }
+        }
+        if let Some(Value::Sequence(traits)) = component.get("traits") {
+            for component_trait in traits {
+                let properties = component_trait.get("properties");
+                match component_trait.get("type").and_then(Value::as_str) {
+                    Some("spreadscaler") => {
+                        if let Some(Value::Mapping(properties)) = properties {
+                            for (key, value) in properties {
+                                fields.insert(
+                                    format!("spreadscaler.{}", display_value(key)),
+                                    display_value(value),
+                                );
+                            }
+                        }
+                    }
+                    Some("linkdef") => {
+                        let property = |name: &str| {
+                            properties
+                                .and_then(|p| p.get(name))
+                                .map(display_value)
+                                .filter(|value| !value.is_empty())
+                        };
+                        let key = [
+                            property("target").unwrap_or_default(),
+                            property("contract").unwrap_or_default(),
+                            property("link_name").unwrap_or_else(|| DEFAULT_LINK_NAME.to_string()),
+                        ];
+                        let values = properties
+                            .and_then(|p| p.get("values"))
+                            .map(display_value)
+                            .unwrap_or_else(|| "{}".to_string());
+                        fields.insert(format!("links.{}", key.join(".")), values);
+                    }
+                    Some(other) => {
+                        fields.insert(
+                            format!("traits.{}", other),
+                            properties.map(display_value).unwrap_or_default(),
+                        );
+                    }
+                    None => {}
+                }
+            }
+        }
+        flattened.insert(name.to_string(), fields);
+    }
+    Ok(flattened)
+}
+
+/// Renders scalars as-is and collections as compact JSON
+fn display_value(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Null => String::new(),
+        Value::Bool(b) => b.to_string(),
+        Value::Number(n) => n.to_string(),
+        other => serde_json::to_string(other).unwrap_or_default(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{diff_models, parse_model, ComponentDiff, FieldChange};
+
+    const V1: &str = r#"apiVersion: core.oam.dev/v1beta1
+kind: Application
+metadata:
+  name: echo
+  annotations:
+    version: v0.0.1
+spec:
+  components:
+    - name: echo
+      type: actor
+      properties:
+        image: wasmcloud.azurecr.io/echo:0.3.7
+      traits:
+        - type: spreadscaler
+          properties:
+            replicas: 1
+        - type: linkdef
+          properties:
+            target: httpserver
+            values:
+              address: 0.0.0.0:8080
+    - name: httpserver
+      type: capability
+      properties:
+        image: wasmcloud.azurecr.io/httpserver:0.17.0
+        contract: wasmcloud:httpserver
+    - name: logging
+      type: capability
+      properties:
+        image: wasmcloud.azurecr.io/logging:0.9.3
+        contract: wasmcloud:builtin:logging
+"#;
+
+    #[test]
+    fn test_diff_models() {
+        let v2 = V1
+            .replace("echo:0.3.7", "echo:0.3.8")
+            .replace("replicas: 1", "replicas: 3")
+            .replace("0.0.0.0:8080", "0.0.0.0:9090")
+            .replace("name: logging", "name: kvredis")
+            .replace("logging:0.9.3", "kvredis:0.19.0")
+            .replace("wasmcloud:builtin:logging", "wasmcloud:keyvalue");
+        let diff = diff_models(
+            &parse_model(V1).unwrap(),
+            &parse_model(&v2).unwrap(),
+            "v0.0.1",
+            "v0.0.2",
+        )
+        .unwrap();
+
+        assert_eq!(diff.name, "echo");
+        assert_eq!(
+            diff.components,
+            vec![
+                ComponentDiff::Changed {
+                    name: "echo".to_string(),
+                    changes: vec![
+                        FieldChange {
+                            field: "image".to_string(),
+                            old: Some("wasmcloud.azurecr.io/echo:0.3.7".to_string()),
+                            new: Some("wasmcloud.azurecr.io/echo:0.3.8".to_string()),
+                        },
+                        FieldChange {
+                            field: "links.httpserver..default".to_string(),
+                            old: Some(r#"{"address":"0.0.0.0:8080"}"#.to_string()),
+                            new: Some(r#"{"address":"0.0.0.0:9090"}"#.to_string()),
+                        },
+                        FieldChange {
+                            field: "spreadscaler.replicas".to_string(),
+                            old: Some("1".to_string()),
+                            new: Some("3".to_string()),
+                        },
+                    ],
+                },
+                ComponentDiff::Removed {
+                    name: "logging".to_string(),
+                    component_type: "capability".to_string(),
+                    image: "wasmcloud.azurecr.io/logging:0.9.3".to_string(),
+                },
+                ComponentDiff::Added {
+                    name: "kvredis".to_string(),
+                    component_type: "capability".to_string(),
+                    image: "wasmcloud.azurecr.io/kvredis:0.19.0".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_diff_keys_do_not_collide() {
+        // A second link to the same target under another link name, and a property that shares
+        // its name with a spreadscaler setting
+        let v2 = V1
+            .replace(
+                "image: wasmcloud.azurecr.io/echo:0.3.7",
+                "image: wasmcloud.azurecr.io/echo:0.3.7\n        replicas: 5",
+            )
+            .replace(
+                "              address: 0.0.0.0:8080\n",
+                r#"              address: 0.0.0.0:8080
+        - type: linkdef
+          properties:
+            target: httpserver
+            contract: wasmcloud:httpserver
+            link_name: admin
+            values:
+              address: 127.0.0.1:8081
+"#,
+            );
+        let diff = diff_models(
+            &parse_model(V1).unwrap(),
+            &parse_model(&v2).unwrap(),
+            "v0.0.1",
+            "v0.0.2",
+        )
+        .unwrap();
+
+        assert_eq!(
+            diff.components,
+            vec![ComponentDiff::Changed {
+                name: "echo".to_string(),
+                changes: vec![
+                    FieldChange {
+                        field: "links.httpserver.wasmcloud:httpserver.admin".to_string(),
+                        old: None,
+                        new: Some(r#"{"address":"127.0.0.1:8081"}"#.to_string()),
+                    },
+                    FieldChange {
+                        field: "replicas".to_string(),
+                        old: None,
+                        new: Some("5".to_string()),
+                    },
+                ],
+            }]
+        );
+    }
+
+    #[test]
+    fn test_diff_identical_models() {
+        let model = parse_model(V1).unwrap();
+        let diff = diff_models(&model, &model, "v0.0.1", "v0.0.1").unwrap();
+        assert!(diff.components.is_empty());
+    }
+}
@@ -1,24 +1,34 @@
-use std::{collections::HashMap, path::PathBuf, time::Duration};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::Duration,
+};
 
-use anyhow::{bail, Result};
-use async_nats::Client;
+use anyhow::{bail, Context, Result};
 use clap::{Args, Subcommand};
-use serde::{Deserialize, Serialize};
 use serde_json::json;
-use wash_lib::cli::{CommandOutput, OutputKind};
-use wash_lib::config::{DEFAULT_NATS_HOST, DEFAULT_NATS_PORT};
-use wash_lib::context::{
-    fs::{load_context, ContextDir},
-    ContextManager,
+use wash_lib::app::{
+    DeployReply, ModelDetails, ModelRevision, ModelStatus, ModelSummary, PutReply, WadmClient,
 };
+use wash_lib::cli::{CommandOutput, OutputKind};
 
 use crate::{
     appearance::spinner::Spinner,
-    ctl::ConnectionOpts,
-    ctx::{context_dir, ensure_host_config_context},
+    ctl::{ctl_client_from_opts, manifest::HostManifest, nats_client_from_opts, ConnectionOpts},
+    util::convert_error,
 };
 
+/// How many times requests to wadm are retried, unless otherwise specified
+const DEFAULT_WADM_RETRIES: u32 = 2;
+const DEFAULT_WADM_RETRY_BACKOFF_MS: u64 = 500;
+
+mod convert;
+mod diff;
 mod output;
+mod status;
+mod template;
+mod validate;
 
 #[derive(Debug, Clone, Subcommand)]
 pub(crate) enum AppCliCommand {
@@ -28,6 +38,9 @@ pub(crate) enum AppCliCommand {
     /// Retrieve the details for a specific version of an app specification
     #[clap(name = "get")]
     Get(GetCommand),
+    /// Compare the components of two versions of an app specification, or of a version and a local file
+    #[clap(name = "diff")]
+    Diff(DiffCommand),
     /// Retrieve the version history of a given model within the lattice
     #[clap(name = "history")]
     History(HistoryCommand),
@@ -43,12 +56,37 @@ pub(crate) enum AppCliCommand {
     /// Undeploy an application (stop the deployment monitor)
     #[clap(name = "undeploy")]
     Undeploy(UndeployCommand),
+    /// Show the deployment status of an application
+    #[clap(name = "status")]
+    Status(StatusCommand),
+    /// Check an app specification for errors without sending it to wadm
+    #[clap(name = "validate")]
+    Validate(ValidateCommand),
+    /// Convert a host manifest, as used by `wash ctl apply`, into an app specification
+    #[clap(name = "convert")]
+    Convert(ConvertCommand),
+}
+
+/// Options for commands that talk to wadm
+#[derive(Args, Debug, Clone)]
+pub(crate) struct WadmOpts {
+    #[clap(flatten)]
+    connection: ConnectionOpts,
+
+    /// Number of times to retry requests to wadm that time out or find no wadm to respond.
+    /// Requests that change the lattice are only retried when no wadm responded
+    #[clap(long = "retries", default_value_t = DEFAULT_WADM_RETRIES)]
+    retries: u32,
+
+    /// Delay before the first retry of a request to wadm, doubled for each retry after it
+    #[clap(long = "retry-backoff-ms", default_value_t = DEFAULT_WADM_RETRY_BACKOFF_MS)]
+    retry_backoff_ms: u64,
 }
 
 #[derive(Args, Debug, Clone)]
 pub(crate) struct ListCommand {
     #[clap(flatten)]
-    opts: ConnectionOpts,
+    opts: WadmOpts,
 }
 #[derive(Args, Debug, Clone)]
 pub(crate) struct UndeployCommand {
@@ -57,7 +95,7 @@ pub(crate) struct UndeployCommand {
     model_name: String,
 
     #[clap(flatten)]
-    opts: ConnectionOpts,
+    opts: WadmOpts,
 }
 
 #[derive(Args, Debug, Clone)]
@@ -70,8 +108,34 @@ pub(crate) struct DeployCommand {
     #[clap(name = "version")]
     version: String,
 
+    /// Wait for the application to be deployed, failing if it isn't
+    #[clap(long = "wait")]
+    wait: bool,
+
+    /// How long to wait for the application to be deployed when using --wait
+    #[clap(long = "wait-timeout-ms", default_value_t = status::DEFAULT_WAIT_TIMEOUT_MS)]
+    wait_timeout_ms: u64,
+
     #[clap(flatten)]
-    opts: ConnectionOpts,
+    opts: WadmOpts,
+}
+
+#[derive(Args, Debug, Clone)]
+pub(crate) struct StatusCommand {
+    /// Name of the app specification
+    #[clap(name = "name")]
+    model_name: String,
+
+    /// Wait for the application to be deployed, failing if it isn't
+    #[clap(long = "wait")]
+    wait: bool,
+
+    /// How long to wait for the application to be deployed when using --wait
+    #[clap(long = "wait-timeout-ms", default_value_t = status::DEFAULT_WAIT_TIMEOUT_MS)]
+    wait_timeout_ms: u64,
+
+    #[clap(flatten)]
+    opts: WadmOpts,
 }
 
 #[derive(Args, Debug, Clone)]
@@ -85,13 +149,64 @@ pub(crate) struct DeleteCommand {
     version: String,
 
     #[clap(flatten)]
-    opts: ConnectionOpts,
+    opts: WadmOpts,
 }
 
 #[derive(Args, Debug, Clone)]
 pub(crate) struct PutCommand {
+    /// Input filename (JSON or YAML) containing app specification, `-` to read it from stdin,
+    /// or an http(s) URL to fetch it from
+    source: String,
+
+    /// Values file (JSON or YAML) to render the app specification with as a handlebars
+    /// template. Can be given multiple times, with later files taking precedence
+    #[clap(long = "values")]
+    values: Vec<PathBuf>,
+
+    /// Set a template value, overriding values files. Dotted keys set nested values, e.g.
+    /// `--set echo.replicas=3`
+    #[clap(long = "set", value_name = "KEY=VALUE")]
+    set: Vec<String>,
+
+    #[clap(flatten)]
+    opts: WadmOpts,
+}
+
+#[derive(Args, Debug, Clone)]
+pub(crate) struct ValidateCommand {
     /// Input filename (JSON or YAML) containing app specification
     source: PathBuf,
+}
+
+#[derive(Args, Debug, Clone)]
+pub(crate) struct ConvertCommand {
+    /// Path to the host manifest file (JSON or YAML) to convert
+    source: PathBuf,
+
+    /// Name of the app specification to create
+    #[clap(long = "name")]
+    model_name: String,
+
+    /// Version of the app specification to create
+    #[clap(long = "version", default_value = "v0.0.1")]
+    version: String,
+
+    /// Expand environment variables using substitution syntax within the manifest file
+    #[clap(short = 'e', long = "expand-env")]
+    expand_env: bool,
+
+    /// Look up the images of the actors and providers that links refer to in the running lattice.
+    /// Needed when links can't otherwise be matched to the components of the manifest
+    #[clap(long = "resolve")]
+    resolve: bool,
+
+    /// Format to write the app spec in, either 'yaml' or 'json'
+    #[clap(long = "format", default_value = "yaml")]
+    format: ModelFormat,
+
+    /// File to write the app spec to instead of printing it
+    #[clap(long = "output-file")]
+    output_file: Option<PathBuf>,
 
     #[clap(flatten)]
     opts: ConnectionOpts,
@@ -107,8 +222,58 @@ pub(crate) struct GetCommand {
     #[clap(name = "version")]
     version: String,
 
+    /// Format to print the app spec in, either 'yaml' or 'json'
+    #[clap(long = "format", default_value = "yaml")]
+    format: ModelFormat,
+
+    /// Write the app spec to files in this directory instead of printing it
+    #[clap(long = "output-dir")]
+    output_dir: Option<PathBuf>,
+
     #[clap(flatten)]
-    opts: ConnectionOpts,
+    opts: WadmOpts,
+}
+
+/// The format app specifications are printed in
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub(crate) enum ModelFormat {
+    #[default]
+    Yaml,
+    Json,
+}
+
+impl FromStr for ModelFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "yaml" => Ok(ModelFormat::Yaml),
+            "json" => Ok(ModelFormat::Json),
+            _ => bail!("app spec format must be 'yaml' or 'json'"),
+        }
+    }
+}
+
+#[derive(Args, Debug, Clone)]
+pub(crate) struct DiffCommand {
+    /// The name of the app spec to compare
+    #[clap(name = "name")]
+    model_name: String,
+
+    /// The version of the app spec to compare from
+    #[clap(name = "version")]
+    version: String,
+
+    /// The version of the app spec to compare to
+    #[clap(name = "other-version", required_unless_present = "file")]
+    other_version: Option<String>,
+
+    /// Input filename (JSON or YAML) containing an app specification to compare to, instead of another version
+    #[clap(long = "file", conflicts_with = "other-version")]
+    file: Option<PathBuf>,
+
+    #[clap(flatten)]
+    opts: WadmOpts,
 }
 
 #[derive(Args, Debug, Clone)]
@@ -118,7 +283,7 @@ pub(crate) struct HistoryCommand {
     model_name: String,
 
     #[clap(flatten)]
-    opts: ConnectionOpts,
+    opts: WadmOpts,
 }
 
 pub(crate) async fn handle_command(
@@ -135,9 +300,20 @@ pub(crate) async fn handle_command(
         }
         Get(cmd) => {
             sp.update_spinner_message("Querying app spec details ... ".to_string());
+            let (format, output_dir) = (cmd.format, cmd.output_dir.clone());
             let results = get_model_details(cmd).await?;
-            let (raw, vetted) = write_model(results.clone())?;
-            show_model_output(raw, vetted, results)
+            match output_dir {
+                Some(dir) => {
+                    let (raw, vetted) = write_model(&results, &dir)?;
+                    show_written_model_output(raw, vetted, results)?
+                }
+                None => show_model_output(results, format)?,
+            }
+        }
+        Diff(cmd) => {
+            sp.update_spinner_message("Comparing app spec versions ... ".to_string());
+            let results = diff_model_versions(cmd).await?;
+            show_model_diff(results)
         }
         History(cmd) => {
             sp.update_spinner_message("Querying app revision history ... ".to_string());
@@ -156,14 +332,59 @@ pub(crate) async fn handle_command(
         }
         Deploy(cmd) => {
             sp.update_spinner_message("Deploying application ... ".to_string());
+            let (wait, model_name, version) =
+                (cmd.wait, cmd.model_name.clone(), cmd.version.clone());
+            let timeout = Duration::from_millis(cmd.wait_timeout_ms);
+            let opts = cmd.opts.clone();
             let results = deploy_model(cmd).await?;
-            show_deploy_results(results)
+            if !wait {
+                show_deploy_results(results.acknowledged)
+            } else if results.acknowledged {
+                let status =
+                    status::wait_for_deployment(opts, &model_name, Some(version), timeout, &sp)
+                        .await?;
+                show_model_status(&model_name, status)
+            } else {
+                bail!(
+                    "wadm did not acknowledge the deployment of {}: {}",
+                    model_name,
+                    results
+                        .message
+                        .unwrap_or_else(|| "no reason given".to_string())
+                )
+            }
+        }
+        Status(cmd) => {
+            sp.update_spinner_message("Querying app status ... ".to_string());
+            let status = if cmd.wait {
+                status::wait_for_deployment(
+                    cmd.opts,
+                    &cmd.model_name,
+                    None,
+                    Duration::from_millis(cmd.wait_timeout_ms),
+                    &sp,
+                )
+                .await?
+            } else {
+                status::get_model_status(cmd.opts, &cmd.model_name).await?
+            };
+            show_model_status(&cmd.model_name, status)
         }
         Undeploy(cmd) => {
             sp.update_spinner_message("Undeploying application ... ".to_string());
             let results = undeploy_model(cmd).await?;
             show_undeploy_results(results)
         }
+        Validate(cmd) => {
+            let raw = std::fs::read_to_string(&cmd.source)
+                .with_context(|| format!("Failed to read {}", cmd.source.display()))?;
+            validate_model(&raw)?;
+            show_validate_results(cmd.source)
+        }
+        Convert(cmd) => {
+            sp.update_spinner_message("Converting host manifest ... ".to_string());
+            convert_host_manifest(cmd).await?
+        }
     };
     sp.finish_and_clear();
 
@@ -171,92 +392,163 @@ pub(crate) async fn handle_command(
 }
 
 async fn undeploy_model(cmd: UndeployCommand) -> Result<bool> {
-    let res = json_request(cmd.opts, &["undeploy", &cmd.model_name], json!({})).await?;
-
-    Ok(res.is_some())
+    let client = wadm_client_from_opts(cmd.opts).await?;
+    Ok(client.undeploy(&cmd.model_name).await?)
 }
 
-async fn deploy_model(cmd: DeployCommand) -> Result<bool> {
-    let res = json_request(
-        cmd.opts,
-        &["deploy", &cmd.model_name],
-        json!({
-            "version": cmd.version
-        }),
-    )
-    .await?;
+async fn deploy_model(cmd: DeployCommand) -> Result<DeployReply> {
+    let client = wadm_client_from_opts(cmd.opts).await?;
+    client
+        .deploy(&cmd.model_name, &cmd.version)
+        .await
+        .context("Failed to deploy application")
+}
 
-    if let Some(v) = res {
-        Ok(v["acknowledged"].as_bool().unwrap_or(false))
+async fn put_model(cmd: PutCommand) -> Result<PutReply> {
+    let mut raw = template::read_source(&cmd.source).await?;
+    // Specifications are only treated as templates when values are given, so that existing
+    // specifications are sent exactly as written
+    if !cmd.values.is_empty() || !cmd.set.is_empty() {
+        let values = template::load_values(&cmd.values, &cmd.set)?;
+        raw = template::render_manifest(&raw, &values)?;
+    }
+    validate_model(&raw)?;
+    let client = wadm_client_from_opts(cmd.opts).await?;
+    client
+        .put(raw.as_bytes())
+        .await
+        .context("Failed to put app specification")
+}
+
+async fn convert_host_manifest(cmd: ConvertCommand) -> Result<CommandOutput> {
+    let manifest = match HostManifest::from_path(&cmd.source, cmd.expand_env) {
+        Ok(manifest) => manifest,
+        Err(e) => bail!("Failed to load manifest: {}", e),
+    };
+    let images = if cmd.resolve {
+        lattice_images(cmd.opts).await?
     } else {
-        bail!("Failed to deploy application")
+        HashMap::new()
+    };
+    let app = convert::convert_manifest(&manifest, &cmd.model_name, &cmd.version, &images)?;
+    let rendered = match cmd.format {
+        ModelFormat::Yaml => serde_yaml::to_string(&app)?,
+        ModelFormat::Json => serde_json::to_string_pretty(&app)?,
+    };
+
+    let mut map = HashMap::new();
+    map.insert("app".to_string(), json!(app));
+    let text = match cmd.output_file {
+        Some(path) => {
+            std::fs::write(&path, &rendered)
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+            map.insert("output_file".to_string(), json!(path));
+            format!(
+                "Converted {} to app specification {} v{} in {}",
+                cmd.source.display(),
+                cmd.model_name,
+                cmd.version,
+                path.display()
+            )
+        }
+        None => rendered.trim_end().to_string(),
+    };
+    Ok(CommandOutput::new(text, map))
+}
+
+/// Maps the public keys of the actors and providers running in the lattice to their images
+async fn lattice_images(opts: ConnectionOpts) -> Result<HashMap<String, String>> {
+    let client = ctl_client_from_opts(opts, None).await?;
+    let mut images = HashMap::new();
+    for host in client.get_hosts().await.map_err(convert_error)? {
+        let inventory = client
+            .get_host_inventory(&host.id)
+            .await
+            .map_err(convert_error)?;
+        images.extend(
+            inventory
+                .actors
+                .into_iter()
+                .filter_map(|a| Some((a.id, a.image_ref?))),
+        );
+        images.extend(
+            inventory
+                .providers
+                .into_iter()
+                .filter_map(|p| Some((p.id, p.image_ref?))),
+        );
     }
+    Ok(images)
 }
 
-async fn put_model(cmd: PutCommand) -> Result<PutReply> {
-    let raw = std::fs::read_to_string(&cmd.source)?;
-    let res = raw_request(cmd.opts, &["put"], raw.as_bytes()).await?;
-    if let Some(v) = res {
-        let r: PutReply = serde_json::from_value(v)?;
-        Ok(r)
+/// Runs the offline checks on an app specification, failing with every problem found
+pub(crate) fn validate_model(raw: &str) -> Result<()> {
+    let errors = validate::validate_manifest(raw);
+    if errors.is_empty() {
+        Ok(())
     } else {
-        bail!("Failed to put app specification");
+        bail!(
+            "App specification is invalid:\n{}",
+            validate::format_errors(&errors)
+        )
     }
 }
 
 async fn get_model_history(cmd: HistoryCommand) -> Result<Vec<ModelRevision>> {
-    let res = json_request(cmd.opts, &["versions", &cmd.model_name], json!({})).await?;
-    if let Some(v) = res {
-        let revs: Vec<ModelRevision> = serde_json::from_value(v)?;
-        Ok(revs)
-    } else {
-        bail!("Failed to get model history");
-    }
+    let client = wadm_client_from_opts(cmd.opts).await?;
+    client
+        .history(&cmd.model_name)
+        .await
+        .context("Failed to get model history")
 }
 
 async fn get_model_details(cmd: GetCommand) -> Result<ModelDetails> {
-    let res = json_request(
-        cmd.opts,
-        &["get", &cmd.model_name],
-        json!({
-            "version": cmd.version
-        }),
-    )
+    let client = wadm_client_from_opts(cmd.opts).await?;
+    Ok(client.get(&cmd.model_name, &cmd.version).await?)
+}
+
+async fn diff_model_versions(cmd: DiffCommand) -> Result<diff::ModelDiff> {
+    let old = get_model_details(GetCommand {
+        model_name: cmd.model_name.clone(),
+        version: cmd.version.clone(),
+        format: ModelFormat::default(),
+        output_dir: None,
+        opts: cmd.opts.clone(),
+    })
     .await?;
-    if let Some(v) = res {
-        let md: ModelDetails = serde_json::from_value(v)?;
-        Ok(md)
-    } else {
-        bail!("Failed to obtain reply from wadm");
-    }
+    let old = diff::parse_model(&old.raw)?;
+
+    let (new, to) = match (cmd.file, cmd.other_version) {
+        (Some(file), _) => {
+            let raw = std::fs::read_to_string(&file)
+                .with_context(|| format!("Failed to read {}", file.display()))?;
+            (diff::parse_model(&raw)?, file.display().to_string())
+        }
+        (None, Some(version)) => {
+            let new = get_model_details(GetCommand {
+                model_name: cmd.model_name,
+                version: version.clone(),
+                format: ModelFormat::default(),
+                output_dir: None,
+                opts: cmd.opts,
+            })
+            .await?;
+            (diff::parse_model(&new.raw)?, version)
+        }
+        (None, None) => bail!("Either a second version or --file is required"),
+    };
+
+    diff::diff_models(&old, &new, &cmd.version, &to)
 }
 
 async fn delete_model_version(cmd: DeleteCommand) -> Result<bool> {
-    let res = json_request(
-        cmd.opts,
-        &["del", &cmd.model_name],
-        json!({
-            "version": cmd.version
-        }),
-    )
-    .await?;
-
-    if res.is_none() {
-        Ok(false)
-    } else {
-        Ok(true)
-    }
+    let client = wadm_client_from_opts(cmd.opts).await?;
+    Ok(client.del(&cmd.model_name, &cmd.version).await?)
 }
 
 async fn get_models(cmd: ListCommand) -> Result<Vec<ModelSummary>> {
-    let res = json_request(cmd.opts, &["list"], json!({})).await?;
-
-    if let Some(v) = res {
-        let v: Vec<ModelSummary> = serde_json::from_value(v)?;
-        Ok(v)
-    } else {
-        bail!("Failed to obtain reply from wadm");
-    }
+    let client = wadm_client_from_opts(cmd.opts).await?;
+    Ok(client.list().await?)
 }
 
 fn list_models_output(results: Vec<ModelSummary>) -> CommandOutput {
@@ -265,10 +557,48 @@ fn list_models_output(results: Vec<ModelSummary>) -> CommandOutput {
     CommandOutput::new(output::list_models_table(results), map)
 }
 
-fn show_model_output(raw: PathBuf, vetted: PathBuf, md: ModelDetails) -> CommandOutput {
+fn show_model_output(md: ModelDetails, format: ModelFormat) -> Result<CommandOutput> {
+    let model = diff::parse_model(&md.raw)?;
+    let rendered = match format {
+        ModelFormat::Yaml => serde_yaml::to_string(&model)?,
+        ModelFormat::Json => serde_json::to_string_pretty(&model)?,
+    };
     let mut map = HashMap::new();
     map.insert("model".to_string(), json!(md));
-    CommandOutput::new(output::show_model_details(raw, vetted), map)
+    Ok(CommandOutput::new(
+        format!(
+            "{}\n\n{}",
+            output::model_summary_table(&model),
+            rendered.trim_end()
+        ),
+        map,
+    ))
+}
+
+fn show_written_model_output(
+    raw: PathBuf,
+    vetted: PathBuf,
+    md: ModelDetails,
+) -> Result<CommandOutput> {
+    let model = diff::parse_model(&md.raw)?;
+    let mut map = HashMap::new();
+    map.insert("model".to_string(), json!(md));
+    map.insert("raw_file".to_string(), json!(raw));
+    map.insert("json_file".to_string(), json!(vetted));
+    Ok(CommandOutput::new(
+        format!(
+            "{}\n\n{}",
+            output::model_summary_table(&model),
+            output::show_model_files(raw, vetted)
+        ),
+        map,
+    ))
+}
+
+fn show_model_diff(results: diff::ModelDiff) -> CommandOutput {
+    let mut map = HashMap::new();
+    map.insert("diff".to_string(), json!(results));
+    CommandOutput::new(output::model_diff_text(&results), map)
 }
 
 fn show_put_results(results: PutReply) -> CommandOutput {
@@ -283,6 +613,15 @@ fn show_put_results(results: PutReply) -> CommandOutput {
     )
 }
 
+fn show_validate_results(source: PathBuf) -> CommandOutput {
+    let mut map = HashMap::new();
+    map.insert("valid".to_string(), json!(true));
+    CommandOutput::new(
+        format!("App specification {} is valid", source.display()),
+        map,
+    )
+}
+
 fn show_undeploy_results(results: bool) -> CommandOutput {
     let mut map = HashMap::new();
     map.insert("results".to_string(), json!(results));
@@ -322,145 +661,39 @@ fn show_deploy_results(results: bool) -> CommandOutput {
     )
 }
 
+fn show_model_status(model_name: &str, status: ModelStatus) -> CommandOutput {
+    let mut map = HashMap::new();
+    map.insert("status".to_string(), json!(status));
+    CommandOutput::new(output::model_status_table(model_name, &status), map)
+}
+
 fn show_model_history(results: Vec<ModelRevision>) -> CommandOutput {
     let mut map = HashMap::new();
     map.insert("revisions".to_string(), json!(results));
     CommandOutput::new(output::list_revisions_table(results), map)
 }
 
-fn write_model(model: ModelDetails) -> Result<(PathBuf, PathBuf)> {
+/// Writes the raw and vetted versions of a model to `<name>_v<version>.txt` and
+/// `<name>_v<version>.json` in the given directory, creating it if needed
+fn write_model(model: &ModelDetails, dir: &Path) -> Result<(PathBuf, PathBuf)> {
     let name = model.vetted["name"].as_str().unwrap_or("");
     let version = model.vetted["version"].as_str().unwrap_or("");
-    let json_filename = format!("{}_v{}.json", name, version);
-    let raw_filename = format!("{}_v{}.txt", name, version);
+    std::fs::create_dir_all(dir)
+        .with_context(|| format!("Failed to create directory {}", dir.display()))?;
 
-    let json_buf = PathBuf::from(json_filename);
-    let raw_buf = PathBuf::from(raw_filename);
-    let _ = std::fs::write(&json_buf, serde_json::to_vec(&model.vetted).unwrap());
-    let _ = std::fs::write(&raw_buf, model.raw);
+    let json_buf = dir.join(format!("{}_v{}.json", name, version));
+    let raw_buf = dir.join(format!("{}_v{}.txt", name, version));
+    std::fs::write(&json_buf, serde_json::to_vec(&model.vetted)?)
+        .with_context(|| format!("Failed to write {}", json_buf.display()))?;
+    std::fs::write(&raw_buf, &model.raw)
+        .with_context(|| format!("Failed to write {}", raw_buf.display()))?;
 
     Ok((raw_buf, json_buf))
 }
 
-async fn nats_client_from_opts(opts: ConnectionOpts) -> Result<(Client, Duration)> {
-    // Attempt to load a context, falling back on the default if not supplied
-    let ctx = if let Some(context) = opts.context {
-        Some(load_context(context)?)
-    } else if let Ok(ctx_dir) = context_dir(None) {
-        let ctx_dir = ContextDir::new(ctx_dir)?;
-        ensure_host_config_context(&ctx_dir)?;
-        Some(ctx_dir.load_default_context()?)
-    } else {
-        None
-    };
-
-    let ctl_host = opts.ctl_host.unwrap_or_else(|| {
-        ctx.as_ref()
-            .map(|c| c.ctl_host.clone())
-            .unwrap_or_else(|| DEFAULT_NATS_HOST.to_string())
-    });
-
-    let ctl_port = opts.ctl_port.unwrap_or_else(|| {
-        ctx.as_ref()
-            .map(|c| c.ctl_port.to_string())
-            .unwrap_or_else(|| DEFAULT_NATS_PORT.to_string())
-    });
-
-    let ctl_jwt = if opts.ctl_jwt.is_some() {
-        opts.ctl_jwt
-    } else {
-        ctx.as_ref().map(|c| c.ctl_jwt.clone()).unwrap_or_default()
-    };
-
-    let ctl_seed = if opts.ctl_seed.is_some() {
-        opts.ctl_seed
-    } else {
-        ctx.as_ref().map(|c| c.ctl_seed.clone()).unwrap_or_default()
-    };
-
-    let ctl_credsfile = if opts.ctl_credsfile.is_some() {
-        opts.ctl_credsfile
-    } else {
-        ctx.as_ref()
-            .map(|c| c.ctl_credsfile.clone())
-            .unwrap_or_default()
-    };
-
-    let nc =
-        crate::util::nats_client_from_opts(&ctl_host, &ctl_port, ctl_jwt, ctl_seed, ctl_credsfile)
-            .await?;
-
-    let timeout = Duration::from_millis(opts.timeout_ms);
-
-    Ok((nc, timeout))
-}
-
-fn generate_topic(prefix: Option<String>, elements: &[&str]) -> String {
-    let prefix = prefix.unwrap_or_else(|| "default".to_string());
-    format!("wadm.api.{}.model.{}", prefix, elements.join("."))
-}
-
-#[derive(Serialize, Deserialize, Debug, Clone)]
-pub(crate) struct ModelSummary {
-    pub name: String,
-    pub version: String,
-    pub description: String,
-    pub deployment_status: String,
-}
-
-#[derive(Serialize, Deserialize, Debug, Clone)]
-pub(crate) struct ModelRevision {
-    pub version: String,
-    pub created: String,
-    pub deployed: bool,
-}
-
-#[derive(Serialize, Deserialize, Debug, Clone)]
-struct WadmEnvelope {
-    pub result: String,
-    pub message: Option<String>,
-    pub data: Option<serde_json::Value>,
-}
-
-#[derive(Serialize, Deserialize, Debug, Clone)]
-pub(crate) struct ModelDetails {
-    pub raw: String,
-    pub vetted: serde_json::Value,
-}
-
-#[derive(Serialize, Deserialize, Debug, Clone)]
-pub(crate) struct PutReply {
-    pub current_version: String,
-    pub name: String,
-}
-
-async fn raw_request(
-    opts: ConnectionOpts,
-    elements: &[&str],
-    req: &[u8],
-) -> Result<Option<serde_json::Value>> {
-    let (nc, timeout) = nats_client_from_opts(opts.clone()).await?;
-    let topic = generate_topic(opts.lattice_prefix, elements);
-
-    match tokio::time::timeout(timeout, nc.request(topic, req.to_vec().into())).await {
-        Ok(Ok(res)) => {
-            let env: WadmEnvelope = serde_json::from_slice(&res.payload)?;
-            if env.result == "success" {
-                Ok(env.data)
-            } else {
-                bail!("{}", env.message.unwrap_or_default())
-            }
-        }
-        Ok(Err(e)) => bail!("Error making message request: {}", e),
-        Err(e) => bail!("Request timed out:  {}", e),
-    }
-}
-
-async fn json_request(
-    opts: ConnectionOpts,
-    elements: &[&str],
-    req: serde_json::Value,
-) -> Result<Option<serde_json::Value>> {
-    let msg = serde_json::to_vec(&req)?;
-    raw_request(opts, elements, &msg).await
+pub(crate) async fn wadm_client_from_opts(opts: WadmOpts) -> Result<WadmClient> {
+    let timeout = Duration::from_millis(opts.connection.timeout_ms);
+    let (nc, lattice_prefix) = nats_client_from_opts(opts.connection).await?;
+    Ok(WadmClient::new(nc, Some(lattice_prefix), timeout)
+        .with_retries(opts.retries, Duration::from_millis(opts.retry_backoff_ms)))
 }
@@ -1,19 +1,120 @@
 use std::path::PathBuf;
 
+use console::style;
+use serde_yaml::Value;
 use term_table::{
     row::Row,
     table_cell::{Alignment, TableCell},
     Table,
 };
+use wash_lib::app::{ModelRevision, ModelStatus, ModelSummary};
 
-use super::{ModelRevision, ModelSummary};
+use super::diff::{ComponentDiff, ModelDiff};
 
-pub(crate) fn show_model_details(raw: PathBuf, vetted: PathBuf) -> String {
-    // TODO: in a forthcoming release, do something more meaningful than just
-    // dump the file paths
+pub(crate) fn show_model_files(raw: PathBuf, vetted: PathBuf) -> String {
     format!(
-        "Wrote model details to files:\nRaw: {:?}\nJSON: {:?}",
-        raw, vetted
+        "Wrote model details to files:\nRaw: {}\nJSON: {}",
+        raw.display(),
+        vetted.display()
+    )
+}
+
+/// A summary of a component of an OAM app specification, as shown by `wash app get`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct ComponentSummary {
+    name: String,
+    component_type: String,
+    image: String,
+    traits: Vec<String>,
+    links: Vec<String>,
+}
+
+fn summarize_components(model: &Value) -> Vec<ComponentSummary> {
+    let components = match model.get("spec").and_then(|s| s.get("components")) {
+        Some(Value::Sequence(components)) => components,
+        _ => return Vec::new(),
+    };
+    let str_field = |value: &Value, key: &str| {
+        value
+            .get(key)
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string()
+    };
+
+    components
+        .iter()
+        .map(|component| {
+            let mut summary = ComponentSummary {
+                name: str_field(component, "name"),
+                component_type: str_field(component, "type"),
+                image: component
+                    .get("properties")
+                    .map(|p| str_field(p, "image"))
+                    .unwrap_or_default(),
+                ..Default::default()
+            };
+            let traits = component.get("traits").and_then(Value::as_sequence);
+            for component_trait in traits.into_iter().flatten() {
+                let properties = component_trait.get("properties");
+                match component_trait.get("type").and_then(Value::as_str) {
+                    Some("linkdef") => summary.links.push(
+                        properties
+                            .map(|p| str_field(p, "target"))
+                            .unwrap_or_default(),
+                    ),
+                    Some("spreadscaler") => {
+                        let replicas = properties
+                            .and_then(|p| p.get("replicas"))
+                            .and_then(Value::as_u64);
+                        summary.traits.push(match replicas {
+                            Some(replicas) => format!("spreadscaler ({} replicas)", replicas),
+                            None => "spreadscaler".to_string(),
+                        });
+                    }
+                    Some(other) => summary.traits.push(other.to_string()),
+                    None => {}
+                }
+            }
+            summary
+        })
+        .collect()
+}
+
+pub(crate) fn model_summary_table(model: &Value) -> String {
+    let mut table = Table::new();
+    crate::util::configure_table_style(&mut table);
+
+    table.add_row(Row::new(vec![
+        TableCell::new_with_alignment("Component", 1, Alignment::Left),
+        TableCell::new_with_alignment("Type", 1, Alignment::Left),
+        TableCell::new_with_alignment("Image", 1, Alignment::Left),
+        TableCell::new_with_alignment("Traits", 1, Alignment::Left),
+        TableCell::new_with_alignment("Links", 1, Alignment::Left),
+    ]));
+    summarize_components(model).into_iter().for_each(|c| {
+        table.add_row(Row::new(vec![
+            TableCell::new_with_alignment(c.name, 1, Alignment::Left),
+            TableCell::new_with_alignment(c.component_type, 1, Alignment::Left),
+            TableCell::new_with_alignment(c.image, 1, Alignment::Left),
+            TableCell::new_with_alignment(c.traits.join(", "), 1, Alignment::Left),
+            TableCell::new_with_alignment(c.links.join(", "), 1, Alignment::Left),
+        ]))
+    });
+
+    let metadata = model.get("metadata");
+    format!(
+        "App {} v{}\n\n{}",
+        metadata
+            .and_then(|m| m.get("name"))
+            .and_then(Value::as_str)
+            .unwrap_or_default(),
+        metadata
+            .and_then(|m| m.get("annotations"))
+            .and_then(|a| a.get("version"))
+            .and_then(Value::as_str)
+            .unwrap_or_default(),
+        table.render()
     )
 }
 
@@ -59,3 +160,138 @@ pub(crate) fn list_models_table(models: Vec<ModelSummary>) -> String {
 
     table.render()
 }
+
+pub(crate) fn model_diff_text(diff: &ModelDiff) -> String {
+    let mut lines = vec![format!(
+        "Comparing {} {} to {}",
+        diff.name, diff.from, diff.to
+    )];
+    if diff.components.is_empty() {
+        lines.push("No differences found".to_string());
+    }
+    for component in &diff.components {
+        match component {
+            ComponentDiff::Added {
+                name,
+                component_type,
+                image,
+            } => lines.push(
+                style(format!("+ {} ({} {})", name, component_type, image))
+                    .green()
+                    .to_string(),
+            ),
+            ComponentDiff::Removed {
+                name,
+                component_type,
+                image,
+            } => lines.push(
+                style(format!("- {} ({} {})", name, component_type, image))
+                    .red()
+                    .to_string(),
+            ),
+            ComponentDiff::Changed { name, changes } => {
+                lines.push(style(format!("~ {}", name)).yellow().to_string());
+                for change in changes {
+                    let old = change.old.as_deref().unwrap_or("(none)");
+                    let new = change.new.as_deref().unwrap_or("(none)");
+                    lines.push(format!(
+                        "    {}: {} -> {}",
+                        change.field,
+                        style(old).red(),
+                        style(new).green()
+                    ));
+                }
+            }
+        }
+    }
+    lines.join("\n")
+}
+
+pub(crate) fn model_status_table(name: &str, status: &ModelStatus) -> String {
+    let mut table = Table::new();
+    crate::util::configure_table_style(&mut table);
+
+    table.add_row(Row::new(vec![
+        TableCell::new_with_alignment("Component", 1, Alignment::Left),
+        TableCell::new_with_alignment("Type", 1, Alignment::Left),
+        TableCell::new_with_alignment("Status", 1, Alignment::Left),
+        TableCell::new_with_alignment("Message", 1, Alignment::Left),
+    ]));
+    status.components.iter().for_each(|c| {
+        table.add_row(Row::new(vec![
+            TableCell::new_with_alignment(c.name.clone(), 1, Alignment::Left),
+            TableCell::new_with_alignment(c.component_type.clone(), 1, Alignment::Left),
+            TableCell::new_with_alignment(c.status, 1, Alignment::Left),
+            TableCell::new_with_alignment(
+                c.message.clone().unwrap_or_default(),
+                1,
+                Alignment::Left,
+            ),
+        ]))
+    });
+
+    let mut summary = format!("App {} v{} is {}", name, status.version, status.status);
+    if let Some(message) = &status.message {
+        summary.push_str(&format!(": {}", message));
+    }
+    format!("{}\n\n{}", summary, table.render())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{summarize_components, ComponentSummary};
+    use crate::app::diff::parse_model;
+
+    #[test]
+    fn test_summarize_components() {
+        let model = parse_model(
+            r#"apiVersion: core.oam.dev/v1beta1
+kind: Application
+metadata:
+  name: echo
+  annotations:
+    version: v0.0.1
+spec:
+  components:
+    - name: echo
+      type: actor
+      properties:
+        image: wasmcloud.azurecr.io/echo:0.3.7
+      traits:
+        - type: spreadscaler
+          properties:
+            replicas: 2
+        - type: linkdef
+          properties:
+            target: httpserver
+            values:
+              address: 0.0.0.0:8080
+    - name: httpserver
+      type: capability
+      properties:
+        image: wasmcloud.azurecr.io/httpserver:0.17.0
+        contract: wasmcloud:httpserver
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            summarize_components(&model),
+            vec![
+                ComponentSummary {
+                    name: "echo".to_string(),
+                    component_type: "actor".to_string(),
+                    image: "wasmcloud.azurecr.io/echo:0.3.7".to_string(),
+                    traits: vec!["spreadscaler (2 replicas)".to_string()],
+                    links: vec!["httpserver".to_string()],
+                },
+                ComponentSummary {
+                    name: "httpserver".to_string(),
+                    component_type: "capability".to_string(),
+                    image: "wasmcloud.azurecr.io/httpserver:0.17.0".to_string(),
+                    ..Default::default()
+                },
+            ]
+        );
+    }
+}
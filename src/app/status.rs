@@ -0,0 +1,237 @@
+use std::{collections::BTreeMap, time::Duration};
+
+use anyhow::{bail, Context, Result};
+use serde_yaml::Value;
+use tokio::time::Instant;
+use wash_lib::app::{ModelStatus, StatusType};
+
+use crate::{
+    appearance::spinner::Spinner,
+    ctl::{ctl_client_from_opts, wait::get_wasmbus_event_info},
+    util::convert_error,
+};
+
+use super::{diff::parse_model, wadm_client_from_opts, WadmOpts};
+
+/// How long to wait for an app to deploy, unless otherwise specified
+pub(crate) const DEFAULT_WAIT_TIMEOUT_MS: u64 = 60_000;
+/// How often wadm is asked for the status of an app while waiting for it to deploy
+const STATUS_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// What the lattice events seen so far say about a component of the app being deployed
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct ComponentProgress {
+    image: String,
+    started: usize,
+    error: Option<String>,
+}
+
+pub(crate) async fn get_model_status(opts: WadmOpts, model_name: &str) -> Result<ModelStatus> {
+    let client = wadm_client_from_opts(opts).await?;
+    client
+        .status(model_name)
+        .await
+        .context("Failed to obtain app status from wadm")
+}
+
+/// Polls wadm for the status of an app, and follows the lattice events for its components,
+/// until wadm reports the app as deployed or failed, or the timeout passes. Fails unless the
+/// app was deployed. When no version is given, the version wadm reports is waited on
+pub(crate) async fn wait_for_deployment(
+    opts: WadmOpts,
+    model_name: &str,
+    version: Option<String>,
+    timeout: Duration,
+    sp: &Spinner,
+) -> Result<ModelStatus> {
+    let deadline = Instant::now() + timeout;
+    let ctl_client = ctl_client_from_opts(opts.connection.clone(), None).await?;
+    let mut receiver = ctl_client.events_receiver().await.map_err(convert_error)?;
+    let client = wadm_client_from_opts(opts).await?;
+
+    let version = match version {
+        Some(version) => version,
+        None => client.status(model_name).await?.version,
+    };
+    let details = client.get(model_name, &version).await?;
+    let mut progress = component_images(&parse_model(&details.raw)?)
+        .into_iter()
+        .map(|(name, image)| {
+            (
+                name,
+                ComponentProgress {
+                    image,
+                    ..Default::default()
+                },
+            )
+        })
+        .collect::<BTreeMap<_, _>>();
+
+    let mut poll = tokio::time::interval(STATUS_POLL_INTERVAL);
+    loop {
+        sp.update_spinner_message(format!(
+            "Waiting for {} v{} to deploy: {}",
+            model_name,
+            version,
+            progress_summary(&progress)
+        ));
+        tokio::select! {
+            _ = poll.tick() => {
+                let status = client.status(model_name).await?;
+                if !status.version.is_empty() && status.version != version {
+                    continue;
+                }
+                match status.status {
+                    StatusType::Deployed => return Ok(status),
+                    StatusType::Failed => bail!(
+                        "App {} v{} failed to deploy: {}",
+                        model_name,
+                        version,
+                        status
+                            .message
+                            .unwrap_or_else(|| progress_summary(&progress))
+                    ),
+                    _ => {}
+                }
+            }
+            Some(event) = receiver.recv() => {
+                if let Ok(event) = get_wasmbus_event_info(event) {
+                    record_event(&mut progress, &event.event_type, &event.data);
+                }
+            }
+            _ = tokio::time::sleep_until(deadline) => bail!(
+                "Timed out after {}s waiting for {} v{} to deploy: {}",
+                timeout.as_secs(),
+                model_name,
+                version,
+                progress_summary(&progress)
+            ),
+        }
+    }
+}
+
+/// Updates the progress of the components whose image an actor or provider event refers to
+fn record_event(
+    progress: &mut BTreeMap<String, ComponentProgress>,
+    event_type: &str,
+    data: &serde_json::Value,
+) {
+    let (image_key, started) = match event_type {
+        "com.wasmcloud.lattice.actor_started" | "com.wasmcloud.lattice.provider_started" => {
+            ("image_ref", true)
+        }
+        "com.wasmcloud.lattice.actor_start_failed" => ("actor_ref", false),
+        "com.wasmcloud.lattice.provider_start_failed" => ("provider_ref", false),
+        _ => return,
+    };
+    let image = match data.get(image_key).and_then(|i| i.as_str()) {
+        Some(image) => image,
+        None => return,
+    };
+    for component in progress.values_mut().filter(|c| c.image == image) {
+        if started {
+            component.started += 1;
+            component.error = None;
+        } else {
+            component.error = data
+                .get("error")
+                .and_then(|e| e.as_str())
+                .map(String::from)
+                .or_else(|| Some("failed to start".to_string()));
+        }
+    }
+}
+
+fn progress_summary(progress: &BTreeMap<String, ComponentProgress>) -> String {
+    if progress.is_empty() {
+        return "reconciling".to_string();
+    }
+    progress
+        .iter()
+        .map(
+            |(name, component)| match (&component.error, component.started) {
+                (Some(error), _) => format!("{} failed ({})", name, error),
+                (None, 0) => format!("{} pending", name),
+                (None, started) => format!("{} started ({})", name, started),
+            },
+        )
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// The image of each component of an OAM app specification, by component name
+fn component_images(model: &Value) -> BTreeMap<String, String> {
+    model
+        .get("spec")
+        .and_then(|s| s.get("components"))
+        .and_then(Value::as_sequence)
+        .map(|components| {
+            components
+                .iter()
+                .filter_map(|c| {
+                    let name = c.get("name")?.as_str()?;
+                    let image = c.get("properties")?.get("image")?.as_str()?;
+                    Some((name.to_string(), image.to_string()))
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod test {
+    use super::{progress_summary, record_event, ComponentProgress, ModelStatus, StatusType};
+    use serde_json::json;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_parse_model_status() {
+        let status: ModelStatus = serde_json::from_value(json!({
+            "version": "v0.0.2",
+            "status": "reconciling",
+            "components": [
+                { "name": "echo", "type": "actor", "status": "deployed" },
+                { "name": "httpserver", "type": "capability", "status": "something_new" }
+            ]
+        }))
+        .unwrap();
+        assert_eq!(status.status, StatusType::Reconciling);
+        assert_eq!(status.components[0].status, StatusType::Deployed);
+        assert_eq!(status.components[1].status, StatusType::Unknown);
+    }
+
+    #[test]
+    fn test_record_events() {
+        let mut progress = BTreeMap::new();
+        for (name, image) in [
+            ("echo", "wasmcloud.azurecr.io/echo:0.3.7"),
+            ("httpserver", "wasmcloud.azurecr.io/httpserver:0.17.0"),
+        ] {
+            progress.insert(
+                name.to_string(),
+                ComponentProgress {
+                    image: image.to_string(),
+                    ..Default::default()
+                },
+            );
+        }
+
+        record_event(
+            &mut progress,
+            "com.wasmcloud.lattice.actor_started",
+            &json!({ "image_ref": "wasmcloud.azurecr.io/echo:0.3.7" }),
+        );
+        record_event(
+            &mut progress,
+            "com.wasmcloud.lattice.provider_start_failed",
+            &json!({
+                "provider_ref": "wasmcloud.azurecr.io/httpserver:0.17.0",
+                "error": "port in use"
+            }),
+        );
+        assert_eq!(
+            progress_summary(&progress),
+            "echo started (1), httpserver failed (port in use)"
+        );
+    }
+}
@@ -0,0 +1,174 @@
+use std::{io::Read, path::PathBuf};
+
+use anyhow::{anyhow, bail, Context, Result};
+use serde_json::{Map, Value};
+use weld_codegen::render::{RenderConfig, Renderer};
+
+/// Reads an app specification from a file, from stdin if the source is `-`, or from an
+/// http(s) URL
+pub(crate) async fn read_source(source: &str) -> Result<String> {
+    if source == "-" {
+        let mut buf = String::new();
+        std::io::stdin()
+            .read_to_string(&mut buf)
+            .context("Failed to read app specification from stdin")?;
+        Ok(buf)
+    } else if source.starts_with("http://") || source.starts_with("https://") {
+        let resp = reqwest::get(source)
+            .await
+            .with_context(|| format!("Failed to fetch {}", source))?;
+        if !resp.status().is_success() {
+            bail!("Failed to fetch {}: {}", source, resp.status());
+        }
+        resp.text()
+            .await
+            .with_context(|| format!("Failed to read {}", source))
+    } else {
+        std::fs::read_to_string(source).with_context(|| format!("Failed to read {}", source))
+    }
+}
+
+/// Loads the values used to render an app specification template. Values files (JSON or YAML)
+/// are merged in order, and then each `key=value` override is applied. Dotted keys, like
+/// `echo.replicas=3`, set nested values
+pub(crate) fn load_values(files: &[PathBuf], overrides: &[String]) -> Result<Value> {
+    let mut values = Value::Object(Map::new());
+    for file in files {
+        let contents = std::fs::read_to_string(file)
+            .with_context(|| format!("Failed to read values file {}", file.display()))?;
+        let file_values: Value = serde_yaml::from_str(&contents)
+            .with_context(|| format!("Failed to parse values file {}", file.display()))?;
+        if !file_values.is_object() {
+            bail!("Values file {} must contain a mapping", file.display());
+        }
+        merge(&mut values, file_values);
+    }
+    for item in overrides {
+        let (key, value) = parse_override(item)?;
+        let mut nested = value;
+        for part in key.rsplit('.') {
+            nested = Value::Object(Map::from_iter([(part.to_string(), nested)]));
+        }
+        merge(&mut values, nested);
+    }
+    Ok(values)
+}
+
+/// Renders an app specification template with handlebars. Referencing a value that wasn't
+/// provided is an error, so that a typo can't silently produce an empty field
+pub(crate) fn render_manifest(template: &str, values: &Value) -> Result<String> {
+    let renderer = Renderer::init(&RenderConfig {
+        strict_mode: true,
+        ..Default::default()
+    })
+    .map_err(|e| anyhow!("Failed to initialize template renderer: {}", e))?;
+    renderer
+        .render_template(template, values)
+        .map_err(|e| anyhow!("Failed to render app specification: {}", e))
+}
+
+/// Parses a `key=value` override. Booleans and integers keep their type, so that they can be
+/// used in conditionals; anything else (like an image tag such as `1.10`) stays a string
+fn parse_override(item: &str) -> Result<(&str, Value)> {
+    let (key, value) = item
+        .split_once('=')
+        .with_context(|| format!("Invalid value '{}', expected key=value", item))?;
+    if key.is_empty() || key.split('.').any(str::is_empty) {
+        bail!("Invalid key in '{}'", item);
+    }
+    let value = match (value, value.parse::<i64>()) {
+        ("true", _) => Value::Bool(true),
+        ("false", _) => Value::Bool(false),
+        (raw, Ok(number)) if number.to_string() == raw => Value::from(number),
+        (raw, _) => Value::String(raw.to_string()),
+    };
+    Ok((key, value))
+}
+
+/// Recursively merges `overlay` into `base`, replacing anything that isn't a mapping
+fn merge(base: &mut Value, overlay: Value) {
+    match (base, overlay) {
+        (Value::Object(base), Value::Object(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(&key) {
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{load_values, render_manifest};
+    use serde_json::json;
+    use std::io::Write;
+
+    const TEMPLATE: &str = r#"apiVersion: core.oam.dev/v1beta1
+kind: Application
+metadata:
+  name: echo
+  annotations:
+    version: {{version}}
+spec:
+  components:
+    - name: echo
+      type: actor
+      properties:
+        image: wasmcloud.azurecr.io/echo:{{echo.tag}}
+      traits:
+        - type: spreadscaler
+          properties:
+            replicas: {{echo.replicas}}
+"#;
+
+    #[test]
+    fn test_load_values() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        write!(
+            file,
+            "version: v0.0.1\necho:\n  tag: 0.3.7\n  replicas: 1\n  debug: false\n"
+        )
+        .unwrap();
+
+        let values = load_values(
+            &[file.path().to_path_buf()],
+            &[
+                "echo.replicas=3".to_string(),
+                "echo.tag=1.10".to_string(),
+                "region=us-east=1".to_string(),
+            ],
+        )
+        .unwrap();
+        assert_eq!(
+            values,
+            json!({
+                "version": "v0.0.1",
+                "echo": { "tag": "1.10", "replicas": 3, "debug": false },
+                "region": "us-east=1"
+            })
+        );
+
+        assert!(load_values(&[], &["replicas".to_string()]).is_err());
+        assert!(load_values(&[], &["echo..tag=1".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_render_manifest() {
+        let rendered = render_manifest(
+            TEMPLATE,
+            &json!({ "version": "v0.0.2", "echo": { "tag": "0.3.8", "replicas": 3 } }),
+        )
+        .unwrap();
+        assert!(rendered.contains("version: v0.0.2"));
+        assert!(rendered.contains("image: wasmcloud.azurecr.io/echo:0.3.8"));
+        assert!(rendered.contains("replicas: 3"));
+
+        let missing = render_manifest(TEMPLATE, &json!({ "version": "v0.0.2" }));
+        assert!(missing.is_err());
+    }
+}
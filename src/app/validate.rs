@@ -0,0 +1,530 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    str::FromStr,
+};
+
+use oci_distribution::Reference;
+use serde::Serialize;
+use serde_yaml::Value;
+use yaml_rust::{
+    parser::{Event, MarkedEventReceiver, Parser},
+    scanner::Marker,
+};
+
+/// The OAM API version supported by wadm
+const OAM_API_VERSION: &str = "core.oam.dev/v1beta1";
+const OAM_KIND: &str = "Application";
+/// The annotation wadm uses to version app specifications
+const VERSION_ANNOTATION: &str = "version";
+
+const ACTOR_COMPONENT: &str = "actor";
+const CAPABILITY_COMPONENT: &str = "capability";
+const SPREADSCALER_TRAIT: &str = "spreadscaler";
+const LINKDEF_TRAIT: &str = "linkdef";
+
+/// A problem found in an app manifest, along with where it was found
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub(crate) struct ValidationError {
+    /// Line of the manifest the problem was found on, if it could be located
+    pub line: Option<usize>,
+    /// Path to the offending field, e.g. `spec.components[0].properties.image`
+    pub path: String,
+    pub message: String,
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.line, self.path.is_empty()) {
+            (Some(line), false) => write!(f, "line {} ({}): {}", line, self.path, self.message),
+            (Some(line), true) => write!(f, "line {}: {}", line, self.message),
+            (None, false) => write!(f, "{}: {}", self.path, self.message),
+            (None, true) => write!(f, "{}", self.message),
+        }
+    }
+}
+
+/// Checks, without contacting wadm, that the contents of a JSON or YAML file are a well formed
+/// OAM application that wadm can deploy. Returns every problem found, or an empty list if the
+/// manifest is valid
+pub(crate) fn validate_manifest(contents: &str) -> Vec<ValidationError> {
+    let doc: Value = match serde_yaml::from_str(contents) {
+        Ok(doc) => doc,
+        Err(e) => {
+            return vec![ValidationError {
+                line: e.location().map(|l| l.line()),
+                path: String::new(),
+                message: format!("Failed to parse manifest: {}", e),
+            }]
+        }
+    };
+
+    let mut validator = Validator {
+        lines: LineIndex::new(contents),
+        errors: Vec::new(),
+    };
+    validator.validate(&doc);
+    validator.errors
+}
+
+/// Formats validation errors as a single message, one problem per line
+pub(crate) fn format_errors(errors: &[ValidationError]) -> String {
+    errors
+        .iter()
+        .map(|e| format!("  {}", e))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+struct Validator {
+    lines: LineIndex,
+    errors: Vec<ValidationError>,
+}
+
+impl Validator {
+    fn error(&mut self, path: &str, message: impl Into<String>) {
+        self.errors.push(ValidationError {
+            line: self.lines.line_of(path),
+            path: path.to_string(),
+            message: message.into(),
+        });
+    }
+
+    /// Gets a required string field, reporting an error if it is missing or not a string
+    fn required_str<'a>(&mut self, value: &'a Value, parent: &str, key: &str) -> Option<&'a str> {
+        let path = join(parent, key);
+        match value.get(key) {
+            Some(Value::String(s)) if !s.trim().is_empty() => Some(s),
+            Some(Value::String(_)) => {
+                self.error(&path, format!("`{}` cannot be empty", key));
+                None
+            }
+            Some(_) => {
+                self.error(&path, format!("`{}` must be a string", key));
+                None
+            }
+            None => {
+                self.error(parent, format!("missing required field `{}`", key));
+                None
+            }
+        }
+    }
+
+    fn validate(&mut self, doc: &Value) {
+        if !doc.is_mapping() {
+            self.error("", "manifest must be a mapping");
+            return;
+        }
+
+        if let Some(api_version) = self.required_str(doc, "", "apiVersion") {
+            if api_version != OAM_API_VERSION {
+                self.error(
+                    "apiVersion",
+                    format!(
+                        "unsupported apiVersion `{}`, expected `{}`",
+                        api_version, OAM_API_VERSION
+                    ),
+                );
+            }
+        }
+        if let Some(kind) = self.required_str(doc, "", "kind") {
+            if kind != OAM_KIND {
+                self.error(
+                    "kind",
+                    format!("unsupported kind `{}`, expected `{}`", kind, OAM_KIND),
+                );
+            }
+        }
+
+        match doc.get("metadata") {
+            Some(metadata) if metadata.is_mapping() => self.validate_metadata(metadata),
+            Some(_) => self.error("metadata", "`metadata` must be a mapping"),
+            None => self.error("", "missing required field `metadata`"),
+        }
+
+        match doc.get("spec").map(|spec| (spec, spec.get("components"))) {
+            Some((_, Some(Value::Sequence(components)))) if !components.is_empty() => {
+                self.validate_components(components)
+            }
+            Some((_, Some(Value::Sequence(_)))) => {
+                self.error("spec.components", "at least one component is required")
+            }
+            Some((_, Some(_))) => self.error("spec.components", "`components` must be a list"),
+            Some((_, None)) => self.error("spec", "missing required field `components`"),
+            None => self.error("", "missing required field `spec`"),
+        }
+    }
+
+    fn validate_metadata(&mut self, metadata: &Value) {
+        self.required_str(metadata, "metadata", "name");
+        match metadata.get("annotations") {
+            Some(annotations) if annotations.is_mapping() => {
+                self.required_str(annotations, "metadata.annotations", VERSION_ANNOTATION);
+            }
+            Some(_) => self.error("metadata.annotations", "`annotations` must be a mapping"),
+            None => self.error(
+                "metadata",
+                format!(
+                    "missing required field `annotations` with a `{}` annotation",
+                    VERSION_ANNOTATION
+                ),
+            ),
+        }
+    }
+
+    fn validate_components(&mut self, components: &[Value]) {
+        // Component names by type, so link targets can be checked once all are known
+        let mut component_types: HashMap<&str, &str> = HashMap::new();
+        let mut seen = HashSet::new();
+        for (idx, component) in components.iter().enumerate() {
+            let path = format!("spec.components[{}]", idx);
+            let name = self.required_str(component, &path, "name");
+            if let Some(name) = name {
+                if !seen.insert(name) {
+                    self.error(
+                        &join(&path, "name"),
+                        format!("duplicate component name `{}`", name),
+                    );
+                }
+            }
+            let component_type = self.required_str(component, &path, "type");
+            if let (Some(name), Some(component_type)) = (name, component_type) {
+                component_types.insert(name, component_type);
+            }
+        }
+
+        for (idx, component) in components.iter().enumerate() {
+            let path = format!("spec.components[{}]", idx);
+            let component_type = component.get("type").and_then(Value::as_str);
+            match component_type {
+                Some(ACTOR_COMPONENT) | Some(CAPABILITY_COMPONENT) => {}
+                Some(other) => self.error(
+                    &join(&path, "type"),
+                    format!(
+                        "unknown component type `{}`, expected `{}` or `{}`",
+                        other, ACTOR_COMPONENT, CAPABILITY_COMPONENT
+                    ),
+                ),
+                None => {}
+            }
+
+            let properties_path = join(&path, "properties");
+            match component.get("properties") {
+                Some(properties) if properties.is_mapping() => {
+                    if let Some(image) = self.required_str(properties, &properties_path, "image") {
+                        if let Err(e) = Reference::from_str(image) {
+                            self.error(
+                                &join(&properties_path, "image"),
+                                format!("`{}` is not a valid OCI reference: {}", image, e),
+                            );
+                        }
+                    }
+                    if component_type == Some(CAPABILITY_COMPONENT) {
+                        self.required_str(properties, &properties_path, "contract");
+                    }
+                }
+                Some(_) => self.error(&properties_path, "`properties` must be a mapping"),
+                None => self.error(&path, "missing required field `properties`"),
+            }
+
+            match component.get("traits") {
+                Some(Value::Sequence(traits)) => {
+                    for (trait_idx, component_trait) in traits.iter().enumerate() {
+                        self.validate_trait(
+                            component_trait,
+                            &format!("{}.traits[{}]", path, trait_idx),
+                            component_type,
+                            &component_types,
+                        );
+                    }
+                }
+                Some(_) => self.error(&join(&path, "traits"), "`traits` must be a list"),
+                None => {}
+            }
+        }
+    }
+
+    fn validate_trait(
+        &mut self,
+        component_trait: &Value,
+        path: &str,
+        component_type: Option<&str>,
+        component_types: &HashMap<&str, &str>,
+    ) {
+        let trait_type = match self.required_str(component_trait, path, "type") {
+            Some(trait_type) => trait_type,
+            None => return,
+        };
+        let properties_path = join(path, "properties");
+        let properties = match component_trait.get("properties") {
+            Some(properties) if properties.is_mapping() => properties,
+            Some(_) => {
+                self.error(&properties_path, "`properties` must be a mapping");
+                return;
+            }
+            None => {
+                self.error(path, "missing required field `properties`");
+                return;
+            }
+        };
+
+        match trait_type {
+            SPREADSCALER_TRAIT => self.validate_spreadscaler(properties, &properties_path),
+            LINKDEF_TRAIT => {
+                if component_type == Some(CAPABILITY_COMPONENT) {
+                    self.error(
+                        &join(path, "type"),
+                        "`linkdef` traits can only be applied to actor components",
+                    );
+                }
+                if let Some(target) = self.required_str(properties, &properties_path, "target") {
+                    match component_types.get(target) {
+                        Some(&CAPABILITY_COMPONENT) => {}
+                        Some(_) => self.error(
+                            &join(&properties_path, "target"),
+                            format!("link target `{}` is not a capability component", target),
+                        ),
+                        None => self.error(
+                            &join(&properties_path, "target"),
+                            format!("link target `{}` is not a component of this app", target),
+                        ),
+                    }
+                }
+                if let Some(values) = properties.get("values") {
+                    if !values.is_mapping() {
+                        self.error(
+                            &join(&properties_path, "values"),
+                            "`values` must be a mapping",
+                        );
+                    }
+                }
+            }
+            other => self.error(
+                &join(path, "type"),
+                format!(
+                    "unknown trait type `{}`, expected `{}` or `{}`",
+                    other, SPREADSCALER_TRAIT, LINKDEF_TRAIT
+                ),
+            ),
+        }
+    }
+
+    fn validate_spreadscaler(&mut self, properties: &Value, path: &str) {
+        match properties.get("replicas") {
+            Some(replicas) if replicas.as_u64().is_some() => {}
+            Some(_) => self.error(
+                &join(path, "replicas"),
+                "`replicas` must be a non-negative integer",
+            ),
+            None => self.error(path, "missing required field `replicas`"),
+        }
+        match properties.get("spread") {
+            Some(Value::Sequence(spread)) => {
+                for (idx, entry) in spread.iter().enumerate() {
+                    let entry_path = format!("{}.spread[{}]", path, idx);
+                    self.required_str(entry, &entry_path, "name");
+                    if let Some(weight) = entry.get("weight") {
+                        if weight.as_u64().is_none() {
+                            self.error(
+                                &join(&entry_path, "weight"),
+                                "`weight` must be a non-negative integer",
+                            );
+                        }
+                    }
+                    if let Some(requirements) = entry.get("requirements") {
+                        if !requirements.is_mapping() {
+                            self.error(
+                                &join(&entry_path, "requirements"),
+                                "`requirements` must be a mapping",
+                            );
+                        }
+                    }
+                }
+            }
+            Some(_) => self.error(&join(path, "spread"), "`spread` must be a list"),
+            None => {}
+        }
+    }
+}
+
+fn join(parent: &str, key: &str) -> String {
+    if parent.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}.{}", parent, key)
+    }
+}
+
+/// Maps the path of each node in a YAML document (in the same format as
+/// [ValidationError::path]) to the line it starts on. serde_yaml doesn't keep track of
+/// positions, so the document is walked a second time with yaml-rust, which does
+#[derive(Default)]
+struct LineIndex {
+    lines: HashMap<String, usize>,
+    stack: Vec<Frame>,
+}
+
+enum Frame {
+    Mapping { path: String, key: Option<String> },
+    Sequence { path: String, idx: usize },
+}
+
+impl LineIndex {
+    fn new(contents: &str) -> LineIndex {
+        let mut index = LineIndex::default();
+        // Documents that fail to parse here have already been parsed by serde_yaml, so the
+        // worst case is errors without line numbers
+        let _ = Parser::new(contents.chars()).load(&mut index, false);
+        index
+    }
+
+    /// Finds the line of a path, falling back to its closest ancestor with a known line
+    fn line_of(&self, path: &str) -> Option<usize> {
+        let mut path = path;
+        loop {
+            if let Some(line) = self.lines.get(path) {
+                return Some(*line);
+            }
+            path = &path[..path.rfind(['.', '['])?];
+        }
+    }
+
+    /// The path of the next node, i.e. the value of the current key or sequence entry
+    fn child_path(&self) -> String {
+        match self.stack.last() {
+            Some(Frame::Mapping {
+                path,
+                key: Some(key),
+            }) => join(path, key),
+            Some(Frame::Sequence { path, idx }) => format!("{}[{}]", path, idx),
+            _ => String::new(),
+        }
+    }
+
+    /// Moves past a node that has been fully read
+    fn advance(&mut self) {
+        match self.stack.last_mut() {
+            Some(Frame::Mapping { key, .. }) => *key = None,
+            Some(Frame::Sequence { idx, .. }) => *idx += 1,
+            None => {}
+        }
+    }
+
+    /// Records the line a node starts on, unless its key already was
+    fn record(&mut self, path: String, mark: Marker) {
+        self.lines.entry(path).or_insert(mark.line());
+    }
+}
+
+impl MarkedEventReceiver for LineIndex {
+    fn on_event(&mut self, ev: Event, mark: Marker) {
+        match ev {
+            Event::Scalar(value, ..) => {
+                if let Some(Frame::Mapping { path, key: None }) = self.stack.last() {
+                    let key_path = join(path, &value);
+                    if let Some(Frame::Mapping { key, .. }) = self.stack.last_mut() {
+                        *key = Some(value);
+                    }
+                    self.record(key_path, mark);
+                } else {
+                    let path = self.child_path();
+                    self.record(path, mark);
+                    self.advance();
+                }
+            }
+            Event::Alias(_) => self.advance(),
+            Event::MappingStart(_) => {
+                let path = self.child_path();
+                self.record(path.clone(), mark);
+                self.stack.push(Frame::Mapping { path, key: None });
+            }
+            Event::SequenceStart(_) => {
+                let path = self.child_path();
+                self.record(path.clone(), mark);
+                self.stack.push(Frame::Sequence { path, idx: 0 });
+            }
+            Event::MappingEnd | Event::SequenceEnd => {
+                self.stack.pop();
+                self.advance();
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::validate_manifest;
+
+    const VALID: &str = r#"apiVersion: core.oam.dev/v1beta1
+kind: Application
+metadata:
+  name: echo
+  annotations:
+    version: v0.0.1
+    description: "wasmCloud echo example"
+spec:
+  components:
+    - name: echo
+      type: actor
+      properties:
+        image: wasmcloud.azurecr.io/echo:0.3.7
+      traits:
+        - type: spreadscaler
+          properties:
+            replicas: 1
+            spread:
+              - name: eastcoast
+                requirements:
+                  zone: us-east-1
+                weight: 80
+        - type: linkdef
+          properties:
+            target: httpserver
+            values:
+              address: 0.0.0.0:8080
+    - name: httpserver
+      type: capability
+      properties:
+        image: wasmcloud.azurecr.io/httpserver:0.17.0
+        contract: wasmcloud:httpserver
+"#;
+
+    #[test]
+    fn test_valid_manifest() {
+        assert_eq!(validate_manifest(VALID), vec![]);
+    }
+
+    #[test]
+    fn test_invalid_manifest_reports_lines() {
+        let invalid = VALID
+            .replace("    version: v0.0.1\n", "")
+            .replace("target: httpserver", "target: httpclient")
+            .replace("type: spreadscaler", "type: daemonscaler")
+            .replace(
+                "image: wasmcloud.azurecr.io/echo:0.3.7",
+                "image: \"wasmcloud.azurecr.io/Echo:0.3.7\"",
+            );
+        let errors = validate_manifest(&invalid);
+        let found = errors
+            .iter()
+            .map(|e| (e.line, e.path.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            found,
+            vec![
+                (Some(5), "metadata.annotations"),
+                (Some(12), "spec.components[0].properties.image"),
+                (Some(14), "spec.components[0].traits[0].type"),
+                (Some(24), "spec.components[0].traits[1].properties.target"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unparseable_manifest() {
+        let errors = validate_manifest("apiVersion: [\n");
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].line.is_some());
+    }
+}
@@ -1,6 +1,7 @@
 use anyhow::Result;
-use indicatif::{ProgressBar, ProgressStyle};
+use indicatif::{HumanBytes, ProgressBar, ProgressStyle};
 use wash_lib::cli::OutputKind;
+use wash_lib::start::DownloadProgress;
 
 // For more spinners check out the cli-spinners project:
 // https://github.com/sindresorhus/cli-spinners/blob/master/spinners.json
@@ -19,11 +20,7 @@ impl Spinner {
     pub(crate) fn new(output_kind: &OutputKind) -> Result<Self> {
         match output_kind {
             OutputKind::Text => {
-                let style = ProgressStyle::default_spinner()
-                    .tick_strings(DOTS_12)
-                    .template("{prefix:.bold.dim} {spinner:.bold.dim} {wide_msg:.bold.dim}")?;
-
-                let spinner = ProgressBar::new_spinner().with_style(style);
+                let spinner = ProgressBar::new_spinner().with_style(spinner_style()?);
 
                 spinner.enable_steady_tick(std::time::Duration::from_millis(200));
                 Ok(Self {
@@ -39,6 +36,12 @@ impl Spinner {
     pub fn update_spinner_message(&self, msg: String) {
         match &self.spinner {
             Some(spinner) => {
+                // Switch back from a download bar, if one was shown
+                if spinner.length().is_some() {
+                    if let Ok(style) = spinner_style() {
+                        spinner.set_style(style);
+                    }
+                }
                 spinner.set_prefix(">>>");
                 spinner.set_message(msg);
             }
@@ -46,6 +49,28 @@ impl Spinner {
         }
     }
 
+    /// Shows the progress of a download as a bar of bytes downloaded out of the total, or just the
+    /// bytes downloaded when the total isn't known
+    pub fn update_download_progress(&self, msg: &str, progress: DownloadProgress) {
+        match (&self.spinner, progress.total) {
+            (Some(spinner), Some(total)) => {
+                if spinner.length() != Some(total) {
+                    if let Ok(style) = download_style() {
+                        spinner.set_style(style);
+                    }
+                    spinner.set_length(total);
+                }
+                spinner.set_prefix(">>>");
+                spinner.set_message(msg.to_string());
+                spinner.set_position(progress.downloaded);
+            }
+            (Some(_), None) => {
+                self.update_spinner_message(format!("{} {}", msg, HumanBytes(progress.downloaded)))
+            }
+            (None, _) => {}
+        }
+    }
+
     pub fn finish_and_clear(&self) {
         match &self.spinner {
             Some(progress_bar) => progress_bar.finish_and_clear(),
@@ -53,3 +78,17 @@ impl Spinner {
         }
     }
 }
+
+fn spinner_style() -> Result<ProgressStyle> {
+    Ok(ProgressStyle::default_spinner()
+        .tick_strings(DOTS_12)
+        .template("{prefix:.bold.dim} {spinner:.bold.dim} {wide_msg:.bold.dim}")?)
+}
+
+fn download_style() -> Result<ProgressStyle> {
+    Ok(ProgressStyle::default_bar()
+        .template(
+            "{prefix:.bold.dim} {msg:.bold.dim} [{bar:30}] {bytes}/{total_bytes} ({bytes_per_sec})",
+        )?
+        .progress_chars("=> "))
+}
@@ -1,23 +1,32 @@
-use std::{collections::HashMap, path::PathBuf, time::Duration};
+use std::{
+    collections::{BTreeSet, HashMap},
+    io::Read,
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::Duration,
+};
 
-use anyhow::{bail, Context, Result};
+use anyhow::{anyhow, bail, Context, Result};
 use clap::Args;
 use log::{debug, error};
 use wash_lib::cli::CommandOutput;
 use wash_lib::config::{DEFAULT_LATTICE_PREFIX, DEFAULT_NATS_HOST, DEFAULT_NATS_PORT};
 use wash_lib::context::{
     fs::{load_context, ContextDir},
-    ContextManager,
+    ContextManager, WashContext,
 };
-use wash_lib::id::{ClusterSeed, ModuleId};
+use wash_lib::id::{ClusterSeed, ModuleId, ServiceId};
 use wasmbus_rpc::{common::Message, core::WasmCloudEntity, rpc_client::RpcClient};
+use wasmcloud_control_interface::{Client as CtlClient, HostInventory};
 use wasmcloud_test_util::testing::TestResults;
 
 use crate::{
     ctx::{context_dir, ensure_host_config_context},
+    spy::{read_recorded_requests, SpyRecord},
     util::{
-        default_timeout_ms, extract_arg_value, json_str_to_msgpack_bytes, msgpack_to_json_val,
-        nats_client_from_opts,
+        binary_to_json, convert_error, default_timeout_ms, extract_arg_value,
+        json_str_to_msgpack_bytes, msgpack_to_json_val, nats_client_from_opts,
+        yaml_str_to_msgpack_bytes, BinaryDisplay,
     },
 };
 
@@ -28,7 +37,10 @@ const WASH_ORIGIN_KEY: &str = "__WASH__";
 const WASH_HOST_ID: &str = "NwashHostCallerId000000000000000000000000000000000000000";
 
 #[derive(Debug, Args, Clone)]
-#[clap(name = "call")]
+#[clap(
+    name = "call",
+    override_usage = "wash call [OPTIONS] <actor-id> <operation> [payload]...\n       wash call [OPTIONS] --provider <PROVIDER_ID> --contract <CONTRACT_ID> <operation> [payload]..."
+)]
 pub(crate) struct CallCli {
     #[clap(flatten)]
     command: CallCommand,
@@ -41,11 +53,59 @@ impl CallCli {
 }
 
 pub(crate) async fn handle_command(cmd: CallCommand) -> Result<CommandOutput> {
+    if let Some(recording) = cmd.replay.clone() {
+        return handle_replay(cmd, recording).await;
+    }
     let is_test = cmd.test;
     let save_output = cmd.save.clone();
     let bin = cmd.bin;
+    let output_encoding = cmd.output_encoding;
     let res = handle_call(cmd).await?;
-    call_output(res, save_output, bin, is_test)
+    call_output(res, save_output, bin, output_encoding, is_test)
+}
+
+/// The format of a payload supplied with the payload args, a data file or stdin
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum PayloadFormat {
+    Json,
+    Yaml,
+}
+
+impl FromStr for PayloadFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "json" => Ok(PayloadFormat::Json),
+            "yaml" => Ok(PayloadFormat::Yaml),
+            _ => bail!("data format must be 'json' or 'yaml'"),
+        }
+    }
+}
+
+/// How the response of an invocation is decoded for display
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub(crate) enum OutputEncoding {
+    /// The response is msgpack, as sent by actors using wasmbus-rpc
+    #[default]
+    Msgpack,
+    /// The response is a json document
+    Json,
+    /// The response is arbitrary bytes and is displayed as-is
+    Raw,
+}
+
+impl FromStr for OutputEncoding {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "msgpack" => Ok(OutputEncoding::Msgpack),
+            "json" => Ok(OutputEncoding::Json),
+            "raw" => Ok(OutputEncoding::Raw),
+            _ => bail!("output encoding must be 'msgpack', 'json' or 'raw'"),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Args)]
@@ -89,22 +149,45 @@ pub(crate) struct ConnectionOpts {
     pub(crate) context: Option<PathBuf>,
 }
 
+impl ConnectionOpts {
+    /// The lattice prefix to use for RPC subjects
+    pub(crate) fn lattice_prefix(&self) -> String {
+        self.lattice_prefix
+            .clone()
+            .unwrap_or_else(|| DEFAULT_LATTICE_PREFIX.to_string())
+    }
+}
+
 #[derive(Args, Debug, Clone)]
 pub(crate) struct CallCommand {
     #[clap(flatten)]
     opts: ConnectionOpts,
 
-    /// Optional json file to send as the operation payload
+    /// Optional json or yaml file to send as the operation payload, or `-` to read it from stdin
     #[clap(short, long)]
     pub(crate) data: Option<PathBuf>,
 
+    /// Format of the payload args, data file or stdin ('json' or 'yaml'). Defaults to yaml for
+    /// data files with a .yaml or .yml extension, and json otherwise
+    #[clap(long = "data-format")]
+    pub(crate) data_format: Option<PayloadFormat>,
+
+    /// Optional file to send unmodified as the operation payload, or `-` to read it from stdin.
+    /// Unlike --data, the contents are not converted to msgpack
+    #[clap(long = "raw-file", conflicts_with_all = ["data", "data_format"])]
+    pub(crate) raw_file: Option<PathBuf>,
+
     /// Optional file for saving binary response
     #[clap(long)]
     pub(crate) save: Option<PathBuf>,
 
     /// When using json output, display binary as binary('b'), string('s'), or both('2')
     #[clap(long, default_value = "b")]
-    pub(crate) bin: char,
+    pub(crate) bin: BinaryDisplay,
+
+    /// How to decode the response for display: 'msgpack', 'json', or 'raw' bytes
+    #[clap(long = "output-encoding", default_value = "msgpack")]
+    pub(crate) output_encoding: OutputEncoding,
 
     /// When invoking a test actor, interpret the response as TestResults
     #[clap(long)]
@@ -121,50 +204,176 @@ pub(crate) struct CallCommand {
     )]
     pub(crate) cluster_seed: Option<ClusterSeed>,
 
-    /// Public key or OCI reference of actor
-    #[clap(name = "actor-id")]
-    pub(crate) actor_id: ModuleId,
+    /// Public key of a capability provider to invoke instead of an actor. When supplied, the
+    /// actor-id argument is omitted and the operation follows the options directly
+    #[clap(long = "provider", value_parser, requires = "contract_id")]
+    pub(crate) provider_id: Option<ServiceId>,
+
+    /// Capability contract ID of the provider to invoke, e.g. `wasmcloud:keyvalue`
+    #[clap(long = "contract", requires = "provider_id")]
+    pub(crate) contract_id: Option<String>,
+
+    /// Link name of the provider to invoke
+    #[clap(short = 'l', long = "link-name", default_value = "default")]
+    pub(crate) link_name: String,
+
+    /// Re-send the invocations recorded with `wash spy --record` instead of a single invocation
+    #[clap(
+        long = "replay",
+        conflicts_with_all = ["data", "data_format", "raw_file", "save", "test", "provider_id", "actor-id"]
+    )]
+    pub(crate) replay: Option<PathBuf>,
+
+    /// Public key, call alias or OCI reference of actor
+    #[clap(name = "actor-id", required_unless_present_any = ["provider_id", "replay"])]
+    pub(crate) actor_id: Option<String>,
 
     /// Operation to invoke on actor
     #[clap(name = "operation")]
-    pub(crate) operation: String,
+    pub(crate) operation: Option<String>,
 
     /// Payload to send with operation (in the form of '{"field": "value"}' )
     #[clap(name = "payload")]
     pub(crate) payload: Vec<String>,
 }
 
+/// The entity that receives an invocation sent with `wash call`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum CallTarget {
+    Actor(ModuleId),
+    /// A call alias or OCI reference of a running actor, which must be resolved to the
+    /// actor's public key before it can be invoked
+    ActorRef(String),
+    Provider {
+        provider_id: ServiceId,
+        contract_id: String,
+        link_name: String,
+    },
+}
+
+impl CallTarget {
+    fn entity(&self) -> Result<WasmCloudEntity> {
+        match self {
+            CallTarget::Actor(actor_id) => Ok(WasmCloudEntity::new_actor(actor_id)?),
+            CallTarget::ActorRef(actor_ref) => {
+                bail!(
+                    "Actor reference {} must be resolved before it can be invoked",
+                    actor_ref
+                )
+            }
+            CallTarget::Provider {
+                provider_id,
+                contract_id,
+                link_name,
+            } => Ok(WasmCloudEntity {
+                public_key: provider_id.to_string(),
+                contract_id: contract_id.to_owned(),
+                link_name: link_name.to_owned(),
+            }),
+        }
+    }
+}
+
+impl CallCommand {
+    /// Sorts the positional arguments into the call target, the operation and the payload.
+    /// Provider invocations don't take an actor ID, so their first positional is the operation
+    pub(crate) fn target(&self) -> Result<(CallTarget, String, Vec<String>)> {
+        match (&self.provider_id, &self.contract_id) {
+            (Some(provider_id), Some(contract_id)) => {
+                let mut positionals = self
+                    .actor_id
+                    .iter()
+                    .chain(self.operation.iter())
+                    .chain(self.payload.iter())
+                    .cloned();
+                let operation = positionals
+                    .next()
+                    .ok_or_else(|| anyhow!("An operation is required to invoke a provider"))?;
+                Ok((
+                    CallTarget::Provider {
+                        provider_id: provider_id.to_owned(),
+                        contract_id: contract_id.to_owned(),
+                        link_name: self.link_name.clone(),
+                    },
+                    operation,
+                    positionals.collect(),
+                ))
+            }
+            (Some(_), None) => bail!("--contract is required when invoking a provider"),
+            (None, _) => {
+                let actor_id = self
+                    .actor_id
+                    .as_deref()
+                    .ok_or_else(|| anyhow!("An actor ID is required to invoke an actor"))?;
+                let target = match actor_id.parse::<ModuleId>() {
+                    Ok(actor_id) => CallTarget::Actor(actor_id),
+                    Err(_) => CallTarget::ActorRef(actor_id.to_string()),
+                };
+                let operation = self
+                    .operation
+                    .clone()
+                    .ok_or_else(|| anyhow!("An operation is required to invoke an actor"))?;
+                Ok((target, operation, self.payload.clone()))
+            }
+        }
+    }
+}
+
 pub(crate) async fn handle_call(cmd: CallCommand) -> Result<Vec<u8>> {
+    let (target, operation, payload) = cmd.target()?;
     debug!(
-        "calling actor with operation: {}, data: {}",
-        &cmd.operation,
-        cmd.payload.join("")
+        "calling {:?} with operation: {}, data: {}",
+        &target,
+        &operation,
+        payload.join("")
     );
-    if !"bs2".contains(cmd.bin) {
-        bail!("'bin' parameter must be 'b', 's', or '2'");
-    }
+
+    let target = match target {
+        CallTarget::ActorRef(actor_ref) => {
+            CallTarget::Actor(resolve_actor_ref(&cmd.opts, &actor_ref).await?)
+        }
+        target => target,
+    };
 
     let origin = WasmCloudEntity::new_actor(WASH_ORIGIN_KEY)?;
-    let target = WasmCloudEntity::new_actor(&cmd.actor_id)?;
+    let target = target.entity()?;
 
-    if cmd.data.is_some() && !cmd.payload.is_empty() {
-        bail!("you can use either -d/--data or the payload args, but not both.");
+    if (cmd.data.is_some() || cmd.raw_file.is_some()) && !payload.is_empty() {
+        bail!(
+            "you can use either -d/--data, --raw-file or the payload args, but not more than one."
+        );
     }
-    let payload = if let Some(fname) = cmd.data {
-        std::fs::read_to_string(fname)?
+    let bytes = if let Some(fname) = cmd.raw_file {
+        read_input(&fname)?
     } else {
-        cmd.payload.join("")
+        let (payload, format) = if let Some(fname) = cmd.data {
+            let contents = String::from_utf8(read_input(&fname)?)
+                .with_context(|| format!("Data file {} is not valid UTF-8", fname.display()))?;
+            let format = cmd.data_format.unwrap_or_else(|| {
+                match fname.extension().and_then(|e| e.to_str()) {
+                    Some("yaml") | Some("yml") => PayloadFormat::Yaml,
+                    _ => PayloadFormat::Json,
+                }
+            });
+            (contents, format)
+        } else {
+            (
+                payload.join(""),
+                cmd.data_format.unwrap_or(PayloadFormat::Json),
+            )
+        };
+        debug!(
+            "calling {} with operation: {}, data: {}",
+            target.url(),
+            &operation,
+            &payload
+        );
+        match format {
+            PayloadFormat::Json => json_str_to_msgpack_bytes(&payload)?,
+            PayloadFormat::Yaml => yaml_str_to_msgpack_bytes(&payload)?,
+        }
     };
-    debug!(
-        "calling actor with operation: {}, data: {}",
-        &cmd.operation, &payload
-    );
-    let bytes = json_str_to_msgpack_bytes(&payload)?;
-    let lattice_prefix = cmd
-        .opts
-        .lattice_prefix
-        .clone()
-        .unwrap_or_else(|| DEFAULT_LATTICE_PREFIX.to_string());
+    let lattice_prefix = cmd.opts.lattice_prefix();
 
     let (client, timeout_ms) = rpc_client_from_opts(cmd.opts, cmd.cluster_seed).await?;
     Ok(client
@@ -173,7 +382,7 @@ pub(crate) async fn handle_call(cmd: CallCommand) -> Result<Vec<u8>> {
             target,
             &lattice_prefix,
             Message {
-                method: &cmd.operation,
+                method: &operation,
                 arg: bytes.into(),
             },
             Duration::from_millis(timeout_ms),
@@ -181,11 +390,176 @@ pub(crate) async fn handle_call(cmd: CallCommand) -> Result<Vec<u8>> {
         .await?)
 }
 
+/// Resolves a call alias or OCI reference to the public key of the running actor it refers to.
+/// Call aliases are looked up in the claims known to the lattice, and OCI references in the
+/// inventories of its hosts. References that could be either, like `org/echo`, are looked up as
+/// call aliases first
+async fn resolve_actor_ref(opts: &ConnectionOpts, actor_ref: &str) -> Result<ModuleId> {
+    let (nc, _) = nats_client_and_context_from_opts(opts).await?;
+    let timeout = Duration::from_millis(opts.timeout_ms);
+    let client = CtlClient::new(nc, Some(opts.lattice_prefix()), timeout, timeout);
+
+    let mut kind = "call alias";
+    let mut matches = BTreeSet::new();
+    if !is_oci_ref(actor_ref) {
+        let claims = client.get_claims().await.map_err(convert_error)?;
+        matches = actors_with_call_alias(claims.claims, actor_ref);
+    }
+    if matches.is_empty() {
+        kind = if is_oci_ref(actor_ref) {
+            "OCI reference"
+        } else {
+            "call alias or OCI reference"
+        };
+        let hosts = client.get_hosts().await.map_err(convert_error)?;
+        let mut inventories = Vec::with_capacity(hosts.len());
+        for host in hosts {
+            inventories.push(
+                client
+                    .get_host_inventory(&host.id)
+                    .await
+                    .map_err(convert_error)?,
+            );
+        }
+        matches = actors_with_image_ref(inventories, actor_ref);
+    }
+
+    let matches = matches.into_iter().collect::<Vec<_>>();
+    match matches.as_slice() {
+        [] => bail!("No running actor found with {} {}", kind, actor_ref),
+        [actor_id] => {
+            debug!("resolved {} {} to {}", kind, actor_ref, actor_id);
+            actor_id
+                .parse()
+                .with_context(|| format!("Invalid public key {} for {}", actor_id, actor_ref))
+        }
+        _ => bail!(
+            "Multiple running actors match {} {}: {}. Use the public key of the actor to invoke instead",
+            kind,
+            actor_ref,
+            matches.join(", ")
+        ),
+    }
+}
+
+/// Call aliases can't contain these characters, so only OCI references do
+fn is_oci_ref(actor_ref: &str) -> bool {
+    actor_ref.contains([':', '.', '@'])
+}
+
+/// The public keys of the actors whose claims give them the call alias
+fn actors_with_call_alias(claims: Vec<HashMap<String, String>>, alias: &str) -> BTreeSet<String> {
+    claims
+        .into_iter()
+        .filter_map(|mut claim| {
+            if claim.get("call_alias").map(String::as_str) == Some(alias) {
+                claim.remove("sub")
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/// The public keys of the actors the hosts have started from the OCI reference
+fn actors_with_image_ref(inventories: Vec<HostInventory>, image_ref: &str) -> BTreeSet<String> {
+    inventories
+        .into_iter()
+        .flat_map(|inventory| inventory.actors)
+        .filter(|actor| actor.image_ref.as_deref() == Some(image_ref))
+        .map(|actor| actor.id)
+        .collect()
+}
+
+/// Re-sends each invocation in a recording made with `wash spy`, from its original origin to its
+/// original target
+async fn handle_replay(cmd: CallCommand, recording: PathBuf) -> Result<CommandOutput> {
+    let requests = read_recorded_requests(&recording)?;
+    if requests.is_empty() {
+        bail!("No invocations found in recording {}", recording.display());
+    }
+    let bin = cmd.bin;
+    let lattice_prefix = cmd.opts.lattice_prefix();
+    let (client, timeout_ms) = rpc_client_from_opts(cmd.opts, cmd.cluster_seed).await?;
+
+    let mut text = format!(
+        "Replayed {} invocations from {}",
+        requests.len(),
+        recording.display()
+    );
+    let mut results = Vec::new();
+    for request in requests {
+        let (id, origin, target, operation, payload) = match request {
+            SpyRecord::Request {
+                id,
+                origin,
+                target,
+                operation,
+                payload,
+                ..
+            } => (id, origin, target, operation, payload),
+            SpyRecord::Response { .. } => continue,
+        };
+        let target_url = target.url();
+        let res = client
+            .send_timeout(
+                origin,
+                target,
+                &lattice_prefix,
+                Message {
+                    method: &operation,
+                    arg: payload.into(),
+                },
+                Duration::from_millis(timeout_ms),
+            )
+            .await;
+        match res {
+            Ok(response) => {
+                let response = msgpack_to_json_val(response, bin);
+                text.push_str(&format!("\n{} on {}: {}", operation, target_url, response));
+                results.push(serde_json::json!({
+                    "id": id,
+                    "operation": operation,
+                    "target": target_url,
+                    "response": response,
+                }));
+            }
+            Err(e) => {
+                text.push_str(&format!("\n{} on {} failed: {}", operation, target_url, e));
+                results.push(serde_json::json!({
+                    "id": id,
+                    "operation": operation,
+                    "target": target_url,
+                    "error": e.to_string(),
+                }));
+            }
+        }
+    }
+
+    let mut json = HashMap::new();
+    json.insert("responses".to_string(), serde_json::json!(results));
+    Ok(CommandOutput::new(text, json))
+}
+
+/// Reads the contents of a file, or of stdin if the path is `-`
+fn read_input(path: &Path) -> Result<Vec<u8>> {
+    if path == Path::new("-") {
+        let mut buf = Vec::new();
+        std::io::stdin()
+            .read_to_end(&mut buf)
+            .context("Failed to read payload from stdin")?;
+        Ok(buf)
+    } else {
+        std::fs::read(path).with_context(|| format!("Failed to read {}", path.display()))
+    }
+}
+
 // Helper output functions, used to ensure consistent output between call & standalone commands
 pub(crate) fn call_output(
     response: Vec<u8>,
     save_output: Option<PathBuf>,
-    bin: char,
+    bin: BinaryDisplay,
+    output_encoding: OutputEncoding,
     is_test: bool,
 ) -> Result<CommandOutput> {
     if let Some(ref save_path) = save_output {
@@ -214,26 +588,76 @@ pub(crate) fn call_output(
         ));
     }
 
-    let mut json = HashMap::new();
-    json.insert(
-        "response".to_string(),
-        msgpack_to_json_val(response.clone(), bin),
+    let raw_text = format!(
+        "\nCall response (raw): {}",
+        String::from_utf8_lossy(&response)
     );
+    let (value, text) = match output_encoding {
+        OutputEncoding::Msgpack => (msgpack_to_json_val(response, bin), raw_text),
+        OutputEncoding::Json => match serde_json::from_slice::<serde_json::Value>(&response) {
+            Ok(value) => {
+                let text = format!(
+                    "\nCall response (json): {}",
+                    serde_json::to_string_pretty(&value)?
+                );
+                (value, text)
+            }
+            Err(_) => (
+                serde_json::json!({ "error": "Could not decode data" }),
+                raw_text,
+            ),
+        },
+        OutputEncoding::Raw => (binary_to_json(response, bin), raw_text),
+    };
 
-    Ok(CommandOutput::new(
-        format!(
-            "\nCall response (raw): {}",
-            String::from_utf8_lossy(&response)
-        ),
-        json,
-    ))
+    let mut json = HashMap::new();
+    json.insert("response".to_string(), value);
+
+    Ok(CommandOutput::new(text, json))
 }
 
 async fn rpc_client_from_opts(
     opts: ConnectionOpts,
     cmd_cluster_seed: Option<ClusterSeed>,
 ) -> Result<(RpcClient, u64)> {
-    let ctx = if let Some(context) = opts.context {
+    let (nc, ctx) = nats_client_and_context_from_opts(&opts).await?;
+
+    // Cluster seed is optional on the CLI to allow for context to supply that variable.
+    // If no context is supplied, and there is no default context, then the cluster seed
+    // cannot be determined and the RPC will almost certainly fail, unless the antiforgery
+    // check allows the invocation to be unsigned.
+    let cluster_seed = cmd_cluster_seed.unwrap_or_else(|| {
+        ctx.as_ref()
+            .map(|c| {
+                c.cluster_seed.clone().unwrap_or_else(|| {
+                    error!(
+                        "No cluster seed provided and no context available, this RPC will fail."
+                    );
+                    ClusterSeed::default()
+                })
+            })
+            .unwrap_or_default()
+    });
+
+    Ok((
+        RpcClient::new(
+            nc,
+            WASH_HOST_ID.to_string(),
+            Some(Duration::from_millis(opts.timeout_ms)),
+            std::sync::Arc::new(nkeys::KeyPair::from_seed(&extract_arg_value(
+                cluster_seed.as_ref(),
+            )?)?),
+        ),
+        opts.timeout_ms,
+    ))
+}
+
+/// Connects to the lattice NATS server for RPC, using explicitly provided flags, then the
+/// values of the provided (or default) context, and lastly the defaults
+pub(crate) async fn nats_client_and_context_from_opts(
+    opts: &ConnectionOpts,
+) -> Result<(async_nats::Client, Option<WashContext>)> {
+    let ctx = if let Some(context) = &opts.context {
         Some(load_context(context)?)
     } else if let Ok(ctx_dir) = context_dir(None) {
         let ctx_dir = ContextDir::new(ctx_dir)?;
@@ -243,80 +667,55 @@ async fn rpc_client_from_opts(
         None
     };
 
-    // Determine connection parameters, taking explicitly provided flags,
-    // then provided context values, lastly using defaults
-
-    let rpc_host = opts.rpc_host.unwrap_or_else(|| {
+    let rpc_host = opts.rpc_host.clone().unwrap_or_else(|| {
         ctx.as_ref()
             .map(|c| c.rpc_host.clone())
             .unwrap_or_else(|| DEFAULT_NATS_HOST.to_string())
     });
 
-    let rpc_port = opts.rpc_port.unwrap_or_else(|| {
+    let rpc_port = opts.rpc_port.clone().unwrap_or_else(|| {
         ctx.as_ref()
             .map(|c| c.rpc_port.to_string())
             .unwrap_or_else(|| DEFAULT_NATS_PORT.to_string())
     });
 
     let rpc_jwt = if opts.rpc_jwt.is_some() {
-        opts.rpc_jwt
+        opts.rpc_jwt.clone()
     } else {
         ctx.as_ref().map(|c| c.rpc_jwt.clone()).unwrap_or_default()
     };
 
     let rpc_seed = if opts.rpc_seed.is_some() {
-        opts.rpc_seed
+        opts.rpc_seed.clone()
     } else {
         ctx.as_ref().map(|c| c.rpc_seed.clone()).unwrap_or_default()
     };
 
     let rpc_credsfile = if opts.rpc_credsfile.is_some() {
-        opts.rpc_credsfile
+        opts.rpc_credsfile.clone()
     } else {
         ctx.as_ref()
             .map(|c| c.rpc_credsfile.clone())
             .unwrap_or_default()
     };
 
-    // Cluster seed is optional on the CLI to allow for context to supply that variable.
-    // If no context is supplied, and there is no default context, then the cluster seed
-    // cannot be determined and the RPC will almost certainly fail, unless the antiforgery
-    // check allows the invocation to be unsigned.
-    let cluster_seed = cmd_cluster_seed.unwrap_or_else(|| {
-        ctx.as_ref()
-            .map(|c| {
-                c.cluster_seed.clone().unwrap_or_else(|| {
-                    error!(
-                        "No cluster seed provided and no context available, this RPC will fail."
-                    );
-                    ClusterSeed::default()
-                })
-            })
-            .unwrap_or_default()
-    });
-
     let nc = nats_client_from_opts(&rpc_host, &rpc_port, rpc_jwt, rpc_seed, rpc_credsfile).await?;
-
-    Ok((
-        RpcClient::new(
-            nc,
-            WASH_HOST_ID.to_string(),
-            Some(Duration::from_millis(opts.timeout_ms)),
-            std::sync::Arc::new(nkeys::KeyPair::from_seed(&extract_arg_value(
-                cluster_seed.as_ref(),
-            )?)?),
-        ),
-        opts.timeout_ms,
-    ))
+    Ok((nc, ctx))
 }
 
 #[cfg(test)]
 mod test {
-    use super::CallCommand;
+    use super::{CallCommand, CallTarget, OutputEncoding, PayloadFormat};
+    use crate::util::BinaryDisplay;
     use anyhow::Result;
     use clap::Parser;
-    use std::{path::PathBuf, str::FromStr};
-    use wash_lib::id::ModuleId;
+    use std::{
+        collections::{BTreeSet, HashMap},
+        path::PathBuf,
+        str::FromStr,
+    };
+    use wash_lib::id::{ModuleId, ServiceId};
+    use wasmcloud_control_interface::{ActorDescription, HostInventory};
 
     const RPC_HOST: &str = "127.0.0.1";
     const RPC_PORT: &str = "4222";
@@ -325,6 +724,7 @@ mod test {
     const DATA_FNAME: &str = "/tmp/data.json";
 
     const ACTOR_ID: &str = "MDPDJEYIAK6MACO67PRFGOSSLODBISK4SCEYDY3HEOY4P5CVJN6UCWUK";
+    const PROVIDER_ID: &str = "VBKTSBG2WKP6RJWLQ5O7RDVIIB4LMW6U5R67A7QMIDBZDGZWYTUE3TSI";
 
     #[derive(Debug, Parser)]
     struct Cmd {
@@ -339,10 +739,14 @@ mod test {
             "--test",
             "--data",
             DATA_FNAME,
+            "--data-format",
+            "yaml",
             "--save",
             SAVE_FNAME,
             "--bin",
             "2",
+            "--output-encoding",
+            "json",
             "--context",
             "~/.wash/contexts/default.json",
             "--cluster-seed",
@@ -359,13 +763,27 @@ mod test {
             "HandleOperation",
             "{ \"hello\": \"world\"}",
         ])?;
+        let (target, operation, payload) = call_all.command.target()?;
+        assert_eq!(
+            target,
+            CallTarget::Actor(ModuleId::from_str(ACTOR_ID).unwrap())
+        );
+        assert_eq!(operation, "HandleOperation");
+        assert_eq!(payload, vec!["{ \"hello\": \"world\"}".to_string()]);
         match call_all.command {
             CallCommand {
                 opts,
                 data,
+                data_format,
+                raw_file,
                 save,
                 bin,
+                output_encoding,
                 test,
+                provider_id,
+                contract_id,
+                link_name,
+                replay,
                 actor_id,
                 operation,
                 payload,
@@ -380,6 +798,8 @@ mod test {
                     Some(PathBuf::from("~/.wash/contexts/default.json"))
                 );
                 assert_eq!(data, Some(PathBuf::from(DATA_FNAME)));
+                assert_eq!(data_format, Some(PayloadFormat::Yaml));
+                assert_eq!(raw_file, None);
                 assert_eq!(save, Some(PathBuf::from(SAVE_FNAME)));
                 assert_eq!(
                     cluster_seed.unwrap(),
@@ -388,9 +808,14 @@ mod test {
                         .unwrap()
                 );
                 assert!(test);
-                assert_eq!(bin, '2');
-                assert_eq!(actor_id, ModuleId::from_str(ACTOR_ID).unwrap());
-                assert_eq!(operation, "HandleOperation");
+                assert_eq!(bin, BinaryDisplay::Both);
+                assert_eq!(output_encoding, OutputEncoding::Json);
+                assert_eq!(provider_id, None);
+                assert_eq!(contract_id, None);
+                assert_eq!(link_name, "default");
+                assert_eq!(replay, None);
+                assert_eq!(actor_id, Some(ACTOR_ID.to_string()));
+                assert_eq!(operation, Some("HandleOperation".to_string()));
                 assert_eq!(payload, vec!["{ \"hello\": \"world\"}".to_string()])
             }
             #[allow(unreachable_patterns)]
@@ -398,4 +823,138 @@ mod test {
         }
         Ok(())
     }
+
+    #[test]
+    fn test_rpc_provider() -> Result<()> {
+        let call_provider: Cmd = Parser::try_parse_from([
+            "call",
+            "--provider",
+            PROVIDER_ID,
+            "--link-name",
+            "secondary",
+            "--contract",
+            "wasmcloud:keyvalue",
+            "KeyValue.Get",
+            "{ \"key\": \"counter\"}",
+        ])?;
+        let (target, operation, payload) = call_provider.command.target()?;
+        assert_eq!(
+            target,
+            CallTarget::Provider {
+                provider_id: ServiceId::from_str(PROVIDER_ID).unwrap(),
+                contract_id: "wasmcloud:keyvalue".to_string(),
+                link_name: "secondary".to_string(),
+            }
+        );
+        assert_eq!(operation, "KeyValue.Get");
+        assert_eq!(payload, vec!["{ \"key\": \"counter\"}".to_string()]);
+
+        let missing_contract: Result<Cmd, _> =
+            Parser::try_parse_from(["call", "--provider", PROVIDER_ID, "KeyValue.Get"]);
+        assert!(missing_contract.is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_rpc_raw_file() -> Result<()> {
+        let call_raw: Cmd = Parser::try_parse_from([
+            "call",
+            "--raw-file",
+            "-",
+            "--output-encoding",
+            "raw",
+            "--bin",
+            "s",
+            ACTOR_ID,
+            "HandleOperation",
+        ])?;
+        assert_eq!(call_raw.command.raw_file, Some(PathBuf::from("-")));
+        assert_eq!(call_raw.command.output_encoding, OutputEncoding::Raw);
+        assert_eq!(call_raw.command.bin, BinaryDisplay::String);
+
+        let raw_and_data: Result<Cmd, _> = Parser::try_parse_from([
+            "call",
+            "--raw-file",
+            "payload.bin",
+            "--data",
+            DATA_FNAME,
+            ACTOR_ID,
+            "HandleOperation",
+        ]);
+        assert!(raw_and_data.is_err());
+
+        let bad_bin: Result<Cmd, _> =
+            Parser::try_parse_from(["call", "--bin", "x", ACTOR_ID, "HandleOperation"]);
+        assert!(bad_bin.is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_rpc_replay() -> Result<()> {
+        let replay: Cmd = Parser::try_parse_from(["call", "--replay", "traffic.jsonl"])?;
+        assert_eq!(replay.command.replay, Some(PathBuf::from("traffic.jsonl")));
+        assert_eq!(replay.command.actor_id, None);
+
+        let replay_and_actor: Result<Cmd, _> = Parser::try_parse_from([
+            "call",
+            "--replay",
+            "traffic.jsonl",
+            ACTOR_ID,
+            "HandleOperation",
+        ]);
+        assert!(replay_and_actor.is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_rpc_actor_ref() -> Result<()> {
+        for actor_ref in ["echo", "wasmcloud.azurecr.io/echo:0.3.4"] {
+            let call: Cmd = Parser::try_parse_from(["call", actor_ref, "HandleOperation"])?;
+            let (target, operation, _) = call.command.target()?;
+            assert_eq!(target, CallTarget::ActorRef(actor_ref.to_string()));
+            assert_eq!(operation, "HandleOperation");
+            assert!(target.entity().is_err());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_rpc_actor_ref_matches() {
+        let claims = vec![HashMap::from([
+            ("sub".to_string(), "MALIAS".to_string()),
+            ("call_alias".to_string(), "org/echo".to_string()),
+        ])];
+        let inventories = vec![HostInventory {
+            actors: vec![
+                ActorDescription {
+                    id: "MIMAGE".to_string(),
+                    image_ref: Some("org/echo".to_string()),
+                    ..Default::default()
+                },
+                ActorDescription {
+                    id: "MTAGGED".to_string(),
+                    image_ref: Some("wasmcloud.azurecr.io/echo:0.3.4".to_string()),
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        }];
+
+        // `org/echo` could be a call alias or an image reference without a registry or tag
+        assert!(!super::is_oci_ref("org/echo"));
+        assert!(super::is_oci_ref("wasmcloud.azurecr.io/echo:0.3.4"));
+        assert_eq!(
+            super::actors_with_call_alias(claims, "org/echo"),
+            BTreeSet::from(["MALIAS".to_string()])
+        );
+        assert!(super::actors_with_call_alias(vec![], "org/echo").is_empty());
+        assert_eq!(
+            super::actors_with_image_ref(inventories.clone(), "org/echo"),
+            BTreeSet::from(["MIMAGE".to_string()])
+        );
+        assert_eq!(
+            super::actors_with_image_ref(inventories, "wasmcloud.azurecr.io/echo:0.3.4"),
+            BTreeSet::from(["MTAGGED".to_string()])
+        );
+    }
 }
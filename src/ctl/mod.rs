@@ -34,9 +34,9 @@ use wait::{
     wait_for_provider_stop_event, FindEventOutcome,
 };
 
-mod manifest;
+pub(crate) mod manifest;
 mod output;
-mod wait;
+pub(crate) mod wait;
 
 // default start actor command starts with one actor
 const ONE_ACTOR: u16 = 1;
@@ -1025,7 +1025,7 @@ pub(crate) async fn apply_manifest(cmd: ApplyCommand) -> Result<Vec<String>> {
     Ok(results)
 }
 
-async fn apply_manifest_actors(
+pub(crate) async fn apply_manifest_actors(
     host_id: &ServerId,
     client: &CtlClient,
     hm: &HostManifest,
@@ -1057,7 +1057,10 @@ async fn apply_manifest_actors(
     Ok(results)
 }
 
-async fn apply_manifest_linkdefs(client: &CtlClient, hm: &HostManifest) -> Result<Vec<String>> {
+pub(crate) async fn apply_manifest_linkdefs(
+    client: &CtlClient,
+    hm: &HostManifest,
+) -> Result<Vec<String>> {
     let mut results = vec![];
 
     for ld in hm.links.iter() {
@@ -1091,7 +1094,7 @@ async fn apply_manifest_linkdefs(client: &CtlClient, hm: &HostManifest) -> Resul
     Ok(results)
 }
 
-async fn apply_manifest_providers(
+pub(crate) async fn apply_manifest_providers(
     host_id: &ServerId,
     client: &CtlClient,
     hm: &HostManifest,
@@ -1129,10 +1132,40 @@ async fn apply_manifest_providers(
     Ok(results)
 }
 
-async fn ctl_client_from_opts(
+pub(crate) async fn ctl_client_from_opts(
     opts: ConnectionOpts,
     auction_timeout_ms: Option<u64>,
 ) -> Result<CtlClient> {
+    let auction_timeout_ms = auction_timeout_ms.unwrap_or(opts.timeout_ms);
+    let timeout_ms = opts.timeout_ms;
+    let (nc, lattice_prefix) = nats_client_from_opts(opts).await?;
+
+    let ctl_client = if let Ok(topic_prefix) = std::env::var("WASMCLOUD_CTL_TOPIC_PREFIX") {
+        CtlClient::new_with_topic_prefix(
+            nc,
+            &topic_prefix,
+            Some(lattice_prefix),
+            Duration::from_millis(timeout_ms),
+            Duration::from_millis(auction_timeout_ms),
+        )
+    } else {
+        CtlClient::new(
+            nc,
+            Some(lattice_prefix),
+            Duration::from_millis(timeout_ms),
+            Duration::from_millis(auction_timeout_ms),
+        )
+    };
+
+    Ok(ctl_client)
+}
+
+/// Connects to the NATS server of the control interface, taking explicitly provided flags,
+/// then the values of the provided (or default) context, and lastly the defaults. Returns the
+/// client along with the lattice prefix to use
+pub(crate) async fn nats_client_from_opts(
+    opts: ConnectionOpts,
+) -> Result<(async_nats::Client, String)> {
     // Attempt to load a context, falling back on the default if not supplied
     let ctx = if let Some(context) = opts.context {
         Some(load_context(context)?)
@@ -1181,31 +1214,13 @@ async fn ctl_client_from_opts(
             .map(|c| c.ctl_credsfile.clone())
             .unwrap_or_default()
     };
-    let auction_timeout_ms = auction_timeout_ms.unwrap_or(opts.timeout_ms);
 
     let nc =
         crate::util::nats_client_from_opts(&ctl_host, &ctl_port, ctl_jwt, ctl_seed, ctl_credsfile)
             .await
             .context("Failed to create NATS client")?;
 
-    let ctl_client = if let Ok(topic_prefix) = std::env::var("WASMCLOUD_CTL_TOPIC_PREFIX") {
-        CtlClient::new_with_topic_prefix(
-            nc,
-            &topic_prefix,
-            Some(lattice_prefix),
-            Duration::from_millis(opts.timeout_ms),
-            Duration::from_millis(auction_timeout_ms),
-        )
-    } else {
-        CtlClient::new(
-            nc,
-            Some(lattice_prefix),
-            Duration::from_millis(opts.timeout_ms),
-            Duration::from_millis(auction_timeout_ms),
-        )
-    };
-
-    Ok(ctl_client)
+    Ok((nc, lattice_prefix))
 }
 
 #[cfg(test)]
@@ -289,14 +289,14 @@ pub async fn wait_for_actor_stop_event(
 }
 
 /// Useful parts of a CloudEvent coming in from the wasmbus.
-struct CloudEventData {
-    event_type: String,
-    source: String,
-    data: serde_json::Value,
+pub(crate) struct CloudEventData {
+    pub(crate) event_type: String,
+    pub(crate) source: String,
+    pub(crate) data: serde_json::Value,
 }
 
 /// Get the useful parts out of a wasmbus cloud event.
-fn get_wasmbus_event_info(event: Event) -> Result<CloudEventData> {
+pub(crate) fn get_wasmbus_event_info(event: Event) -> Result<CloudEventData> {
     let data: serde_json::Value = event
         .data()
         .ok_or_else(|| anyhow!("No data in event"))?
@@ -27,6 +27,8 @@ use wash_lib::generate::{
     project_variables::StringEntry,
 };
 
+use crate::up::state::{env_dir, read_up_state_blocking, UpState};
+
 const CTX_DIR_NAME: &str = "contexts";
 
 pub(crate) async fn handle_command(ctx_cmd: CtxCommand) -> Result<CommandOutput> {
@@ -255,13 +257,23 @@ pub(crate) fn ensure_host_config_context(context_dir: &ContextDir) -> Result<()>
     create_host_config_context(context_dir)
 }
 
-/// Load the host configuration file and create a context called `host_config` from it
+/// Load the host configuration file and create a context called `host_config` from it, pointing it
+/// at the NATS server and lattice of the default `wash up` environment while that's running
 fn create_host_config_context(context_dir: &ContextDir) -> Result<()> {
-    let host_config_ctx = WashContext {
+    let mut host_config_ctx = WashContext {
         name: HOST_CONFIG_NAME.to_string(),
         ..load_context(cfg_dir()?.join(format!("{HOST_CONFIG_NAME}.json")))
             .unwrap_or_else(|_| WashContext::default())
     };
+    // A state file that can't be read shouldn't keep the context from being created
+    match env_dir(None).and_then(|dir| read_up_state_blocking(&dir)) {
+        Ok(Some(state)) => apply_up_state(&mut host_config_ctx, &state),
+        Ok(None) => (),
+        Err(e) => warn!(
+            "Ignoring the state of the running wash up environment: {}",
+            e
+        ),
+    }
     context_dir.save_context(&host_config_ctx)?;
     // Set the default context if it isn't set yet
     if context_dir.default_context()?.is_none() {
@@ -270,6 +282,22 @@ fn create_host_config_context(context_dir: &ContextDir) -> Result<()> {
     Ok(())
 }
 
+/// `wash up` may have picked the NATS port itself (`--nats-port auto`), so the state of a running
+/// environment knows better than the host configuration file where its NATS server is
+fn apply_up_state(ctx: &mut WashContext, state: &UpState) {
+    if let Some(host) = &state.nats_host {
+        ctx.ctl_host = host.clone();
+        ctx.rpc_host = host.clone();
+    }
+    if let Some(port) = state.nats_port {
+        ctx.ctl_port = port;
+        ctx.rpc_port = port;
+    }
+    if let Some(lattice_prefix) = &state.lattice_prefix {
+        ctx.lattice_prefix = lattice_prefix.clone();
+    }
+}
+
 /// Given an optional supplied directory, determine the context directory either from the supplied
 /// directory or using the home directory and the predefined `.wash/contexts` folder.
 pub(crate) fn context_dir(cmd_dir: Option<PathBuf>) -> Result<PathBuf> {
@@ -435,6 +463,36 @@ mod test {
         #[clap(subcommand)]
         cmd: CtxCommand,
     }
+
+    #[test]
+    fn test_apply_up_state() {
+        let mut ctx = WashContext::default();
+        let mut state = UpState {
+            nats_bin: None,
+            wasmcloud_bin: PathBuf::from("/tmp/downloads/v0.62.0/bin/wasmcloud_host"),
+            hosts: Vec::new(),
+            lattice_prefix: None,
+            nats_host: None,
+            nats_port: None,
+        };
+        // States written before wash up recorded its NATS server leave the context as it is
+        apply_up_state(&mut ctx, &state);
+        let default = WashContext::default();
+        assert_eq!(
+            (ctx.ctl_host.as_str(), ctx.ctl_port),
+            (default.ctl_host.as_str(), default.ctl_port)
+        );
+        assert_eq!(ctx.lattice_prefix, default.lattice_prefix);
+
+        state.lattice_prefix = Some("dev".to_string());
+        state.nats_host = Some("127.0.0.1".to_string());
+        state.nats_port = Some(53123);
+        apply_up_state(&mut ctx, &state);
+        assert_eq!((ctx.ctl_host.as_str(), ctx.ctl_port), ("127.0.0.1", 53123));
+        assert_eq!((ctx.rpc_host.as_str(), ctx.rpc_port), ("127.0.0.1", 53123));
+        assert_eq!(ctx.lattice_prefix, "dev");
+    }
+
     #[test]
     // Enumerates all options of ctx subcommands to ensure
     // changes are not made to the ctx API
@@ -5,17 +5,44 @@ use std::time::Duration;
 
 use anyhow::Result;
 use clap::Parser;
-use serde_json::json;
+use serde_json::{json, Value};
 use tokio::process::Command;
 use wash_lib::cli::{CommandOutput, OutputKind};
 use wash_lib::start::*;
 
 use crate::appearance::spinner::Spinner;
-use crate::cfg::cfg_dir;
-use crate::up::DOWNLOADS_DIR;
+use crate::up::config::NATS_SERVER_VERSION;
+use crate::up::state::{
+    env_dir, named_envs, parse_env_name, read_up_state, remove_up_state, DEFAULT_ENV, RELEASE_NODE,
+};
 
 #[derive(Parser, Debug, Clone)]
-pub(crate) struct DownCommand {}
+pub(crate) struct DownCommand {
+    /// Name of the environment to stop, as given to `wash up --name`. Stops the default environment if omitted
+    #[clap(long = "name", value_parser = parse_env_name)]
+    pub(crate) name: Option<String>,
+
+    /// Stop the default environment and every named environment
+    #[clap(long = "all", conflicts_with = "name")]
+    pub(crate) all: bool,
+}
+
+/// The NATS server binary to stop when no up state was recorded: the legacy unversioned binary if
+/// present, otherwise the default version's install or, failing that, any installed version
+async fn default_nats_bin(install_dir: &Path) -> Option<PathBuf> {
+    let legacy_bin = install_dir.join(NATS_SERVER_BINARY);
+    if legacy_bin.is_file() {
+        return Some(legacy_bin);
+    }
+    match find_nats_server_binary(install_dir, NATS_SERVER_VERSION).await {
+        Some(bin) => Some(bin),
+        None => installed_nats_servers(install_dir)
+            .await
+            .into_iter()
+            .next()
+            .map(|(_, bin)| bin),
+    }
+}
 
 pub(crate) async fn handle_command(
     command: DownCommand,
@@ -25,37 +52,103 @@ pub(crate) async fn handle_command(
 }
 
 pub(crate) async fn handle_down(
-    _cmd: DownCommand,
+    cmd: DownCommand,
     output_kind: OutputKind,
 ) -> Result<CommandOutput> {
-    let install_dir = cfg_dir()?.join(DOWNLOADS_DIR);
     let sp = Spinner::new(&output_kind)?;
 
+    let (mut out_text, mut out_json) = if cmd.all {
+        let mut names = vec![None];
+        names.extend(named_envs().await?.into_iter().map(Some));
+        let mut out_text = String::new();
+        let mut environments = HashMap::new();
+        for name in names {
+            let (text, env_json) = stop_env(name.as_deref(), &sp).await?;
+            if !text.is_empty() {
+                out_text.push_str(&format!(
+                    "Environment '{}':\n{}",
+                    name.as_deref().unwrap_or(DEFAULT_ENV),
+                    text
+                ));
+            }
+            environments.insert(name.unwrap_or_else(|| DEFAULT_ENV.to_string()), env_json);
+        }
+        let mut out_json = HashMap::new();
+        out_json.insert("environments".to_string(), json!(environments));
+        (out_text, out_json)
+    } else {
+        stop_env(cmd.name.as_deref(), &sp).await?
+    };
+
+    out_json.insert("success".to_string(), json!(true));
+    out_text.push_str("🛁 wash down completed successfully");
+
+    sp.finish_and_clear();
+    Ok(CommandOutput::new(out_text, out_json))
+}
+
+/// Stops the hosts and NATS server of an environment, returning what was stopped as text and JSON
+async fn stop_env(name: Option<&str>, sp: &Spinner) -> Result<(String, HashMap<String, Value>)> {
+    let state_dir = env_dir(name)?;
+
     let mut out_json = HashMap::new();
     let mut out_text = String::from("");
-    let host_bin = install_dir.join(WASMCLOUD_HOST_BIN);
+    // Stop every host and the NATS server the last `wash up` started, falling back to the default
+    // host and NATS server otherwise. Named environments have no such fallback
+    let (host_bin, node_names, nats_bin) = match (read_up_state(&state_dir).await?, name) {
+        (Some(state), _) => (
+            state.wasmcloud_bin,
+            state.hosts.into_iter().map(|h| h.node_name).collect(),
+            state.nats_bin,
+        ),
+        (None, None) => (
+            state_dir.join(WASMCLOUD_HOST_BIN),
+            vec![None],
+            default_nats_bin(&state_dir).await,
+        ),
+        (None, Some(name)) => {
+            out_json.insert("running".to_string(), json!(false));
+            out_text.push_str(&format!(
+                "🤔 Environment '{}' isn't running, assuming it's already stopped\n",
+                name
+            ));
+            return Ok((out_text, out_json));
+        }
+    };
     if host_bin.is_file() {
         sp.update_spinner_message(" Stopping host ...".to_string());
-        if let Ok(output) = stop_wasmcloud(host_bin).await {
-            if output.stderr.is_empty() && output.stdout.is_empty() {
+        let mut stopped = 0;
+        for node_name in &node_names {
+            if let Ok(output) = stop_wasmcloud(&host_bin, node_name.as_deref()).await {
                 // if there was a host running, 'stop' has no output.
-                // Give it time to stop before stopping nats
-                tokio::time::sleep(Duration::from_secs(6)).await;
-                out_json.insert("host_stopped".to_string(), json!(true));
-                out_text.push_str("✅ wasmCloud host stopped successfully\n");
+                if output.stderr.is_empty() && output.stdout.is_empty() {
+                    stopped += 1;
+                }
+            }
+        }
+        out_json.insert("host_stopped".to_string(), json!(true));
+        out_json.insert("hosts_stopped".to_string(), json!(stopped));
+        if stopped > 0 {
+            // Give hosts time to stop before stopping nats
+            tokio::time::sleep(Duration::from_secs(6)).await;
+            if node_names.len() > 1 {
+                out_text.push_str(&format!(
+                    "✅ {} wasmCloud hosts stopped successfully\n",
+                    stopped
+                ));
             } else {
-                out_json.insert("host_stopped".to_string(), json!(true));
-                out_text.push_str(
-                    "🤔 Host did not appear to be running, assuming it's already stopped\n",
-                );
+                out_text.push_str("✅ wasmCloud host stopped successfully\n");
             }
+        } else {
+            out_text
+                .push_str("🤔 Host did not appear to be running, assuming it's already stopped\n");
         }
     }
+    remove_up_state(&state_dir).await?;
 
-    let nats_bin = install_dir.join(NATS_SERVER_BINARY);
-    if nats_bin.is_file() {
+    if let Some(nats_bin) = nats_bin.filter(|bin| bin.is_file()) {
         sp.update_spinner_message(" Stopping NATS server ...".to_string());
-        if let Err(e) = stop_nats(install_dir).await {
+        if let Err(e) = stop_nats(nats_bin, &state_dir).await {
             out_json.insert("nats_stopped".to_string(), json!(false));
             out_text.push_str(&format!(
                 "❌ NATS server did not stop successfully: {:?}\n",
@@ -66,21 +159,20 @@ pub(crate) async fn handle_down(
             out_text.push_str("✅ NATS server stopped successfully\n");
         }
     }
-
-    out_json.insert("success".to_string(), json!(true));
-    out_text.push_str("🛁 wash down completed successfully");
-
-    sp.finish_and_clear();
-    Ok(CommandOutput::new(out_text, out_json))
+    Ok((out_text, out_json))
 }
 
-/// Helper function to send wasmCloud the `stop` command and wait for it to clean up
-pub(crate) async fn stop_wasmcloud<P>(bin_path: P) -> Result<Output>
+/// Helper function to send wasmCloud the `stop` command and wait for it to clean up. When
+/// several hosts run on this machine, `node_name` selects the one to stop
+pub(crate) async fn stop_wasmcloud<P>(bin_path: P, node_name: Option<&str>) -> Result<Output>
 where
     P: AsRef<Path>,
 {
-    Command::new(bin_path.as_ref())
-        .stdout(Stdio::piped())
+    let mut cmd = Command::new(bin_path.as_ref());
+    if let Some(node_name) = node_name {
+        cmd.env(RELEASE_NODE, node_name);
+    }
+    cmd.stdout(Stdio::piped())
         .arg("stop")
         .output()
         .await
@@ -88,18 +180,18 @@ where
 }
 
 /// Helper function to send the nats-server the stop command
-pub(crate) async fn stop_nats<P>(install_dir: P) -> Result<Output>
+pub(crate) async fn stop_nats<P, D>(bin_path: P, state_dir: D) -> Result<Output>
 where
     P: AsRef<Path>,
+    D: AsRef<Path>,
 {
-    let bin_path = install_dir.as_ref().join(NATS_SERVER_BINARY);
-    let pid_file = nats_pid_path(install_dir);
+    let pid_file = nats_pid_path(&bin_path, &state_dir);
     let signal = if pid_file.is_file() {
         format!("stop={}", &pid_file.display())
     } else {
         "stop".into()
     };
-    let output = Command::new(bin_path)
+    let output = Command::new(bin_path.as_ref())
         .arg("--signal")
         .arg(signal)
         .stdin(Stdio::null())
@@ -114,10 +206,21 @@ where
     output
 }
 
-/// Helper function to get the path to the NATS server pid file
-pub(crate) fn nats_pid_path<P>(install_dir: P) -> PathBuf
+/// Helper function to get the path to the NATS server pid file, which the server writes to the
+/// directory of its environment. Servers started by older versions of wash wrote it next to their
+/// binary instead
+pub(crate) fn nats_pid_path<P, D>(bin_path: P, state_dir: D) -> PathBuf
 where
     P: AsRef<Path>,
+    D: AsRef<Path>,
 {
-    install_dir.as_ref().join(NATS_SERVER_PID)
+    let pid_file = state_dir.as_ref().join(NATS_SERVER_PID);
+    if pid_file.is_file() {
+        return pid_file;
+    }
+    bin_path
+        .as_ref()
+        .parent()
+        .unwrap_or_else(|| Path::new("."))
+        .join(NATS_SERVER_PID)
 }
@@ -0,0 +1,554 @@
+use std::collections::{BTreeMap, HashMap};
+use std::io::SeekFrom;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
+
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Local, NaiveDateTime, NaiveTime, TimeZone};
+use clap::Parser;
+use console::style;
+use regex::Regex;
+use serde_json::{json, Map, Value};
+use tokio::io::{AsyncBufReadExt, AsyncSeekExt, BufReader};
+use wash_lib::cli::{CommandOutput, OutputKind};
+
+use crate::up::config::parse_key_value;
+use crate::up::state::{env_dir, parse_env_name, read_up_state};
+
+/// How often a followed log is checked for new lines
+const FOLLOW_INTERVAL: Duration = Duration::from_millis(250);
+/// Keys that hold the timestamp, level and message of a structured log line, in order of preference
+const TIME_KEYS: &[&str] = &["time", "timestamp"];
+const LEVEL_KEYS: &[&str] = &["level", "severity"];
+const MESSAGE_KEYS: &[&str] = &["message", "msg"];
+/// Key some JSON log formatters nest the metadata of a line under
+const METADATA_KEY: &str = "metadata";
+
+#[derive(Parser, Debug, Clone)]
+pub(crate) struct LogsCommand {
+    /// Whose logs to show, `host` or `nats`
+    #[clap(name = "source", default_value = "host")]
+    pub(crate) source: LogSource,
+
+    /// Name of the environment to show logs of, as given to `wash up --name`. Shows logs of the default environment if omitted
+    #[clap(long = "name", value_parser = parse_env_name)]
+    pub(crate) name: Option<String>,
+
+    /// Which host to show logs of when several were started with `wash up --hosts`, counting from 1
+    #[clap(long = "host", default_value_t = 1, value_parser = clap::value_parser!(u16).range(1..))]
+    pub(crate) host: u16,
+
+    /// Keep showing lines as they're written, until CTRL+C is pressed
+    #[clap(short = 'f', long = "follow")]
+    pub(crate) follow: bool,
+
+    /// Only show lines logged within this long ago, e.g. `10m` or `1h 30m`
+    #[clap(long = "since", value_parser = humantime::parse_duration)]
+    pub(crate) since: Option<Duration>,
+
+    /// Only show lines of this level or above: `trace`, `debug`, `info`, `warn` or `error`
+    #[clap(long = "level")]
+    pub(crate) level: Option<LogLevel>,
+
+    /// Only show lines matching this regular expression
+    #[clap(long = "grep")]
+    pub(crate) grep: Option<Regex>,
+
+    /// Only show structured log lines with this field, in the form `key=value`. Can be repeated
+    #[clap(long = "field", value_parser = parse_key_value)]
+    pub(crate) fields: Vec<(String, String)>,
+
+    /// Show structured log lines as they were written, instead of pretty-printing them
+    #[clap(long = "raw")]
+    pub(crate) raw: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum LogSource {
+    Host,
+    Nats,
+}
+
+impl FromStr for LogSource {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "host" => Ok(LogSource::Host),
+            "nats" => Ok(LogSource::Nats),
+            _ => bail!("'{}' is not a log source, use 'host' or 'nats'", s),
+        }
+    }
+}
+
+/// Severity of a log line, ordered from least to most severe
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum LogLevel {
+    Trace,
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+impl FromStr for LogLevel {
+    type Err = anyhow::Error;
+
+    /// Parses the level names used by the wasmCloud host and NATS, e.g. `warning` and `WRN`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "trace" | "trc" => Ok(LogLevel::Trace),
+            "debug" | "dbg" => Ok(LogLevel::Debug),
+            "info" | "inf" | "notice" => Ok(LogLevel::Info),
+            "warn" | "warning" | "wrn" => Ok(LogLevel::Warn),
+            "error" | "err" | "critical" | "alert" | "emergency" | "fatal" | "ftl" => {
+                Ok(LogLevel::Error)
+            }
+            _ => bail!(
+                "'{}' is not a log level, use 'trace', 'debug', 'info', 'warn' or 'error'",
+                s
+            ),
+        }
+    }
+}
+
+impl std::fmt::Display for LogLevel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.pad(match self {
+            LogLevel::Trace => "trace",
+            LogLevel::Debug => "debug",
+            LogLevel::Info => "info",
+            LogLevel::Warn => "warn",
+            LogLevel::Error => "error",
+        })
+    }
+}
+
+/// A line of a log, along with what could be parsed from it
+#[derive(Debug, Clone, PartialEq)]
+struct LogLine {
+    time: Option<DateTime<Local>>,
+    level: Option<LogLevel>,
+    message: String,
+    /// The fields of a structured log line, other than its time, level and message
+    fields: BTreeMap<String, Value>,
+    structured: bool,
+    raw: String,
+}
+
+impl LogLine {
+    fn to_text(&self, raw: bool) -> String {
+        if !self.structured || raw {
+            return self.raw.clone();
+        }
+        let mut text = String::new();
+        if let Some(time) = &self.time {
+            text.push_str(&format!("{} ", time.format("%Y-%m-%d %H:%M:%S%.3f")));
+        }
+        if let Some(level) = self.level {
+            let padded = format!("{:<5}", level);
+            let level = match level {
+                LogLevel::Error => style(padded).red(),
+                LogLevel::Warn => style(padded).yellow(),
+                _ => style(padded).dim(),
+            };
+            text.push_str(&format!("{} ", level));
+        }
+        text.push_str(&self.message);
+        for (key, value) in &self.fields {
+            text.push_str(&format!(" {}={}", style(key).dim(), field_text(value)));
+        }
+        text
+    }
+
+    fn to_json(&self) -> Value {
+        json!({
+            "time": self.time.map(|time| time.to_rfc3339()),
+            "level": self.level.map(|level| level.to_string()),
+            "message": self.message,
+            "fields": self.fields,
+            "line": self.raw,
+        })
+    }
+}
+
+fn field_text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Parses the lines of wasmCloud host and NATS logs, whether structured as JSON or not. Lines
+/// without a time or level of their own, like those of a stack trace, take those of the line
+/// before them
+struct LogParser {
+    now: DateTime<Local>,
+    nats_line: Regex,
+    elixir_line: Regex,
+    last_time: Option<DateTime<Local>>,
+    last_level: Option<LogLevel>,
+}
+
+impl LogParser {
+    fn new(now: DateTime<Local>) -> Self {
+        LogParser {
+            now,
+            // e.g. `[1234] 2023/02/01 12:00:00.000000 [INF] Server is ready`
+            nats_line: Regex::new(
+                r"^\[\d+\] (\d{4}/\d{2}/\d{2} \d{2}:\d{2}:\d{2}(?:\.\d+)?) \[([A-Z]{3})\] (.*)$",
+            )
+            .expect("valid regex"),
+            // e.g. `12:00:00.000 [info] Starting wasmCloud host`, with optional metadata before the level
+            elixir_line: Regex::new(
+                r"^(\d{2}:\d{2}:\d{2}(?:\.\d+)?) (?:[^\[]*)\[([a-z]+)\] ?(.*)$",
+            )
+            .expect("valid regex"),
+            last_time: None,
+            last_level: None,
+        }
+    }
+
+    fn parse(&mut self, raw: &str) -> LogLine {
+        let line = self
+            .parse_json(raw)
+            .or_else(|| self.parse_nats(raw))
+            .or_else(|| self.parse_elixir(raw))
+            .unwrap_or_else(|| LogLine {
+                time: None,
+                level: None,
+                message: raw.to_string(),
+                fields: BTreeMap::new(),
+                structured: false,
+                raw: raw.to_string(),
+            });
+        let line = LogLine {
+            time: line.time.or(self.last_time),
+            level: line.level.or(self.last_level),
+            ..line
+        };
+        self.last_time = line.time;
+        self.last_level = line.level;
+        line
+    }
+
+    fn parse_json(&self, raw: &str) -> Option<LogLine> {
+        let mut object: Map<String, Value> = serde_json::from_str(raw.trim()).ok()?;
+        let time = take_first(&mut object, TIME_KEYS).and_then(|time| {
+            DateTime::parse_from_rfc3339(time.as_str()?)
+                .ok()
+                .map(|time| time.with_timezone(&Local))
+        });
+        let level = take_first(&mut object, LEVEL_KEYS)
+            .and_then(|level| level.as_str().and_then(|level| level.parse().ok()));
+        let message = take_first(&mut object, MESSAGE_KEYS)
+            .map(|message| field_text(&message))
+            .unwrap_or_default();
+        let mut fields = BTreeMap::new();
+        for (key, value) in object {
+            match value {
+                Value::Object(metadata) if key == METADATA_KEY => fields.extend(metadata),
+                value => {
+                    fields.insert(key, value);
+                }
+            }
+        }
+        Some(LogLine {
+            time,
+            level,
+            message,
+            fields,
+            structured: true,
+            raw: raw.to_string(),
+        })
+    }
+
+    fn parse_nats(&self, raw: &str) -> Option<LogLine> {
+        let captures = self.nats_line.captures(raw)?;
+        let time = NaiveDateTime::parse_from_str(&captures[1], "%Y/%m/%d %H:%M:%S%.f")
+            .ok()
+            .and_then(|time| Local.from_local_datetime(&time).single());
+        Some(LogLine {
+            time,
+            level: captures[2].parse().ok(),
+            message: captures[3].to_string(),
+            fields: BTreeMap::new(),
+            structured: false,
+            raw: raw.to_string(),
+        })
+    }
+
+    fn parse_elixir(&self, raw: &str) -> Option<LogLine> {
+        let captures = self.elixir_line.captures(raw)?;
+        // Only the time of day is logged, which is assumed to be within the last day
+        let time = NaiveTime::parse_from_str(&captures[1], "%H:%M:%S%.f")
+            .ok()
+            .and_then(|time| {
+                Local
+                    .from_local_datetime(&self.now.date_naive().and_time(time))
+                    .single()
+            })
+            .map(|time| {
+                if time > self.now {
+                    time - chrono::Duration::days(1)
+                } else {
+                    time
+                }
+            });
+        Some(LogLine {
+            time,
+            level: captures[2].parse().ok(),
+            message: captures[3].to_string(),
+            fields: BTreeMap::new(),
+            structured: false,
+            raw: raw.to_string(),
+        })
+    }
+}
+
+fn take_first(object: &mut Map<String, Value>, keys: &[&str]) -> Option<Value> {
+    keys.iter().find_map(|key| object.remove(*key))
+}
+
+/// Decides which lines are shown, according to the filters of the command
+struct LogFilter {
+    since: Option<DateTime<Local>>,
+    level: Option<LogLevel>,
+    grep: Option<Regex>,
+    fields: Vec<(String, String)>,
+}
+
+impl LogFilter {
+    fn matches(&self, line: &LogLine) -> bool {
+        if let (Some(since), Some(time)) = (self.since, line.time) {
+            if time < since {
+                return false;
+            }
+        }
+        if let Some(min_level) = self.level {
+            if !matches!(line.level, Some(level) if level >= min_level) {
+                return false;
+            }
+        }
+        if let Some(grep) = &self.grep {
+            if !grep.is_match(&line.raw) {
+                return false;
+            }
+        }
+        self.fields.iter().all(|(key, value)| {
+            matches!(line.fields.get(key), Some(field) if field_text(field) == *value)
+        })
+    }
+}
+
+pub(crate) async fn handle_command(
+    command: LogsCommand,
+    output_kind: OutputKind,
+) -> Result<CommandOutput> {
+    let path = log_path(&command).await?;
+    let now = Local::now();
+    let filter = LogFilter {
+        since: command
+            .since
+            .map(|since| chrono::Duration::from_std(since).map(|since| now - since))
+            .transpose()
+            .context("--since is too long")?,
+        level: command.level,
+        grep: command.grep.clone(),
+        fields: command.fields.clone(),
+    };
+    let mut parser = LogParser::new(now);
+    // Lines are printed as they're read, or returned together as one JSON document
+    let mut json_lines = Vec::new();
+    let mut show = |raw: &str| {
+        let line = parser.parse(raw);
+        if filter.matches(&line) {
+            match output_kind {
+                OutputKind::Text => println!("{}", line.to_text(command.raw)),
+                OutputKind::Json => json_lines.push(line.to_json()),
+            }
+        }
+    };
+
+    let file = tokio::fs::File::open(&path)
+        .await
+        .with_context(|| format!("Failed to open {}", path.display()))?;
+    let mut reader = BufReader::new(file);
+    let mut buf = String::new();
+    // Created once, so a CTRL+C pressed while lines are being read isn't lost
+    let ctrl_c = tokio::signal::ctrl_c();
+    tokio::pin!(ctrl_c);
+    loop {
+        let read = reader.read_line(&mut buf).await?;
+        if read > 0 {
+            // A line without a newline at the end of the log may still be being written
+            if buf.ends_with('\n') {
+                show(buf.trim_end());
+                buf.clear();
+            }
+            continue;
+        }
+        if !command.follow {
+            if !buf.is_empty() {
+                show(buf.trim_end());
+            }
+            break;
+        }
+        // `wash up` truncates the log when it starts the host or NATS again
+        if tokio::fs::metadata(&path).await?.len() < reader.stream_position().await? {
+            reader.seek(SeekFrom::Start(0)).await?;
+            buf.clear();
+        }
+        tokio::select! {
+            _ = tokio::time::sleep(FOLLOW_INTERVAL) => {}
+            _ = &mut ctrl_c => break,
+        }
+    }
+
+    let mut map = HashMap::new();
+    if output_kind == OutputKind::Json {
+        map.insert("log_path".to_string(), json!(path));
+        map.insert("lines".to_string(), json!(json_lines));
+    }
+    Ok(CommandOutput::new("", map))
+}
+
+/// Finds the log of the host or NATS server of an environment
+async fn log_path(command: &LogsCommand) -> Result<PathBuf> {
+    let state_dir = env_dir(command.name.as_deref())?;
+    let path = match (command.source, read_up_state(&state_dir).await?) {
+        (LogSource::Nats, _) => state_dir.join("nats.log"),
+        (LogSource::Host, Some(state)) => match state.hosts.get(command.host as usize - 1) {
+            Some(host) => host.log_path.clone(),
+            None => bail!(
+                "There is no host {}, wasmCloud was started with {} host(s)",
+                command.host,
+                state.hosts.len()
+            ),
+        },
+        (LogSource::Host, None) if command.host == 1 => state_dir.join("wasmcloud.log"),
+        (LogSource::Host, None) => {
+            bail!("There is no host {}, wasmCloud isn't running", command.host)
+        }
+    };
+    if !path.is_file() {
+        bail!(
+            "There is no log at {}. Only wasmCloud started with \"wash up --detached\" writes the logs of its first host to a file",
+            path.display()
+        );
+    }
+    Ok(path)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{LogFilter, LogLevel, LogParser, LogsCommand};
+    use chrono::{Local, TimeZone};
+    use clap::Parser;
+    use serde_json::json;
+
+    #[test]
+    fn test_logs_comprehensive() {
+        let logs: LogsCommand = Parser::try_parse_from([
+            "logs",
+            "nats",
+            "--name",
+            "leaf",
+            "--follow",
+            "--since",
+            "10m",
+            "--level",
+            "warn",
+            "--grep",
+            "leaf",
+            "--field",
+            "actor_id=MABC",
+        ])
+        .unwrap();
+        assert_eq!(logs.source, super::LogSource::Nats);
+        assert!(logs.follow);
+        assert_eq!(logs.since, Some(std::time::Duration::from_secs(600)));
+        assert_eq!(logs.level, Some(LogLevel::Warn));
+        assert_eq!(
+            logs.fields,
+            vec![("actor_id".to_string(), "MABC".to_string())]
+        );
+
+        assert!(LogsCommand::try_parse_from(["logs", "wadm"]).is_err());
+        assert!(LogsCommand::try_parse_from(["logs", "--level", "loud"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_log_lines() {
+        let now = Local.with_ymd_and_hms(2023, 2, 1, 12, 0, 0).unwrap();
+        let mut parser = LogParser::new(now);
+
+        let line = parser.parse(r#"{"time":"2023-02-01T11:55:00.000Z","severity":"warn","message":"Actor failed","metadata":{"actor_id":"MABC"}}"#);
+        assert!(line.structured);
+        assert_eq!(line.level, Some(LogLevel::Warn));
+        assert_eq!(line.message, "Actor failed");
+        assert_eq!(line.fields.get("actor_id"), Some(&json!("MABC")));
+
+        let line = parser.parse("[42] 2023/02/01 11:58:00.123456 [ERR] Leafnode connection closed");
+        assert_eq!(line.level, Some(LogLevel::Error));
+        assert_eq!(
+            line.time,
+            Some(
+                Local.with_ymd_and_hms(2023, 2, 1, 11, 58, 0).unwrap()
+                    + chrono::Duration::microseconds(123_456)
+            )
+        );
+        assert_eq!(line.message, "Leafnode connection closed");
+
+        // Times later in the day than now were logged yesterday
+        let line = parser.parse("13:00:00.000 [info] Starting wasmCloud host");
+        assert_eq!(line.level, Some(LogLevel::Info));
+        assert_eq!(
+            line.time,
+            Some(Local.with_ymd_and_hms(2023, 1, 31, 13, 0, 0).unwrap())
+        );
+
+        // Continuation lines belong to the line before them
+        let line = parser.parse("    (stdlib) gen_server.erl:1123: :gen_server.try_dispatch/4");
+        assert_eq!(line.level, Some(LogLevel::Info));
+        assert!(!line.structured);
+    }
+
+    #[test]
+    fn test_filter_log_lines() {
+        let now = Local.with_ymd_and_hms(2023, 2, 1, 12, 0, 0).unwrap();
+        let mut parser = LogParser::new(now);
+        let filter = LogFilter {
+            since: Some(now - chrono::Duration::minutes(10)),
+            level: Some(LogLevel::Warn),
+            grep: None,
+            fields: vec![("actor_id".to_string(), "MABC".to_string())],
+        };
+        let mut line = |minutes_ago: i64, level: &str, actor_id: &str| {
+            parser.parse(
+                &json!({
+                    "time": (now - chrono::Duration::minutes(minutes_ago)).to_rfc3339(),
+                    "level": level,
+                    "msg": "Actor failed",
+                    "actor_id": actor_id,
+                })
+                .to_string(),
+            )
+        };
+        assert!(filter.matches(&line(5, "warn", "MABC")));
+        assert!(filter.matches(&line(5, "error", "MABC")));
+        assert!(!filter.matches(&line(5, "info", "MABC")));
+        assert!(!filter.matches(&line(5, "error", "MDEF")));
+        assert!(!filter.matches(&line(15, "error", "MABC")));
+
+        let grep = LogFilter {
+            since: None,
+            level: None,
+            grep: Some(regex::Regex::new("Leafnode").unwrap()),
+            fields: Vec::new(),
+        };
+        assert!(grep.matches(&parser.parse("[42] 2023/02/01 11:58:00 [INF] Leafnode connected")));
+        assert!(!grep.matches(&parser.parse("[42] 2023/02/01 11:58:00 [INF] Server is ready")));
+    }
+}
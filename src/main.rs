@@ -10,11 +10,15 @@ use ctx::CtxCommand;
 use down::DownCommand;
 use generate::NewCliCommand;
 use keys::KeysCliCommand;
+use logs::LogsCommand;
 use par::ParCliCommand;
 use reg::RegCliCommand;
 use serde_json::json;
 use smithy::{GenerateCli, LintCli, ValidateCli};
+use spy::SpyCommand;
+use status::StatusCommand;
 use up::UpCommand;
+use wash_lib::app::AppError;
 use wash_lib::cli::claims::ClaimsCliCommand;
 use wash_lib::cli::{CommandOutput, OutputKind};
 use wash_lib::drain::Drain as DrainSelection;
@@ -30,9 +34,12 @@ mod down;
 mod drain;
 mod generate;
 mod keys;
+mod logs;
 mod par;
 mod reg;
 mod smithy;
+mod spy;
+mod status;
 mod up;
 mod util;
 
@@ -74,7 +81,7 @@ enum CliCommand {
     /// Build (and sign) a wasmCloud actor, provider, or interface
     #[clap(name = "build")]
     Build(BuildCommand),
-    /// Invoke a wasmCloud actor
+    /// Invoke a wasmCloud actor or capability provider
     #[clap(name = "call")]
     Call(CallCli),
     /// Generate and manage JWTs for wasmCloud actors
@@ -101,6 +108,9 @@ enum CliCommand {
     /// Perform lint checks on smithy models
     #[clap(name = "lint")]
     Lint(LintCli),
+    /// Show the logs of a wasmCloud host or NATS server launched with wash up
+    #[clap(name = "logs")]
+    Logs(LogsCommand),
     /// Create a new project from template
     #[clap(name = "new", subcommand)]
     New(NewCliCommand),
@@ -110,6 +120,12 @@ enum CliCommand {
     /// Interact with OCI compliant registries
     #[clap(name = "reg", subcommand)]
     Reg(RegCliCommand),
+    /// Watch and record the invocations sent to and from an actor
+    #[clap(name = "spy")]
+    Spy(SpyCommand),
+    /// Report what a wasmCloud environment launched with wash up is running
+    #[clap(name = "status")]
+    Status(StatusCommand),
     /// Bootstrap a wasmCloud environment
     #[clap(name = "up")]
     Up(UpCommand),
@@ -139,9 +155,12 @@ async fn main() {
         CliCommand::Gen(generate_cli) => smithy::handle_gen_command(generate_cli),
         CliCommand::Keys(keys_cli) => keys::handle_command(keys_cli),
         CliCommand::Lint(lint_cli) => smithy::handle_lint_command(lint_cli).await,
+        CliCommand::Logs(logs_cli) => logs::handle_command(logs_cli, output_kind).await,
         CliCommand::New(new_cli) => generate::handle_command(new_cli).await,
         CliCommand::Par(par_cli) => par::handle_command(par_cli, output_kind).await,
         CliCommand::Reg(reg_cli) => reg::handle_command(reg_cli, output_kind).await,
+        CliCommand::Spy(spy_cli) => spy::handle_command(spy_cli, output_kind).await,
+        CliCommand::Status(status_cli) => status::handle_command(status_cli, output_kind).await,
         CliCommand::Up(up_cli) => up::handle_command(up_cli, output_kind).await,
         CliCommand::Validate(validate_cli) => smithy::handle_validate_command(validate_cli).await,
     };
@@ -178,6 +197,13 @@ async fn main() {
                         map.insert("error_chain".to_string(), json!(error_chain));
                     }
 
+                    if let Some(app_error) = e.chain().find_map(|e| e.downcast_ref::<AppError>()) {
+                        map.insert("wadm_error".to_string(), json!(app_error.kind()));
+                        if let AppError::Rejected(message) = app_error {
+                            map.insert("wadm_message".to_string(), json!(message));
+                        }
+                    }
+
                     let backtrace = e.backtrace().to_string();
 
                     if !backtrace.is_empty() && backtrace != "disabled backtrace" {
@@ -0,0 +1,368 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{Context, Result};
+use clap::Args;
+use futures::StreamExt;
+use log::debug;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use wash_lib::cli::{CommandOutput, OutputKind};
+use wash_lib::id::ModuleId;
+use wasmbus_rpc::core::{Invocation, InvocationResponse, WasmCloudEntity};
+
+use crate::{
+    call::{nats_client_and_context_from_opts, ConnectionOpts},
+    util::{msgpack_to_json_val, BinaryDisplay},
+};
+
+/// Prefix of the reply subjects NATS clients use for requests, which carry invocation responses
+const INBOX_PREFIX: &str = "_INBOX";
+
+#[derive(Debug, Args, Clone)]
+pub(crate) struct SpyCommand {
+    #[clap(flatten)]
+    opts: ConnectionOpts,
+
+    /// When using json output, display binary as binary('b'), string('s'), or both('2')
+    #[clap(long, default_value = "b")]
+    bin: BinaryDisplay,
+
+    /// Optional file to record invocations to. Recordings can be re-sent with `wash call --replay`
+    #[clap(long = "record")]
+    record: Option<PathBuf>,
+
+    /// Public key of the actor to spy on
+    #[clap(name = "actor-id", value_parser)]
+    actor_id: ModuleId,
+}
+
+/// A single invocation or invocation response observed by `wash spy`, as it is stored in
+/// recording files (one JSON object per line)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub(crate) enum SpyRecord {
+    Request {
+        id: String,
+        timestamp_ms: u64,
+        origin: WasmCloudEntity,
+        target: WasmCloudEntity,
+        operation: String,
+        #[serde(with = "base64_bytes")]
+        payload: Vec<u8>,
+    },
+    Response {
+        id: String,
+        timestamp_ms: u64,
+        operation: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        error: Option<String>,
+        #[serde(with = "base64_bytes")]
+        payload: Vec<u8>,
+    },
+}
+
+impl SpyRecord {
+    /// Renders the record for display, decoding the msgpack payload
+    fn to_json(&self, bin: BinaryDisplay) -> serde_json::Value {
+        match self {
+            SpyRecord::Request {
+                id,
+                timestamp_ms,
+                origin,
+                target,
+                operation,
+                payload,
+            } => json!({
+                "kind": "request",
+                "id": id,
+                "timestamp_ms": timestamp_ms,
+                "origin": origin.url(),
+                "target": target.url(),
+                "operation": operation,
+                "payload": msgpack_to_json_val(payload.clone(), bin),
+            }),
+            SpyRecord::Response {
+                id,
+                timestamp_ms,
+                operation,
+                error,
+                payload,
+            } => json!({
+                "kind": "response",
+                "id": id,
+                "timestamp_ms": timestamp_ms,
+                "operation": operation,
+                "error": error,
+                "payload": msgpack_to_json_val(payload.clone(), bin),
+            }),
+        }
+    }
+
+    fn to_text(&self, bin: BinaryDisplay) -> String {
+        match self {
+            SpyRecord::Request {
+                origin,
+                target,
+                operation,
+                payload,
+                ..
+            } => format!(
+                "--> {} => {} {}: {}",
+                origin.url(),
+                target.url(),
+                operation,
+                msgpack_to_json_val(payload.clone(), bin)
+            ),
+            SpyRecord::Response {
+                operation,
+                error: Some(error),
+                ..
+            } => format!("<-- {} failed: {}", operation, error),
+            SpyRecord::Response {
+                operation, payload, ..
+            } => format!(
+                "<-- {}: {}",
+                operation,
+                msgpack_to_json_val(payload.clone(), bin)
+            ),
+        }
+    }
+}
+
+/// Reads the invocations recorded with `wash spy --record`, skipping responses
+pub(crate) fn read_recorded_requests(path: impl AsRef<Path>) -> Result<Vec<SpyRecord>> {
+    let file = File::open(path.as_ref())
+        .with_context(|| format!("Failed to open recording {}", path.as_ref().display()))?;
+    let mut requests = Vec::new();
+    for (idx, line) in BufReader::new(file).lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let record: SpyRecord = serde_json::from_str(&line)
+            .with_context(|| format!("Invalid recording entry on line {}", idx + 1))?;
+        if matches!(record, SpyRecord::Request { .. }) {
+            requests.push(record);
+        }
+    }
+    Ok(requests)
+}
+
+pub(crate) async fn handle_command(
+    cmd: SpyCommand,
+    output_kind: OutputKind,
+) -> Result<CommandOutput> {
+    let actor_id = cmd.actor_id.to_string();
+    let lattice_prefix = cmd.opts.lattice_prefix();
+    let (client, _) = nats_client_and_context_from_opts(&cmd.opts).await?;
+
+    let mut invocations = client
+        .subscribe(format!("wasmbus.rpc.{}.>", lattice_prefix))
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to subscribe to lattice RPC subjects: {}", e))?;
+    let mut replies = client
+        .subscribe(format!("{}.>", INBOX_PREFIX))
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to subscribe to invocation responses: {}", e))?;
+
+    let mut recording = match &cmd.record {
+        Some(path) => Some(
+            File::create(path)
+                .with_context(|| format!("Failed to create recording {}", path.display()))?,
+        ),
+        None => None,
+    };
+
+    if output_kind == OutputKind::Text {
+        println!(
+            "Spying on actor {} in lattice {}, press CTRL+C to stop",
+            actor_id, lattice_prefix
+        );
+    }
+
+    // Reply subjects of the invocations we've seen, mapped to their operation
+    let mut pending: HashMap<String, String> = HashMap::new();
+    let mut recorded = 0usize;
+    // Records are printed as they're observed, or returned together as one JSON document
+    let mut json_records = Vec::new();
+    // Created once, so a CTRL+C pressed while a message is being handled isn't lost
+    let ctrl_c = tokio::signal::ctrl_c();
+    tokio::pin!(ctrl_c);
+    loop {
+        let record = tokio::select! {
+            Some(msg) = invocations.next() => {
+                let inv = match wasmbus_rpc::common::deserialize::<Invocation>(&msg.payload) {
+                    Ok(inv) => inv,
+                    Err(e) => {
+                        debug!("Ignoring message on {} that is not an invocation: {}", msg.subject, e);
+                        continue;
+                    }
+                };
+                if inv.origin.public_key != actor_id && inv.target.public_key != actor_id {
+                    continue;
+                }
+                if let Some(reply) = msg.reply {
+                    pending.insert(reply, inv.operation.clone());
+                }
+                SpyRecord::Request {
+                    id: inv.id,
+                    timestamp_ms: now_ms(),
+                    origin: inv.origin,
+                    target: inv.target,
+                    operation: inv.operation,
+                    payload: inv.msg,
+                }
+            }
+            Some(msg) = replies.next() => {
+                let operation = match pending.remove(&msg.subject) {
+                    Some(operation) => operation,
+                    None => continue,
+                };
+                let resp = match wasmbus_rpc::common::deserialize::<InvocationResponse>(&msg.payload) {
+                    Ok(resp) => resp,
+                    Err(e) => {
+                        debug!("Ignoring response to {} that could not be decoded: {}", operation, e);
+                        continue;
+                    }
+                };
+                SpyRecord::Response {
+                    id: resp.invocation_id,
+                    timestamp_ms: now_ms(),
+                    operation,
+                    error: resp.error,
+                    payload: resp.msg,
+                }
+            }
+            _ = &mut ctrl_c => break,
+            else => break,
+        };
+
+        match output_kind {
+            OutputKind::Text => println!("{}", record.to_text(cmd.bin)),
+            OutputKind::Json => json_records.push(record.to_json(cmd.bin)),
+        }
+        if let Some(file) = recording.as_mut() {
+            serde_json::to_writer(&mut *file, &record)?;
+            writeln!(file)?;
+            file.flush()?;
+        }
+        recorded += 1;
+    }
+
+    let mut map = HashMap::new();
+    map.insert("actor_id".to_string(), json!(actor_id));
+    map.insert("messages".to_string(), json!(recorded));
+    if output_kind == OutputKind::Json {
+        map.insert("records".to_string(), json!(json_records));
+    }
+    let text = match &cmd.record {
+        Some(path) => {
+            map.insert("recording".to_string(), json!(path));
+            format!(
+                "Recorded {} messages for actor {} to {}",
+                recorded,
+                actor_id,
+                path.display()
+            )
+        }
+        None => format!("Observed {} messages for actor {}", recorded, actor_id),
+    };
+    Ok(CommandOutput::new(text, map))
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+/// Stores binary payloads in recordings as base64 strings
+mod base64_bytes {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub(super) fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&base64::encode(bytes))
+    }
+
+    pub(super) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<u8>, D::Error> {
+        let encoded = String::deserialize(deserializer)?;
+        base64::decode(encoded).map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{read_recorded_requests, SpyCommand, SpyRecord};
+    use anyhow::Result;
+    use clap::Parser;
+    use std::{io::Write, path::PathBuf};
+    use wasmbus_rpc::core::WasmCloudEntity;
+
+    const ACTOR_ID: &str = "MDPDJEYIAK6MACO67PRFGOSSLODBISK4SCEYDY3HEOY4P5CVJN6UCWUK";
+
+    #[derive(Debug, Parser)]
+    struct Cmd {
+        #[clap(flatten)]
+        command: SpyCommand,
+    }
+
+    #[test]
+    fn test_spy_comprehensive() -> Result<()> {
+        let spy: Cmd = Parser::try_parse_from([
+            "spy",
+            "--record",
+            "traffic.jsonl",
+            "--bin",
+            "s",
+            "--lattice-prefix",
+            "prod",
+            ACTOR_ID,
+        ])?;
+        assert_eq!(spy.command.actor_id.to_string(), ACTOR_ID);
+        assert_eq!(spy.command.record, Some(PathBuf::from("traffic.jsonl")));
+        assert_eq!(spy.command.opts.lattice_prefix(), "prod");
+
+        let not_an_actor: Result<Cmd, _> = Parser::try_parse_from(["spy", "echo"]);
+        assert!(not_an_actor.is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_recording_roundtrip() -> Result<()> {
+        let request = SpyRecord::Request {
+            id: "abc".to_string(),
+            timestamp_ms: 1,
+            origin: WasmCloudEntity::new_actor(ACTOR_ID)?,
+            target: WasmCloudEntity::new_provider(
+                "VBKTSBG2WKP6RJWLQ5O7RDVIIB4LMW6U5R67A7QMIDBZDGZWYTUE3TSI",
+                "wasmcloud:keyvalue",
+            )?,
+            operation: "KeyValue.Get".to_string(),
+            payload: vec![0xa3, b'f', b'o', b'o'],
+        };
+        let response = SpyRecord::Response {
+            id: "abc".to_string(),
+            timestamp_ms: 2,
+            operation: "KeyValue.Get".to_string(),
+            error: None,
+            payload: vec![0xc3],
+        };
+
+        let mut file = tempfile::NamedTempFile::new()?;
+        for record in [&request, &response] {
+            writeln!(file, "{}", serde_json::to_string(record)?)?;
+        }
+
+        assert_eq!(read_recorded_requests(file.path())?, vec![request]);
+        Ok(())
+    }
+}
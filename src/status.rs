@@ -0,0 +1,412 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+
+use anyhow::Result;
+use clap::Parser;
+use serde::Serialize;
+use serde_json::json;
+use tokio::process::Command;
+use wash_lib::cli::{CommandOutput, OutputKind};
+use wash_lib::config::{DEFAULT_NATS_HOST, DEFAULT_NATS_PORT, DEFAULT_NATS_TIMEOUT_MS};
+use wash_lib::start::{installed_nats_servers, installed_wasmcloud_versions};
+
+use crate::appearance::spinner::Spinner;
+use crate::cfg::cfg_dir;
+use crate::ctl::{ctl_client_from_opts, ConnectionOpts};
+use crate::down::nats_pid_path;
+use crate::up::state::{
+    env_dir, named_envs, parse_env_name, read_up_state, UpState, DEFAULT_ENV, RELEASE_NODE,
+    UP_STATE_FILE,
+};
+use crate::up::DOWNLOADS_DIR;
+use crate::util::convert_error;
+
+#[derive(Parser, Debug, Clone)]
+pub(crate) struct StatusCommand {
+    /// Name of the environment to report on, as given to `wash up --name`. Reports on the default environment if omitted
+    #[clap(long = "name", value_parser = parse_env_name)]
+    pub(crate) name: Option<String>,
+
+    /// Report on the default environment and every named environment
+    #[clap(long = "all", conflicts_with = "name")]
+    pub(crate) all: bool,
+
+    /// How long to wait, in milliseconds, for the hosts to answer on the control interface
+    #[clap(long = "timeout-ms", default_value_t = DEFAULT_NATS_TIMEOUT_MS)]
+    pub(crate) timeout_ms: u64,
+}
+
+/// The NATS server started by `wash up`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+struct NatsStatus {
+    version: Option<String>,
+    /// Read from the pid file of the server
+    pid: Option<u32>,
+    host: String,
+    port: u16,
+    /// Whether the server accepts connections
+    listening: bool,
+    uptime_seconds: Option<u64>,
+    log_path: PathBuf,
+}
+
+/// A wasmCloud host started by `wash up`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+struct HostStatus {
+    host_id: Option<String>,
+    node_name: Option<String>,
+    version: Option<String>,
+    /// Reported by the release's `pid` command, which fails when the host isn't running
+    pid: Option<u32>,
+    dashboard_port: u16,
+    /// Whether the host answered a request on the control interface
+    responsive: bool,
+    uptime_seconds: Option<u64>,
+    log_path: PathBuf,
+}
+
+/// What a `wash up` environment is running. `nats` is `None` when `wash up` connected to an
+/// existing NATS server rather than starting one
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+struct EnvStatus {
+    running: bool,
+    lattice_prefix: Option<String>,
+    nats: Option<NatsStatus>,
+    hosts: Vec<HostStatus>,
+}
+
+pub(crate) async fn handle_command(
+    command: StatusCommand,
+    output_kind: OutputKind,
+) -> Result<CommandOutput> {
+    let install_dir = cfg_dir()?.join(DOWNLOADS_DIR);
+    let timeout = Duration::from_millis(command.timeout_ms);
+    let sp = Spinner::new(&output_kind)?;
+    sp.update_spinner_message(" Checking wasmCloud and NATS ...".to_string());
+
+    let mut out_json = HashMap::new();
+    let out_text = if command.all {
+        let mut names = vec![None];
+        names.extend(named_envs().await?.into_iter().map(Some));
+        let mut out_text = String::new();
+        let mut environments = HashMap::new();
+        for name in names {
+            let status = env_status(name.as_deref(), &install_dir, timeout).await?;
+            let _ = writeln!(
+                out_text,
+                "Environment '{}':\n{}",
+                name.as_deref().unwrap_or(DEFAULT_ENV),
+                status_text(name.as_deref(), status.as_ref())
+            );
+            environments.insert(name.unwrap_or_else(|| DEFAULT_ENV.to_string()), status);
+        }
+        let running = environments
+            .values()
+            .any(|status| matches!(status, Some(s) if s.running));
+        out_json.insert("running".to_string(), json!(running));
+        out_json.insert("environments".to_string(), json!(environments));
+        out_text.trim_end().to_string()
+    } else {
+        let status = env_status(command.name.as_deref(), &install_dir, timeout).await?;
+        out_json.insert(
+            "running".to_string(),
+            json!(matches!(&status, Some(s) if s.running)),
+        );
+        out_json.insert("name".to_string(), json!(command.name));
+        if let Some(status) = &status {
+            out_json.insert("lattice_prefix".to_string(), json!(status.lattice_prefix));
+            out_json.insert("nats".to_string(), json!(status.nats));
+            out_json.insert("hosts".to_string(), json!(status.hosts));
+        }
+        status_text(command.name.as_deref(), status.as_ref())
+    };
+
+    sp.finish_and_clear();
+    Ok(CommandOutput::new(out_text, out_json))
+}
+
+/// Checks the processes recorded in the state of an environment, or returns `None` when it
+/// hasn't been started
+async fn env_status(
+    name: Option<&str>,
+    install_dir: &Path,
+    timeout: Duration,
+) -> Result<Option<EnvStatus>> {
+    let state_dir = env_dir(name)?;
+    let state = match read_up_state(&state_dir).await? {
+        Some(state) => state,
+        None => return Ok(None),
+    };
+    let nats_host = state
+        .nats_host
+        .clone()
+        .unwrap_or_else(|| DEFAULT_NATS_HOST.to_string());
+    let nats_port = match state.nats_port {
+        Some(port) => port,
+        None => DEFAULT_NATS_PORT.parse()?,
+    };
+
+    let nats = match &state.nats_bin {
+        Some(nats_bin) => {
+            let pid_file = nats_pid_path(nats_bin, &state_dir);
+            let listening = tokio::net::TcpStream::connect((nats_host.as_str(), nats_port))
+                .await
+                .is_ok();
+            Some(NatsStatus {
+                version: installed_version(installed_nats_servers(install_dir).await, nats_bin),
+                pid: tokio::fs::read_to_string(&pid_file)
+                    .await
+                    .ok()
+                    .and_then(|pid| pid.trim().parse().ok()),
+                host: nats_host.clone(),
+                port: nats_port,
+                listening,
+                // NATS writes its pid file as it starts
+                uptime_seconds: if listening {
+                    seconds_since_modified(&pid_file)
+                } else {
+                    None
+                },
+                log_path: state_dir.join("nats.log"),
+            })
+        }
+        None => None,
+    };
+
+    let answering = answering_hosts(&state, nats_host, nats_port, timeout).await;
+    let wasmcloud_version = installed_version(
+        installed_wasmcloud_versions(install_dir).await,
+        &state.wasmcloud_bin,
+    );
+    // The state is written right after the hosts are started, which is the best estimate of
+    // their uptime when they don't answer themselves
+    let started = seconds_since_modified(&state_dir.join(UP_STATE_FILE));
+    let mut hosts = Vec::with_capacity(state.hosts.len());
+    for host in &state.hosts {
+        let pid = wasmcloud_pid(&state.wasmcloud_bin, host.node_name.as_deref()).await;
+        let answered = host.host_id.as_ref().and_then(|id| answering.get(id));
+        hosts.push(HostStatus {
+            host_id: host.host_id.clone(),
+            node_name: host.node_name.clone(),
+            version: wasmcloud_version.clone(),
+            pid,
+            dashboard_port: host.dashboard_port,
+            responsive: answered.is_some(),
+            uptime_seconds: answered.copied().or(pid.and(started)),
+            log_path: host.log_path.clone(),
+        });
+    }
+
+    Ok(Some(EnvStatus {
+        running: hosts.iter().any(|h| h.pid.is_some() || h.responsive),
+        lattice_prefix: state.lattice_prefix,
+        nats,
+        hosts,
+    }))
+}
+
+/// Asks the lattice of an environment which hosts are up, returning the uptime of each host that
+/// answered. No host answers when NATS can't be reached
+async fn answering_hosts(
+    state: &UpState,
+    nats_host: String,
+    nats_port: u16,
+    timeout: Duration,
+) -> HashMap<String, u64> {
+    let opts = ConnectionOpts {
+        ctl_host: Some(nats_host),
+        ctl_port: Some(nats_port.to_string()),
+        lattice_prefix: state.lattice_prefix.clone(),
+        timeout_ms: timeout.as_millis() as u64,
+        ..Default::default()
+    };
+    let hosts = async {
+        let client = ctl_client_from_opts(opts, None).await?;
+        client.get_hosts().await.map_err(convert_error)
+    };
+    // Hosts are collected for the length of the timeout, allow as long again to connect
+    match tokio::time::timeout(timeout * 2, hosts).await {
+        Ok(Ok(hosts)) => hosts
+            .into_iter()
+            .map(|host| (host.id, host.uptime_seconds))
+            .collect(),
+        _ => HashMap::new(),
+    }
+}
+
+/// Asks the release script of a host for its pid, which fails when the host isn't running
+async fn wasmcloud_pid(bin_path: &Path, node_name: Option<&str>) -> Option<u32> {
+    let mut cmd = Command::new(bin_path);
+    if let Some(node_name) = node_name {
+        cmd.env(RELEASE_NODE, node_name);
+    }
+    let output = cmd
+        .arg("pid")
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .await
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    String::from_utf8_lossy(&output.stdout).trim().parse().ok()
+}
+
+/// Finds the version a binary was installed as
+fn installed_version(installed: Vec<(String, PathBuf)>, bin_path: &Path) -> Option<String> {
+    installed
+        .into_iter()
+        .find(|(_, path)| path == bin_path)
+        .map(|(version, _)| version)
+}
+
+fn seconds_since_modified(path: &Path) -> Option<u64> {
+    let modified = std::fs::metadata(path).ok()?.modified().ok()?;
+    modified.elapsed().ok().map(|elapsed| elapsed.as_secs())
+}
+
+fn status_text(name: Option<&str>, status: Option<&EnvStatus>) -> String {
+    let up_cmd = match name {
+        Some(name) => format!("wash up --name {}", name),
+        None => "wash up".to_string(),
+    };
+    let status = match status {
+        Some(status) => status,
+        None => return format!("🤔 wasmCloud isn't running, start it with \"{}\"", up_cmd),
+    };
+    let mut out_text = String::new();
+    if let Some(nats) = &status.nats {
+        let version = nats.version.as_deref().unwrap_or("(unknown version)");
+        match (nats.listening, nats.pid) {
+            (true, Some(pid)) => {
+                let _ = writeln!(
+                    out_text,
+                    "🕸  NATS {} is running with pid {} at {}:{}{}, logs in {}",
+                    version,
+                    pid,
+                    nats.host,
+                    nats.port,
+                    uptime_text(nats.uptime_seconds),
+                    nats.log_path.display()
+                );
+            }
+            (true, None) => {
+                let _ = writeln!(
+                    out_text,
+                    "🕸  NATS {} is listening at {}:{}, logs in {}",
+                    version,
+                    nats.host,
+                    nats.port,
+                    nats.log_path.display()
+                );
+            }
+            (false, _) => {
+                let _ = writeln!(
+                    out_text,
+                    "❌ NATS {} isn't running at {}:{}, see {}",
+                    version,
+                    nats.host,
+                    nats.port,
+                    nats.log_path.display()
+                );
+            }
+        }
+    }
+    for host in &status.hosts {
+        let name = format!(
+            "Host {} (wasmCloud {})",
+            host.host_id.as_deref().unwrap_or("with unknown ID"),
+            host.version.as_deref().unwrap_or("of unknown version")
+        );
+        let details = format!(
+            "{}, dashboard at http://localhost:{}, logs in {}",
+            uptime_text(host.uptime_seconds),
+            host.dashboard_port,
+            host.log_path.display()
+        );
+        let _ = match (host.pid, host.responsive) {
+            (Some(pid), true) => writeln!(
+                out_text,
+                "✅ {} is running with pid {} and answers on the control interface{}",
+                name, pid, details
+            ),
+            (None, true) => writeln!(
+                out_text,
+                "✅ {} answers on the control interface{}",
+                name, details
+            ),
+            (Some(pid), false) => writeln!(
+                out_text,
+                "⚠️  {} is running with pid {} but doesn't answer on the control interface{}",
+                name, pid, details
+            ),
+            (None, false) => writeln!(
+                out_text,
+                "❌ {} isn't running, see {}",
+                name,
+                host.log_path.display()
+            ),
+        };
+    }
+    if !status.running {
+        let _ = writeln!(
+            out_text,
+            "🤔 wasmCloud isn't running, start it with \"{}\"",
+            up_cmd
+        );
+    }
+    out_text.trim_end().to_string()
+}
+
+fn uptime_text(uptime_seconds: Option<u64>) -> String {
+    uptime_seconds
+        .map(|seconds| format!(", up {}", format_uptime(seconds)))
+        .unwrap_or_default()
+}
+
+/// Formats an uptime by its two most significant units, e.g. `2h 5m`
+fn format_uptime(seconds: u64) -> String {
+    let units = [
+        (seconds / 86_400, "d"),
+        (seconds / 3_600 % 24, "h"),
+        (seconds / 60 % 60, "m"),
+        (seconds % 60, "s"),
+    ];
+    let first = units.iter().position(|(n, _)| *n > 0).unwrap_or(3);
+    units[first..]
+        .iter()
+        .take(2)
+        .map(|(n, unit)| format!("{}{}", n, unit))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod test {
+    use super::{format_uptime, StatusCommand};
+    use clap::Parser;
+
+    #[test]
+    fn test_format_uptime() {
+        assert_eq!(format_uptime(0), "0s");
+        assert_eq!(format_uptime(59), "59s");
+        assert_eq!(format_uptime(3_725), "1h 2m");
+        assert_eq!(format_uptime(90_061), "1d 1h");
+    }
+
+    #[test]
+    fn test_status_comprehensive() {
+        let status: StatusCommand =
+            Parser::try_parse_from(["status", "--name", "leaf", "--timeout-ms", "500"]).unwrap();
+        assert_eq!(status.name, Some("leaf".to_string()));
+        assert_eq!(status.timeout_ms, 500);
+
+        let both: Result<StatusCommand, _> =
+            Parser::try_parse_from(["status", "--name", "leaf", "--all"]);
+        assert!(both.is_err());
+    }
+}
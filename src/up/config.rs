@@ -1,12 +1,16 @@
 use std::collections::HashMap;
 
-use crate::up::{credsfile::parse_credsfile, NatsOpts, WasmcloudOpts};
+use anyhow::{bail, Context, Result};
+
+use crate::up::{credsfile::parse_credsfile, state::HOST_LABEL_PREFIX, NatsOpts, WasmcloudOpts};
 
 pub const DOWNLOADS_DIR: &str = "downloads";
 // NATS configuration values
 pub(crate) const NATS_SERVER_VERSION: &str = "v2.9.14";
 pub(crate) const DEFAULT_NATS_HOST: &str = "127.0.0.1";
 pub(crate) const DEFAULT_NATS_PORT: &str = "4222";
+/// Port given to `--nats-port` or `--dashboard-port` to listen on a free port picked by wash
+pub(crate) const AUTO_PORT: &str = "auto";
 // wasmCloud configuration values, https://wasmcloud.dev/reference/host-runtime/host_configure/
 pub(crate) const WASMCLOUD_HOST_VERSION: &str = "v0.60.0";
 // NATS isolation configuration variables
@@ -211,5 +215,44 @@ pub(crate) async fn configure_host_env(
     if wasmcloud_opts.enable_ipv6 {
         host_config.insert(WASMCLOUD_ENABLE_IPV6.to_string(), "1".to_string());
     }
+
+    // Labels and passthrough variables
+    for (key, value) in wasmcloud_opts.labels {
+        host_config.insert(format!("{}{}", HOST_LABEL_PREFIX, key), value);
+    }
+    host_config.extend(wasmcloud_opts.host_env);
     host_config
 }
+
+/// Parses a `key=value` pair given on the command line, such as a label or environment variable
+pub(crate) fn parse_key_value(pair: &str) -> Result<(String, String)> {
+    match pair.split_once('=') {
+        Some((key, value)) if !key.trim().is_empty() => {
+            Ok((key.trim().to_string(), value.to_string()))
+        }
+        _ => bail!("'{}' is not formatted as key=value", pair),
+    }
+}
+
+/// Parses a port to listen on, where `auto`, like 0, stands for a free port to be picked with
+/// [free_ports]
+pub(crate) fn parse_listen_port(port: &str) -> Result<u16> {
+    if port == AUTO_PORT {
+        return Ok(0);
+    }
+    port.parse()
+        .with_context(|| format!("'{}' is neither a port nor '{}'", port, AUTO_PORT))
+}
+
+/// Finds `count` distinct ports that are free on `host`, by having the OS assign them
+pub(crate) fn free_ports(host: &str, count: usize) -> Result<Vec<u16>> {
+    // Every listener stays open until all ports are known, so the OS hands out distinct ones
+    let listeners = (0..count)
+        .map(|_| std::net::TcpListener::bind((host, 0)))
+        .collect::<std::io::Result<Vec<_>>>()
+        .with_context(|| format!("Failed to find a free port on {}", host))?;
+    listeners
+        .iter()
+        .map(|listener| Ok(listener.local_addr()?.port()))
+        .collect()
+}
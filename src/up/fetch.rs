@@ -0,0 +1,165 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Result};
+use clap::Parser;
+use serde_json::json;
+use tokio::fs::create_dir_all;
+use wash_lib::cli::{CommandOutput, OutputKind};
+use wash_lib::start::{
+    ensure_nats_server_from_mirror, ensure_wasmcloud_from_mirror, find_nats_server_binary,
+    find_wasmcloud_binary, install_nats_server_from_tarball, install_wasmcloud_from_tarball,
+    pinned_wasmcloud_checksum, DownloadProgress, NATS_GITHUB_RELEASE_URL,
+    WASMCLOUD_GITHUB_RELEASE_URL,
+};
+
+use crate::{appearance::spinner::Spinner, cfg::cfg_dir};
+
+use super::config::{DOWNLOADS_DIR, NATS_SERVER_VERSION, WASMCLOUD_HOST_VERSION};
+
+#[derive(Parser, Debug, Clone)]
+pub(crate) struct FetchCommand {
+    /// NATS server version to install, e.g. `v2.7.2`
+    #[clap(long = "nats-version", default_value = NATS_SERVER_VERSION, env = "NATS_VERSION")]
+    pub(crate) nats_version: String,
+
+    /// A NATS server release tarball on disk to install as `--nats-version` instead of downloading it
+    #[clap(
+        long = "nats-tarball",
+        env = "NATS_TARBALL",
+        conflicts_with = "nats_mirror"
+    )]
+    pub(crate) nats_tarball: Option<PathBuf>,
+
+    /// Base URL of a mirror of https://github.com/nats-io/nats-server/releases/download to download NATS from
+    #[clap(long = "nats-mirror", env = "NATS_MIRROR")]
+    pub(crate) nats_mirror: Option<String>,
+
+    /// wasmCloud host version to install, e.g. `v0.55.0`
+    #[clap(long = "wasmcloud-version", default_value = WASMCLOUD_HOST_VERSION, env = "WASMCLOUD_VERSION")]
+    pub(crate) wasmcloud_version: String,
+
+    /// A wasmCloud host release tarball on disk to install as `--wasmcloud-version` instead of downloading it
+    #[clap(
+        long = "wasmcloud-tarball",
+        env = "WASMCLOUD_TARBALL",
+        conflicts_with = "wasmcloud_mirror"
+    )]
+    pub(crate) wasmcloud_tarball: Option<PathBuf>,
+
+    /// Base URL of a mirror of https://github.com/wasmCloud/wasmcloud-otp/releases/download to download wasmCloud from
+    #[clap(long = "wasmcloud-mirror", env = "WASMCLOUD_MIRROR")]
+    pub(crate) wasmcloud_mirror: Option<String>,
+
+    /// Download a wasmCloud version that has no checksum pinned in wash, without verifying it
+    #[clap(long = "allow-unverified")]
+    pub(crate) allow_unverified: bool,
+}
+
+/// Installs NATS and wasmCloud into the downloads directory without starting them, so that a
+/// later `wash up` doesn't need to download anything
+pub(crate) async fn handle_command(
+    command: FetchCommand,
+    output_kind: OutputKind,
+) -> Result<CommandOutput> {
+    let install_dir = cfg_dir()?.join(DOWNLOADS_DIR);
+    create_dir_all(&install_dir).await?;
+    let spinner = Spinner::new(&output_kind)?;
+
+    spinner.update_spinner_message(" Downloading NATS ...".to_string());
+    let nats_bin = ensure_nats(
+        &command.nats_version,
+        command.nats_tarball.as_deref(),
+        command.nats_mirror.as_deref(),
+        &install_dir,
+        |progress| spinner.update_download_progress(" Downloading NATS ...", progress),
+    )
+    .await?;
+    spinner.update_spinner_message(" Downloading wasmCloud ...".to_string());
+    let wasmcloud_bin = ensure_wasmcloud_host(
+        &command.wasmcloud_version,
+        command.wasmcloud_tarball.as_deref(),
+        command.wasmcloud_mirror.as_deref(),
+        command.allow_unverified,
+        &install_dir,
+        |progress| spinner.update_download_progress(" Downloading wasmCloud ...", progress),
+    )
+    .await?;
+    spinner.finish_and_clear();
+
+    let mut out_json = HashMap::new();
+    out_json.insert("nats_version".to_string(), json!(command.nats_version));
+    out_json.insert("nats_bin".to_string(), json!(nats_bin));
+    out_json.insert(
+        "wasmcloud_version".to_string(),
+        json!(command.wasmcloud_version),
+    );
+    out_json.insert("wasmcloud_bin".to_string(), json!(wasmcloud_bin));
+    Ok(CommandOutput::new(
+        format!(
+            "📦 NATS {} is installed at {}\n📦 wasmCloud {} is installed at {}",
+            command.nats_version,
+            nats_bin.display(),
+            command.wasmcloud_version,
+            wasmcloud_bin.display()
+        ),
+        out_json,
+    ))
+}
+
+/// Returns the NATS server binary of `version`, installing it from `tarball`, or downloading it
+/// from `mirror` or GitHub while reporting `progress`, if it isn't installed yet
+pub(crate) async fn ensure_nats(
+    version: &str,
+    tarball: Option<&Path>,
+    mirror: Option<&str>,
+    install_dir: &Path,
+    progress: impl Fn(DownloadProgress),
+) -> Result<PathBuf> {
+    if let Some(nats_bin) = find_nats_server_binary(install_dir, version).await {
+        return Ok(nats_bin);
+    }
+    match tarball {
+        Some(tarball) => install_nats_server_from_tarball(tarball, version, install_dir).await,
+        None => {
+            let mirror = mirror.unwrap_or(NATS_GITHUB_RELEASE_URL);
+            ensure_nats_server_from_mirror(mirror, version, install_dir, progress).await
+        }
+    }
+}
+
+/// Returns the wasmCloud host executable of `version`, installing it from `tarball`, or downloading
+/// it from `mirror` or GitHub while reporting `progress`, if it isn't installed yet. Downloading a
+/// version without a pinned checksum is refused unless `allow_unverified` is set
+pub(crate) async fn ensure_wasmcloud_host(
+    version: &str,
+    tarball: Option<&Path>,
+    mirror: Option<&str>,
+    allow_unverified: bool,
+    install_dir: &Path,
+    progress: impl Fn(DownloadProgress),
+) -> Result<PathBuf> {
+    if let Some(wasmcloud_bin) = find_wasmcloud_binary(install_dir, version).await {
+        return Ok(wasmcloud_bin);
+    }
+    match tarball {
+        Some(tarball) => install_wasmcloud_from_tarball(tarball, version, install_dir).await,
+        None => {
+            let (os, arch) = (std::env::consts::OS, std::env::consts::ARCH);
+            if pinned_wasmcloud_checksum(version, os, arch).is_none() {
+                if !allow_unverified {
+                    bail!(
+                        "No checksum is pinned for wasmCloud {} on {}/{}, pass --allow-unverified to install it without verification",
+                        version, os, arch
+                    );
+                }
+                eprintln!(
+                    "⚠️  No checksum is pinned for wasmCloud {} on {}/{}, it will be installed without verification",
+                    version, os, arch
+                );
+            }
+            let mirror = mirror.unwrap_or(WASMCLOUD_GITHUB_RELEASE_URL);
+            ensure_wasmcloud_from_mirror(mirror, version, install_dir, progress).await
+        }
+    }
+}
@@ -0,0 +1,173 @@
+use std::{path::Path, time::Duration};
+
+use anyhow::{bail, Context, Result};
+use serde_yaml::Value;
+use wash_lib::{
+    app::{AppError, WadmClient},
+    id::ServerId,
+};
+
+use crate::{
+    app::validate_model,
+    ctl::{
+        apply_manifest_actors, apply_manifest_linkdefs, apply_manifest_providers,
+        ctl_client_from_opts, manifest::HostManifest, nats_client_from_opts, ConnectionOpts,
+    },
+};
+
+/// A manifest to apply once `wash up` has started a host
+#[derive(Debug, Clone)]
+pub(crate) enum UpManifest {
+    /// A host manifest, as used by `wash ctl apply`
+    Host(HostManifest),
+    /// An OAM app specification, which is put into and deployed by wadm
+    App(String),
+}
+
+/// Loads a manifest, telling OAM app specifications apart from host manifests by their `kind`.
+/// When `expand_env` is set, environment variables are substituted into host manifests
+pub(crate) fn load_manifest(path: &Path, expand_env: bool) -> Result<UpManifest> {
+    let raw = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read manifest {}", path.display()))?;
+    let doc: Value = serde_yaml::from_str(&raw)
+        .with_context(|| format!("Failed to parse manifest {}", path.display()))?;
+    if doc.get("kind").and_then(Value::as_str) == Some("Application") {
+        validate_model(&raw)?;
+        return Ok(UpManifest::App(raw));
+    }
+    match HostManifest::from_path(path, expand_env) {
+        Ok(manifest) => Ok(UpManifest::Host(manifest)),
+        Err(e) => bail!("Failed to load manifest {}: {}", path.display(), e),
+    }
+}
+
+/// Applies the manifest to a host that has joined the lattice, reporting each step to
+/// `progress`. Returns the outcome of each instruction in the manifest
+pub(crate) async fn apply_up_manifest(
+    manifest: UpManifest,
+    opts: ConnectionOpts,
+    host_id: &str,
+    progress: impl Fn(String),
+) -> Result<Vec<String>> {
+    match manifest {
+        UpManifest::Host(manifest) => {
+            let client = ctl_client_from_opts(opts, None).await?;
+            let host_key: ServerId = host_id.parse()?;
+            let mut results = Vec::new();
+
+            progress("Starting actors".to_string());
+            let actors = apply_manifest_actors(&host_key, &client, &manifest).await?;
+            actors.iter().cloned().for_each(&progress);
+            results.extend(actors);
+
+            progress("Starting providers".to_string());
+            let providers = apply_manifest_providers(&host_key, &client, &manifest).await?;
+            providers.iter().cloned().for_each(&progress);
+            results.extend(providers);
+
+            progress("Advertising links".to_string());
+            let links = apply_manifest_linkdefs(&client, &manifest).await?;
+            links.iter().cloned().for_each(&progress);
+            results.extend(links);
+            Ok(results)
+        }
+        UpManifest::App(raw) => {
+            let timeout = Duration::from_millis(opts.timeout_ms);
+            let (nc, lattice_prefix) = nats_client_from_opts(opts).await?;
+            let wadm = WadmClient::new(nc, Some(lattice_prefix.clone()), timeout);
+
+            progress("Putting app specification into wadm".to_string());
+            let put = match wadm.put(raw.as_bytes()).await {
+                Err(AppError::NoResponders(_)) => bail!(
+                    "The manifest is an app specification, but wadm isn't running on lattice {}",
+                    lattice_prefix
+                ),
+                res => res.context("Failed to put app specification")?,
+            };
+            let stored = format!(
+                "App specification {} v{} stored",
+                put.name, put.current_version
+            );
+            progress(stored.clone());
+
+            progress(format!("Deploying {} v{}", put.name, put.current_version));
+            let reply = wadm
+                .deploy(&put.name, &put.current_version)
+                .await
+                .context("Failed to deploy application")?;
+            if !reply.acknowledged {
+                bail!(
+                    "wadm did not acknowledge the deployment of {} v{}: {}",
+                    put.name,
+                    put.current_version,
+                    reply
+                        .message
+                        .unwrap_or_else(|| "no reason given".to_string())
+                );
+            }
+            let deployed = "App deployment request acknowledged".to_string();
+            progress(deployed.clone());
+            Ok(vec![stored, deployed])
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{load_manifest, UpManifest};
+    use std::io::Write;
+
+    #[test]
+    fn test_load_manifest() {
+        let mut host_manifest = tempfile::Builder::new().suffix(".yaml").tempfile().unwrap();
+        write!(
+            host_manifest,
+            "actors:\n  - wasmcloud.azurecr.io/echo:0.3.7\n"
+        )
+        .unwrap();
+        match load_manifest(host_manifest.path(), false).unwrap() {
+            UpManifest::Host(manifest) => assert_eq!(manifest.actors.len(), 1),
+            other => panic!("expected a host manifest, got {:?}", other),
+        }
+
+        std::env::set_var("WASH_UP_TEST_ACTOR_TAG", "0.3.8");
+        let mut env_manifest = tempfile::Builder::new().suffix(".yaml").tempfile().unwrap();
+        write!(
+            env_manifest,
+            "actors:\n  - wasmcloud.azurecr.io/echo:${{WASH_UP_TEST_ACTOR_TAG}}\n"
+        )
+        .unwrap();
+        for (expand_env, actor) in [
+            (false, "wasmcloud.azurecr.io/echo:${WASH_UP_TEST_ACTOR_TAG}"),
+            (true, "wasmcloud.azurecr.io/echo:0.3.8"),
+        ] {
+            match load_manifest(env_manifest.path(), expand_env).unwrap() {
+                UpManifest::Host(manifest) => assert_eq!(manifest.actors, vec![actor.to_string()]),
+                other => panic!("expected a host manifest, got {:?}", other),
+            }
+        }
+
+        let mut app_manifest = tempfile::Builder::new().suffix(".yaml").tempfile().unwrap();
+        write!(
+            app_manifest,
+            r#"apiVersion: core.oam.dev/v1beta1
+kind: Application
+metadata:
+  name: echo
+  annotations:
+    version: v0.0.1
+spec:
+  components:
+    - name: echo
+      type: actor
+      properties:
+        image: wasmcloud.azurecr.io/echo:0.3.7
+"#
+        )
+        .unwrap();
+        assert!(matches!(
+            load_manifest(app_manifest.path(), false).unwrap(),
+            UpManifest::App(_)
+        ));
+    }
+}
@@ -7,28 +7,42 @@ use std::sync::{
     atomic::{AtomicBool, Ordering},
     Arc,
 };
+use std::time::Duration;
 
-use anyhow::{anyhow, Result};
-use clap::Parser;
+use anyhow::{anyhow, bail, Context, Result};
+use clap::{Parser, Subcommand};
 use serde_json::json;
 
 use tokio::fs::create_dir_all;
 use tokio::{
     io::{AsyncBufReadExt, BufReader},
     process::Child,
+    task::JoinHandle,
 };
 use wash_lib::cli::{CommandOutput, OutputKind};
 use wash_lib::start::*;
 
 use crate::appearance::spinner::Spinner;
 use crate::cfg::cfg_dir;
+use crate::ctl::{ctl_client_from_opts, ConnectionOpts};
 use crate::down::stop_nats;
 use crate::down::stop_wasmcloud;
 
-mod config;
+pub(crate) mod config;
 mod credsfile;
+mod fetch;
+mod manifest;
+mod profile;
+mod ready;
+pub(crate) mod state;
+mod versions;
 pub use config::DOWNLOADS_DIR;
 use config::*;
+use ready::{tail_log, wait_for_hosts, ReadyHost, DEFAULT_HOST_READY_TIMEOUT_MS, LOG_TAIL_LINES};
+use state::{
+    env_dir, parse_env_name, parse_host_labels, write_up_state, LocalHost, UpState,
+    DEFAULT_DASHBOARD_PORT,
+};
 
 #[derive(Parser, Debug, Clone)]
 pub(crate) struct UpCommand {
@@ -36,11 +50,60 @@ pub(crate) struct UpCommand {
     #[clap(short = 'd', long = "detached", alias = "detach")]
     pub(crate) detached: bool,
 
+    /// Name of a profile in `.wash/up.toml` in the current directory or `~/.wash/up.toml` to take settings from. Profiles set flags by their long name, e.g. `nats-port = 4223`, and flags or environment variables given to this command take precedence over them
+    #[clap(long = "profile")]
+    pub(crate) profile: Option<String>,
+
+    /// Print the effective settings, after applying the profile, flags and environment variables, instead of starting wasmCloud. Seeds and JWTs are redacted
+    #[clap(long = "print-config")]
+    pub(crate) print_config: bool,
+
+    /// Name of an environment to start, isolated from the default one and other named ones. Its state, logs, NATS pid file and JetStream storage are kept in `~/.wash/envs/<name>`. To run environments side by side, give each its own `--nats-port`, `--dashboard-port` and `--lattice-prefix`
+    #[clap(long = "name", value_parser = parse_env_name)]
+    pub(crate) name: Option<String>,
+
+    /// Number of wasmCloud hosts to start, all connected to the same NATS server. Each host gets its own dashboard port, starting at `--dashboard-port`, and log file
+    #[clap(long = "hosts", default_value_t = 1, value_parser = clap::value_parser!(u16).range(1..))]
+    pub(crate) hosts: u16,
+
+    /// Port of the wasmCloud dashboard of the first host, further hosts use the ports after it. With `auto`, every host gets a free port picked by wash
+    #[clap(long = "dashboard-port", default_value_t = DEFAULT_DASHBOARD_PORT, value_parser = parse_listen_port)]
+    pub(crate) dashboard_port: u16,
+
+    /// Labels for the hosts, as a comma-separated list with one entry per host. An entry holds `key=value` labels separated by `;`, e.g. `zone=a;tier=web,zone=b`
+    #[clap(long = "host-labels", value_delimiter = ',')]
+    pub(crate) host_labels: Vec<String>,
+
+    /// How long to wait, in milliseconds, for the hosts to join the lattice before stopping them and giving up
+    #[clap(long = "host-ready-timeout-ms", default_value_t = DEFAULT_HOST_READY_TIMEOUT_MS)]
+    pub(crate) host_ready_timeout_ms: u64,
+
+    /// A manifest to apply once the host has joined the lattice. Either a host manifest, as used by `wash ctl apply`, or an app specification to put into and deploy with wadm
+    #[clap(long = "manifest")]
+    pub(crate) manifest: Option<PathBuf>,
+
+    /// Expand environment variables using substitution syntax within a host manifest given with --manifest
+    #[clap(short = 'e', long = "expand-env", requires = "manifest")]
+    pub(crate) expand_env: bool,
+
     #[clap(flatten)]
     pub(crate) nats_opts: NatsOpts,
 
     #[clap(flatten)]
     pub(crate) wasmcloud_opts: WasmcloudOpts,
+
+    #[clap(subcommand)]
+    pub(crate) command: Option<UpSubcommand>,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub(crate) enum UpSubcommand {
+    /// List the installed versions of NATS and wasmCloud, along with the versions available for download
+    #[clap(name = "versions")]
+    Versions(versions::VersionsCommand),
+    /// Download NATS and wasmCloud into the downloads directory without starting them, e.g. to prepare a machine that will run `wash up` without internet access
+    #[clap(name = "fetch")]
+    Fetch(fetch::FetchCommand),
 }
 
 #[derive(Parser, Debug, Clone)]
@@ -73,12 +136,24 @@ pub(crate) struct NatsOpts {
     #[clap(long = "nats-version", default_value = NATS_SERVER_VERSION, env = "NATS_VERSION")]
     pub(crate) nats_version: String,
 
+    /// A NATS server release tarball on disk, e.g. `nats-server-v2.9.14-linux-amd64.tar.gz`, to install as `--nats-version` instead of downloading it, unless that version is already installed
+    #[clap(
+        long = "nats-tarball",
+        env = "NATS_TARBALL",
+        conflicts_with = "nats_mirror"
+    )]
+    pub(crate) nats_tarball: Option<PathBuf>,
+
+    /// Base URL of a mirror of https://github.com/nats-io/nats-server/releases/download to download NATS from, laid out as `<mirror>/<version>/<tarball>`
+    #[clap(long = "nats-mirror", env = "NATS_MIRROR")]
+    pub(crate) nats_mirror: Option<String>,
+
     /// NATS server host to connect to
     #[clap(long = "nats-host", default_value = DEFAULT_NATS_HOST, env = "NATS_HOST")]
     pub(crate) nats_host: String,
 
-    /// NATS server port to connect to. This will be used as the NATS listen port if `--nats-connect-only` isn't set
-    #[clap(long = "nats-port", default_value = DEFAULT_NATS_PORT, env = "NATS_PORT")]
+    /// NATS server port to connect to. This will be used as the NATS listen port if `--nats-connect-only` isn't set, in which case `auto` picks a free port
+    #[clap(long = "nats-port", default_value = DEFAULT_NATS_PORT, env = "NATS_PORT", value_parser = parse_listen_port)]
     pub(crate) nats_port: u16,
 
     /// NATS Server Jetstream domain, defaults to `core`
@@ -94,6 +169,7 @@ impl From<NatsOpts> for NatsConfig {
             js_domain: other.nats_js_domain,
             remote_url: other.nats_remote_url,
             credentials: other.nats_credsfile,
+            store_dir: None,
         }
     }
 }
@@ -104,6 +180,22 @@ pub(crate) struct WasmcloudOpts {
     #[clap(long = "wasmcloud-version", default_value = WASMCLOUD_HOST_VERSION, env = "WASMCLOUD_VERSION")]
     pub(crate) wasmcloud_version: String,
 
+    /// A wasmCloud host release tarball on disk, e.g. `x86_64-linux.tar.gz`, to install as `--wasmcloud-version` instead of downloading it, unless that version is already installed
+    #[clap(
+        long = "wasmcloud-tarball",
+        env = "WASMCLOUD_TARBALL",
+        conflicts_with = "wasmcloud_mirror"
+    )]
+    pub(crate) wasmcloud_tarball: Option<PathBuf>,
+
+    /// Base URL of a mirror of https://github.com/wasmCloud/wasmcloud-otp/releases/download to download wasmCloud from, laid out as `<mirror>/<version>/<tarball>`
+    #[clap(long = "wasmcloud-mirror", env = "WASMCLOUD_MIRROR")]
+    pub(crate) wasmcloud_mirror: Option<String>,
+
+    /// Download a wasmCloud version that has no checksum pinned in wash, without verifying it
+    #[clap(long = "allow-unverified")]
+    pub(crate) allow_unverified: bool,
+
     /// A lattice prefix is a unique identifier for a lattice, and is frequently used within NATS topics to isolate messages from different lattices
     #[clap(
         short = 'x',
@@ -236,6 +328,14 @@ pub(crate) struct WasmcloudOpts {
     #[clap(long = "enable-ipv6", env = WASMCLOUD_ENABLE_IPV6)]
     pub(crate) enable_ipv6: bool,
 
+    /// A label to apply to the hosts, in the form `key=value`. Can be repeated, and applies to every host started, while `--host-labels` sets labels for individual hosts
+    #[clap(short = 'l', long = "label", value_parser = parse_key_value)]
+    pub(crate) labels: Vec<(String, String)>,
+
+    /// An environment variable to pass to the hosts, in the form `KEY=VALUE`, for host settings that wash has no flag for. Can be repeated, and takes precedence over the variables wash sets
+    #[clap(long = "host-env", value_parser = parse_key_value)]
+    pub(crate) host_env: Vec<(String, String)>,
+
     /// If enabled, wasmCloud will not be downloaded if it's not installed
     #[clap(long = "wasmcloud-start-only")]
     pub(crate) start_only: bool,
@@ -245,88 +345,252 @@ pub(crate) async fn handle_command(
     command: UpCommand,
     output_kind: OutputKind,
 ) -> Result<CommandOutput> {
+    match command.command {
+        Some(UpSubcommand::Versions(cmd)) => {
+            return versions::handle_command(cmd, output_kind).await
+        }
+        Some(UpSubcommand::Fetch(cmd)) => return fetch::handle_command(cmd, output_kind).await,
+        None => {}
+    }
+    if command.profile.is_none() && !command.print_config {
+        return handle_up(command, output_kind).await;
+    }
+    let profile = command
+        .profile
+        .as_deref()
+        .map(profile::load_profile)
+        .transpose()?;
+    let (command, matches) = profile::resolve(std::env::args_os(), profile.as_ref())?;
+    if command.print_config {
+        let config = profile::effective_config(&matches)?;
+        let mut out_json = HashMap::new();
+        out_json.insert("profile".to_string(), json!(command.profile));
+        out_json.insert("config".to_string(), json!(config));
+        return Ok(CommandOutput::new(toml::to_string(&config)?, out_json));
+    }
     handle_up(command, output_kind).await
 }
 
-pub(crate) async fn handle_up(cmd: UpCommand, output_kind: OutputKind) -> Result<CommandOutput> {
+pub(crate) async fn handle_up(
+    mut cmd: UpCommand,
+    output_kind: OutputKind,
+) -> Result<CommandOutput> {
     let install_dir = cfg_dir()?.join(DOWNLOADS_DIR);
     create_dir_all(&install_dir).await?;
+    // Binaries are shared, while everything a running environment writes goes to its own directory
+    let state_dir = env_dir(cmd.name.as_deref())?;
+    create_dir_all(&state_dir).await?;
+    let down_cmd = match &cmd.name {
+        Some(name) => format!("wash down --name {}", name),
+        None => "wash down".to_string(),
+    };
+    // Load the manifest up front, so that a broken manifest doesn't leave a lattice behind
+    let manifest = cmd
+        .manifest
+        .as_deref()
+        .map(|path| manifest::load_manifest(path, cmd.expand_env))
+        .transpose()?;
+    // Avoid downloading + starting NATS if the user already runs their own server. Ignore connect_only
+    // if this server has a remote and credsfile as we have to start a leafnode in that scenario
+    let manage_nats = !cmd.nats_opts.connect_only
+        || cmd.nats_opts.nats_remote_url.is_some() && cmd.nats_opts.nats_credsfile.is_some();
+    // Pick a free port for `--nats-port auto` before any other settings are derived from it
+    if cmd.nats_opts.nats_port == 0 {
+        if !manage_nats {
+            bail!(
+                "--nats-port {} can only be used when wash starts NATS, not with --nats-connect-only",
+                AUTO_PORT
+            );
+        }
+        cmd.nats_opts.nats_port = free_ports(&cmd.nats_opts.nats_host, 1)?[0];
+    }
+    // Plan the hosts before starting anything, so that bad host settings don't leave NATS behind
+    let hosts = local_hosts(&cmd, &state_dir)?;
     let spinner = Spinner::new(&output_kind)?;
     // Capture listen address to keep the value after the nats_opts are moved
     let nats_listen_address = format!("{}:{}", cmd.nats_opts.nats_host, cmd.nats_opts.nats_port);
 
-    // Avoid downloading + starting NATS if the user already runs their own server. Ignore connect_only
-    // if this server has a remote and credsfile as we have to start a leafnode in that scenario
     let nats_opts = cmd.nats_opts.clone();
-    let nats_bin = if !cmd.nats_opts.connect_only
-        || cmd.nats_opts.nats_remote_url.is_some() && cmd.nats_opts.nats_credsfile.is_some()
-    {
-        // Download NATS if not already installed
+    // Download NATS and wasmCloud if not already installed, before starting anything, so that a
+    // failed download doesn't leave NATS running
+    let nats_bin = if manage_nats {
         spinner.update_spinner_message(" Downloading NATS ...".to_string());
-        let nats_binary = ensure_nats_server(&cmd.nats_opts.nats_version, &install_dir).await?;
+        Some(
+            fetch::ensure_nats(
+                &cmd.nats_opts.nats_version,
+                cmd.nats_opts.nats_tarball.as_deref(),
+                cmd.nats_opts.nats_mirror.as_deref(),
+                &install_dir,
+                |progress| spinner.update_download_progress(" Downloading NATS ...", progress),
+            )
+            .await?,
+        )
+    } else {
+        None
+    };
+
+    let wasmcloud_executable = if !cmd.wasmcloud_opts.start_only {
+        spinner.update_spinner_message(" Downloading wasmCloud ...".to_string());
+        fetch::ensure_wasmcloud_host(
+            &cmd.wasmcloud_opts.wasmcloud_version,
+            cmd.wasmcloud_opts.wasmcloud_tarball.as_deref(),
+            cmd.wasmcloud_opts.wasmcloud_mirror.as_deref(),
+            cmd.wasmcloud_opts.allow_unverified,
+            &install_dir,
+            |progress| spinner.update_download_progress(" Downloading wasmCloud ...", progress),
+        )
+        .await?
+    } else {
+        return Err(anyhow!("wasmCloud was not installed, exiting without downloading as --wasmcloud-start-only was set"));
+    };
 
+    if let Some(nats_binary) = &nats_bin {
         spinner.update_spinner_message(" Starting NATS ...".to_string());
-        start_nats(&install_dir, &nats_binary, cmd.nats_opts.clone()).await?;
-        Some(nats_binary)
+        // Named environments each get their own JetStream storage, rather than the one in the
+        // system's temporary directory that NATS uses by default
+        let store_dir = cmd.name.as_ref().map(|_| state_dir.join("jetstream"));
+        start_nats(&state_dir, nats_binary, cmd.nats_opts.clone(), store_dir).await?;
     } else {
-        // If we can connect to NATS, return None as we aren't managing the child process.
-        // Otherwise, exit with error since --nats-connect-only was specified
+        // If we can connect to NATS, carry on without managing the server. Otherwise, exit with
+        // error since --nats-connect-only was specified
         tokio::net::TcpStream::connect(&nats_listen_address)
             .await
-            .map(|_| None)
             .map_err(|_| {
                 anyhow!(
                     "Could not connect to NATS at {}, exiting since --nats-connect-only was set",
                     nats_listen_address
                 )
-            })?
-    };
-
-    // Download wasmCloud if not already installed
-    let wasmcloud_executable = if !cmd.wasmcloud_opts.start_only {
-        spinner.update_spinner_message(" Downloading wasmCloud ...".to_string());
-        ensure_wasmcloud(&cmd.wasmcloud_opts.wasmcloud_version, &install_dir).await?
-    } else {
-        // Ensure we clean up the NATS server if we can't start wasmCloud
-        if nats_bin.is_some() {
-            stop_nats(install_dir).await?;
-        }
-        return Err(anyhow!("wasmCloud was not installed, exiting without downloading as --wasmcloud-start-only was set"));
-    };
+            })?;
+    }
 
-    // Redirect output (which is on stderr) to a log file in detached mode, or use the terminal
+    // Redirect output (which is on stderr) to a log file in detached mode, or use the terminal.
+    // Only the first host logs to the terminal, any others always log to their own files
     spinner.update_spinner_message(" Starting wasmCloud ...".to_string());
-    let wasmcloud_log_path = install_dir.join("wasmcloud.log");
-    let stderr: Stdio = if cmd.detached {
-        tokio::fs::File::create(&wasmcloud_log_path)
-            .await?
-            .into_std()
-            .await
-            .into()
-    } else {
-        Stdio::piped()
-    };
-
+    let ctl_opts = ctl_connection_opts(&nats_opts, &cmd.wasmcloud_opts);
+    let host_env_overrides = cmd.wasmcloud_opts.host_env.clone();
     let host_env = configure_host_env(nats_opts, cmd.wasmcloud_opts).await;
-    let wasmcloud_child = match start_wasmcloud_host(
-        &wasmcloud_executable,
-        std::process::Stdio::null(),
-        stderr,
-        host_env,
-    )
-    .await
-    {
-        Ok(child) => child,
-        Err(e) => {
-            // Ensure we clean up the NATS server if we can't start wasmCloud
-            stop_nats(install_dir).await?;
-            return Err(e);
+    let mut wasmcloud_child = None;
+    let mut background_children = Vec::new();
+    for (idx, (host, seed)) in hosts.iter().enumerate() {
+        let stderr: Stdio = if cmd.detached || idx > 0 {
+            tokio::fs::File::create(&host.log_path)
+                .await?
+                .into_std()
+                .await
+                .into()
+        } else {
+            Stdio::piped()
+        };
+
+        let env = host_process_env(&host_env, host, seed, &host_env_overrides);
+        match start_wasmcloud_host(&wasmcloud_executable, Stdio::null(), stderr, env).await {
+            Ok(child) if idx == 0 && !cmd.detached => wasmcloud_child = Some(child),
+            Ok(child) => background_children.push(child),
+            Err(e) => {
+                // Ensure we clean up the hosts started so far and the NATS server
+                stop_hosts(
+                    &wasmcloud_executable,
+                    hosts[..idx].iter().map(|(host, _)| host),
+                )
+                .await;
+                if let Some(nats_bin) = &nats_bin {
+                    stop_nats(nats_bin, &state_dir).await?;
+                }
+                return Err(e);
+            }
         }
+    }
+    let state = UpState {
+        nats_bin: nats_bin.clone(),
+        wasmcloud_bin: wasmcloud_executable.clone(),
+        hosts: hosts.into_iter().map(|(host, _)| host).collect(),
+        lattice_prefix: ctl_opts.lattice_prefix.clone(),
+        nats_host: Some(cmd.nats_opts.nats_host.clone()),
+        nats_port: Some(cmd.nats_opts.nats_port),
     };
-
-    spinner.finish_and_clear();
+    write_up_state(&state_dir, &state).await?;
+    let wasmcloud_log_path = state.hosts[0].log_path.clone();
+    let host_id = state.hosts[0].host_id.clone().unwrap_or_default();
+    let host_ids = state
+        .hosts
+        .iter()
+        .filter_map(|host| host.host_id.clone())
+        .collect::<Vec<_>>();
+    let ready_timeout = Duration::from_millis(cmd.host_ready_timeout_ms);
+
+    let mut ready_hosts = Vec::new();
+    let mut manifest_results = None;
     if !cmd.detached {
-        run_wasmcloud_interactive(wasmcloud_child, output_kind).await?;
+        spinner.finish_and_clear();
+        // Wait for the hosts and apply the manifest alongside the host's output, as the host runs
+        // in the foreground. Only the other hosts' logs need to be shown if they fail to start
+        let background_logs = state
+            .hosts
+            .iter()
+            .skip(1)
+            .map(|host| host.log_path.clone())
+            .collect::<Vec<_>>();
+        let host_id = host_id.clone();
+        let mut ready_task = tokio::spawn(async move {
+            let print = |line: String| {
+                if output_kind == OutputKind::Text {
+                    println!("{}", line)
+                }
+            };
+            let ready = match ctl_client_from_opts(ctl_opts.clone(), None).await {
+                Ok(client) => {
+                    wait_for_hosts(
+                        &client,
+                        &host_ids,
+                        &mut background_children,
+                        ready_timeout,
+                        |_| {},
+                    )
+                    .await
+                }
+                Err(e) => Err(e),
+            };
+            ready?
+                .iter()
+                .for_each(|host| print(format!("✅ {}", ready_host_text(host))));
+            if let Some(manifest) = manifest {
+                if let Err(e) = manifest::apply_up_manifest(manifest, ctl_opts, &host_id, |line| {
+                    print(format!("📄 {}", line))
+                })
+                .await
+                {
+                    eprintln!("❌ Failed to apply manifest: {:#}", e);
+                }
+            }
+            Ok(())
+        });
+        let res = match wasmcloud_child {
+            Some(child) => {
+                run_wasmcloud_interactive(
+                    child,
+                    state.hosts[0].dashboard_port,
+                    output_kind,
+                    &mut ready_task,
+                )
+                .await
+            }
+            None => Ok(()),
+        };
+        ready_task.abort();
+        if let Err(e) = res {
+            // Stop everything as detached mode does. The first host's output was shown as it ran,
+            // so only the other hosts' logs are added
+            stop_hosts(&wasmcloud_executable, state.hosts.iter()).await;
+            if let Some(nats_bin) = &nats_bin {
+                stop_nats(nats_bin, &state_dir).await?;
+            }
+            state::remove_up_state(&state_dir).await?;
+            let logs = background_logs
+                .iter()
+                .map(|log| format!("\n\n{}", log_tail_text(log)))
+                .collect::<String>();
+            return Err(anyhow!("{}, wasmCloud and NATS were stopped{}", e, logs));
+        }
 
         let spinner = Spinner::new(&output_kind)?;
         spinner.update_spinner_message(
@@ -334,13 +598,70 @@ pub(crate) async fn handle_up(cmd: UpCommand, output_kind: OutputKind) -> Result
         );
 
         // Terminate wasmCloud and NATS processes
-        let output = stop_wasmcloud(wasmcloud_executable.clone()).await?;
-        if !output.status.success() {
-            log::warn!("wasmCloud exited with a non-zero exit status, processes may need to be cleaned up manually")
+        for host in &state.hosts {
+            let output = stop_wasmcloud(&wasmcloud_executable, host.node_name.as_deref()).await?;
+            if !output.status.success() {
+                log::warn!("wasmCloud exited with a non-zero exit status, processes may need to be cleaned up manually")
+            }
         }
 
-        stop_nats(install_dir).await?;
+        if let Some(nats_bin) = &nats_bin {
+            stop_nats(nats_bin, &state_dir).await?;
+        }
+        state::remove_up_state(&state_dir).await?;
 
+        spinner.finish_and_clear();
+    } else {
+        let ready = async {
+            let client = ctl_client_from_opts(ctl_opts.clone(), None).await?;
+            wait_for_hosts(
+                &client,
+                &host_ids,
+                &mut background_children,
+                ready_timeout,
+                |message| spinner.update_spinner_message(format!(" {} ...", message)),
+            )
+            .await
+        };
+        ready_hosts = match ready.await {
+            Ok(ready) => ready,
+            Err(e) => {
+                // A host that didn't come up is stopped along with the rest, so that the next
+                // `wash up` starts from a clean slate
+                spinner.finish_and_clear();
+                stop_hosts(&wasmcloud_executable, state.hosts.iter()).await;
+                if let Some(nats_bin) = &nats_bin {
+                    stop_nats(nats_bin, &state_dir).await?;
+                }
+                state::remove_up_state(&state_dir).await?;
+                let logs = state
+                    .hosts
+                    .iter()
+                    .map(|host| log_tail_text(&host.log_path))
+                    .collect::<Vec<_>>();
+                return Err(anyhow!(
+                    "{}, wasmCloud and NATS were stopped\n\n{}",
+                    e,
+                    logs.join("\n\n")
+                ));
+            }
+        };
+        if let Some(manifest) = manifest {
+            let results = manifest::apply_up_manifest(
+                manifest,
+                ctl_opts,
+                &host_id,
+                |message| spinner.update_spinner_message(format!(" {} ...", message)),
+            )
+            .await
+            .with_context(|| {
+                format!(
+                    "Failed to apply manifest, wasmCloud is still running and can be stopped with \"{}\"",
+                    down_cmd
+                )
+            })?;
+            manifest_results = Some(results);
+        }
         spinner.finish_and_clear();
     }
 
@@ -351,11 +672,19 @@ pub(crate) async fn handle_up(cmd: UpCommand, output_kind: OutputKind) -> Result
     out_text.push_str("🛁 wash up completed successfully");
 
     if cmd.detached {
-        let url = "http://localhost:4000";
+        let url = format!("http://localhost:{}", state.hosts[0].dashboard_port);
         out_json.insert("wasmcloud_url".to_string(), json!(url));
         out_json.insert("wasmcloud_log".to_string(), json!(wasmcloud_log_path));
-        out_json.insert("kill_cmd".to_string(), json!("wash down"));
+        out_json.insert("hosts".to_string(), json!(state.hosts));
+        out_json.insert("ready_hosts".to_string(), json!(ready_hosts));
+        out_json.insert("kill_cmd".to_string(), json!(down_cmd));
+        out_json.insert("name".to_string(), json!(cmd.name));
         out_json.insert("nats_url".to_string(), json!(nats_listen_address));
+        out_json.insert("nats_port".to_string(), json!(cmd.nats_opts.nats_port));
+        out_json.insert(
+            "dashboard_port".to_string(),
+            json!(state.hosts[0].dashboard_port),
+        );
 
         let _ = write!(
             out_text,
@@ -367,14 +696,169 @@ pub(crate) async fn handle_up(cmd: UpCommand, output_kind: OutputKind) -> Result
             "\n🌐 The wasmCloud dashboard is running at {}\n📜 Logs for the host are being written to {}",
             url, wasmcloud_log_path.to_string_lossy()
         );
-        let _ = write!(out_text, "\n\n🛑 To stop wasmCloud, run \"wash down\"");
+        for host in &ready_hosts {
+            let _ = write!(out_text, "\n✅ {}", ready_host_text(host));
+        }
+        for host in state.hosts.iter().skip(1) {
+            let _ = write!(
+                out_text,
+                "\n🌐 Another host is running with its dashboard at http://localhost:{} and logs in {}",
+                host.dashboard_port,
+                host.log_path.to_string_lossy()
+            );
+        }
+        if let Some(results) = manifest_results {
+            let _ = write!(
+                out_text,
+                "\n📄 Applied manifest to host {}:\n{}",
+                host_id,
+                results.join("\n")
+            );
+            out_json.insert("manifest_results".to_string(), json!(results));
+        }
+        let _ = write!(out_text, "\n\n🛑 To stop wasmCloud, run \"{}\"", down_cmd);
     }
 
     Ok(CommandOutput::new(out_text, out_json))
 }
 
-/// Helper function to start the NATS binary, redirecting output to nats.log
-async fn start_nats(install_dir: &Path, nats_binary: &Path, nats_opts: NatsOpts) -> Result<Child> {
+/// Stops the given hosts, ignoring failures as they may not have started
+async fn stop_hosts<'a>(wasmcloud_bin: &Path, hosts: impl Iterator<Item = &'a LocalHost>) {
+    for host in hosts {
+        let _ = stop_wasmcloud(wasmcloud_bin, host.node_name.as_deref()).await;
+    }
+}
+
+/// The environment of a host's process: the settings shared by every host, then the host's own
+/// seed, node name, dashboard port and labels, and lastly the `--host-env` variables, which take
+/// precedence over everything wash sets
+fn host_process_env(
+    shared: &HashMap<String, String>,
+    host: &LocalHost,
+    seed: &str,
+    host_env: &[(String, String)],
+) -> HashMap<String, String> {
+    let mut env = shared.clone();
+    env.insert(WASMCLOUD_HOST_SEED.to_string(), seed.to_string());
+    env.extend(host.env());
+    env.extend(host_env.iter().cloned());
+    env
+}
+
+fn ready_host_text(host: &ReadyHost) -> String {
+    let labels = host
+        .labels
+        .iter()
+        .map(|(key, value)| format!("{}={}", key, value))
+        .collect::<Vec<_>>();
+    format!(
+        "Host {} joined the lattice with labels: {}",
+        host.id,
+        labels.join(", ")
+    )
+}
+
+fn log_tail_text(log_path: &Path) -> String {
+    format!(
+        "📜 Last lines of {}:\n{}",
+        log_path.display(),
+        tail_log(log_path, LOG_TAIL_LINES)
+    )
+}
+
+/// Options for connecting to the control interface of the hosts being started
+fn ctl_connection_opts(nats_opts: &NatsOpts, wasmcloud_opts: &WasmcloudOpts) -> ConnectionOpts {
+    ConnectionOpts {
+        ctl_host: Some(
+            wasmcloud_opts
+                .ctl_host
+                .clone()
+                .unwrap_or_else(|| nats_opts.nats_host.clone()),
+        ),
+        ctl_port: Some(
+            wasmcloud_opts
+                .ctl_port
+                .unwrap_or(nats_opts.nats_port)
+                .to_string(),
+        ),
+        ctl_jwt: wasmcloud_opts.ctl_jwt.clone(),
+        ctl_seed: wasmcloud_opts.ctl_seed.clone(),
+        ctl_credsfile: wasmcloud_opts.ctl_credsfile.clone(),
+        lattice_prefix: Some(wasmcloud_opts.lattice_prefix.clone()),
+        ..Default::default()
+    }
+}
+
+/// Determines the node name, dashboard port, labels, log file and seed of each host to start.
+/// The first host keeps the `--host-seed`, if one was given, and the release's default node name,
+/// unless it's part of a named environment
+fn local_hosts(cmd: &UpCommand, state_dir: &Path) -> Result<Vec<(LocalHost, String)>> {
+    let hosts = cmd.hosts as usize;
+    let labels = parse_host_labels(&cmd.host_labels, hosts)?;
+    let dashboard_ports = if cmd.dashboard_port == 0 {
+        free_ports("127.0.0.1", hosts)?
+    } else {
+        (0..cmd.hosts)
+            .map(|idx| {
+                cmd.dashboard_port.checked_add(idx).ok_or_else(|| {
+                    anyhow!(
+                        "--dashboard-port {} leaves no room for {} hosts, each host needs its own port up to 65535",
+                        cmd.dashboard_port,
+                        cmd.hosts
+                    )
+                })
+            })
+            .collect::<Result<_>>()?
+    };
+    labels
+        .into_iter()
+        .zip(dashboard_ports)
+        .enumerate()
+        .map(|(idx, (labels, dashboard_port))| {
+            let seed = match (idx, &cmd.wasmcloud_opts.host_seed) {
+                (0, Some(seed)) => seed.clone(),
+                _ => nkeys::KeyPair::new_server().seed()?,
+            };
+            let host_id = nkeys::KeyPair::from_seed(&seed)
+                .map_err(|e| anyhow!("Invalid host seed: {}", e))?
+                .public_key();
+            let (node_name, log_file) = match (idx, &cmd.name) {
+                (0, None) => (None, "wasmcloud.log".to_string()),
+                (0, Some(name)) => (
+                    Some(format!("wasmcloud_host_{}", name)),
+                    "wasmcloud.log".to_string(),
+                ),
+                (_, None) => (
+                    Some(format!("wasmcloud_host_{}", idx + 1)),
+                    format!("wasmcloud_{}.log", idx + 1),
+                ),
+                (_, Some(name)) => (
+                    Some(format!("wasmcloud_host_{}_{}", name, idx + 1)),
+                    format!("wasmcloud_{}.log", idx + 1),
+                ),
+            };
+            Ok((
+                LocalHost {
+                    node_name,
+                    dashboard_port,
+                    host_id: Some(host_id),
+                    labels,
+                    log_path: state_dir.join(log_file),
+                },
+                seed,
+            ))
+        })
+        .collect()
+}
+
+/// Helper function to start the NATS binary, redirecting output to nats.log and writing its config
+/// and pid file in the environment's directory
+async fn start_nats(
+    state_dir: &Path,
+    nats_binary: &Path,
+    nats_opts: NatsOpts,
+    store_dir: Option<PathBuf>,
+) -> Result<Child> {
     // Ensure that leaf node remote connection can be established before launching NATS
     let nats_opts = match (
         nats_opts.nats_remote_url.as_ref(),
@@ -398,27 +882,33 @@ async fn start_nats(install_dir: &Path, nats_binary: &Path, nats_opts: NatsOpts)
         (_, _) => nats_opts,
     };
     // Start NATS server, redirecting output to a log file
-    let nats_log_path = install_dir.join("nats.log");
+    let nats_log_path = state_dir.join("nats.log");
     let nats_log_file = tokio::fs::File::create(&nats_log_path)
         .await?
         .into_std()
         .await;
-    start_nats_server(nats_binary, nats_log_file, nats_opts.into()).await
+    let mut config: NatsConfig = nats_opts.into();
+    if let Some(store_dir) = store_dir {
+        config = config.with_store_dir(store_dir);
+    }
+    start_nats_server_in_dir(nats_binary, state_dir, nats_log_file, config).await
 }
 
-/// Helper function to run wasmCloud in interactive mode
+/// Helper function to run wasmCloud in interactive mode. Shows the host's output until CTRL+C is
+/// pressed, or until `ready_task` fails because the hosts didn't come up, returning its error
 async fn run_wasmcloud_interactive(
     mut wasmcloud_child: Child,
+    dashboard_port: u16,
     output_kind: OutputKind,
+    ready_task: &mut JoinHandle<Result<()>>,
 ) -> Result<()> {
-    use std::sync::mpsc::channel;
-    let (running_sender, running_receiver) = channel();
+    let (running_sender, mut running_receiver) = tokio::sync::mpsc::unbounded_channel();
     let running = Arc::new(AtomicBool::new(true));
 
     ctrlc::set_handler(move || {
         if running.load(Ordering::SeqCst) {
             running.store(false, Ordering::SeqCst);
-            let _ = running_sender.send(true);
+            let _ = running_sender.send(());
         } else {
             log::warn!("\nRepeated CTRL+C received, killing wasmCloud and NATS. This may result in zombie processes")
         }
@@ -426,7 +916,10 @@ async fn run_wasmcloud_interactive(
     .expect("Error setting Ctrl-C handler, please file a bug issue https://github.com/wasmCloud/wash/issues/new/choose");
 
     if output_kind != OutputKind::Json {
-        println!("🏃 Running in interactive mode, your host is running at http://localhost:4000",);
+        println!(
+            "🏃 Running in interactive mode, your host is running at http://localhost:{}",
+            dashboard_port
+        );
         println!("🚪 Press `CTRL+c` at any time to exit");
     }
 
@@ -441,14 +934,24 @@ async fn run_wasmcloud_interactive(
         })
     });
 
-    // Wait for the user to send Ctrl+C in a thread where blocking is acceptable
-    let _ = running_receiver.recv();
+    // Wait for the user to send Ctrl+C, or for the hosts to fail to come up
+    let ready = async {
+        match ready_task.await {
+            Ok(Err(e)) => Err(e),
+            // Once the hosts are ready, only CTRL+C stops them
+            _ => std::future::pending().await,
+        }
+    };
+    let res = tokio::select! {
+        _ = running_receiver.recv() => Ok(()),
+        res = ready => res,
+    };
 
     // Prevent extraneous messages from the host getting printed as the host shuts down
     if let Some(handle) = handle {
         handle.abort()
     };
-    Ok(())
+    res
 }
 
 #[cfg(test)]
@@ -456,6 +959,7 @@ mod tests {
     use super::UpCommand;
     use anyhow::Result;
     use clap::Parser;
+    use std::path::PathBuf;
 
     const LOCAL_REGISTRY: &str = "localhost:5001";
 
@@ -632,4 +1136,264 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_up_multiple_hosts() -> Result<()> {
+        let up: UpCommand = Parser::try_parse_from([
+            "up",
+            "--hosts",
+            "3",
+            "--host-labels",
+            "zone=a;tier=web,zone=b",
+        ])?;
+        assert_eq!(up.hosts, 3);
+        assert_eq!(
+            up.host_labels,
+            vec!["zone=a;tier=web".to_string(), "zone=b".to_string()]
+        );
+
+        let hosts = super::local_hosts(&up, std::path::Path::new("/tmp/downloads"))?;
+        assert_eq!(hosts.len(), 3);
+        assert_eq!(hosts[0].0.node_name, None);
+        assert_eq!(hosts[2].0.node_name, Some("wasmcloud_host_3".to_string()));
+        assert_eq!(hosts[2].0.dashboard_port, 4002);
+        assert_eq!(
+            hosts[2].0.log_path,
+            std::path::PathBuf::from("/tmp/downloads/wasmcloud_3.log")
+        );
+        assert_ne!(hosts[0].1, hosts[1].1);
+
+        let overflowing: UpCommand =
+            Parser::try_parse_from(["up", "--hosts", "2", "--dashboard-port", "65535"])?;
+        assert!(super::local_hosts(&overflowing, std::path::Path::new("/tmp/downloads")).is_err());
+
+        let no_hosts: Result<UpCommand, _> = Parser::try_parse_from(["up", "--hosts", "0"]);
+        assert!(no_hosts.is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_up_named_environment() -> Result<()> {
+        let up: UpCommand = Parser::try_parse_from([
+            "up",
+            "--name",
+            "leaf",
+            "--hosts",
+            "2",
+            "--dashboard-port",
+            "4100",
+        ])?;
+        assert_eq!(up.name, Some("leaf".to_string()));
+
+        let hosts = super::local_hosts(&up, std::path::Path::new("/tmp/envs/leaf"))?;
+        assert_eq!(
+            hosts[0].0.node_name,
+            Some("wasmcloud_host_leaf".to_string())
+        );
+        assert_eq!(
+            hosts[1].0.node_name,
+            Some("wasmcloud_host_leaf_2".to_string())
+        );
+        assert_eq!(hosts[0].0.dashboard_port, 4100);
+        assert_eq!(hosts[1].0.dashboard_port, 4101);
+        assert_eq!(
+            hosts[0].0.log_path,
+            PathBuf::from("/tmp/envs/leaf/wasmcloud.log")
+        );
+
+        let bad_name: Result<UpCommand, _> = Parser::try_parse_from(["up", "--name", "a/b"]);
+        assert!(bad_name.is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_up_auto_ports() -> Result<()> {
+        let up: UpCommand = Parser::try_parse_from([
+            "up",
+            "--nats-port",
+            "auto",
+            "--dashboard-port",
+            "auto",
+            "--hosts",
+            "3",
+        ])?;
+        assert_eq!(up.nats_opts.nats_port, 0);
+        assert_eq!(up.dashboard_port, 0);
+
+        let hosts = super::local_hosts(&up, std::path::Path::new("/tmp/downloads"))?;
+        let mut ports = hosts
+            .iter()
+            .map(|(host, _)| host.dashboard_port)
+            .collect::<Vec<_>>();
+        assert!(ports.iter().all(|port| *port != 0));
+        ports.sort_unstable();
+        ports.dedup();
+        assert_eq!(ports.len(), 3);
+
+        let bad_port: Result<UpCommand, _> = Parser::try_parse_from(["up", "--nats-port", "any"]);
+        assert!(bad_port.is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_up_manifest() -> Result<()> {
+        let up: UpCommand = Parser::try_parse_from([
+            "up",
+            "--manifest",
+            "./app.yaml",
+            "--expand-env",
+            "--lattice-prefix",
+            "dev",
+            "--ctl-port",
+            "4223",
+            "--host-ready-timeout-ms",
+            "120000",
+        ])?;
+        assert_eq!(up.manifest, Some(std::path::PathBuf::from("./app.yaml")));
+        assert!(up.expand_env);
+        assert_eq!(up.host_ready_timeout_ms, 120_000);
+
+        let opts = super::ctl_connection_opts(&up.nats_opts, &up.wasmcloud_opts);
+        assert_eq!(opts.ctl_host, Some(up.nats_opts.nats_host.clone()));
+        assert_eq!(opts.ctl_port, Some("4223".to_string()));
+        assert_eq!(opts.lattice_prefix, Some("dev".to_string()));
+        Ok(())
+    }
+
+    #[test]
+    fn test_up_versions() -> Result<()> {
+        let up: UpCommand = Parser::try_parse_from(["up", "versions", "--installed"])?;
+        match up.command {
+            Some(super::UpSubcommand::Versions(versions)) => {
+                assert!(versions.installed);
+                assert!(versions.command.is_none());
+            }
+            other => panic!("expected the versions subcommand, got {:?}", other),
+        }
+
+        let up: UpCommand =
+            Parser::try_parse_from(["up", "versions", "prune", "--keep", "v2.8.4"])?;
+        match up.command {
+            Some(super::UpSubcommand::Versions(super::versions::VersionsCommand {
+                command: Some(super::versions::VersionsSubcommand::Prune(prune)),
+                ..
+            })) => assert_eq!(prune.keep, vec!["v2.8.4".to_string()]),
+            other => panic!("expected the prune subcommand, got {:?}", other),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_up_fetch() -> Result<()> {
+        let up: UpCommand = Parser::try_parse_from([
+            "up",
+            "fetch",
+            "--nats-tarball",
+            "/tmp/nats-server-v2.9.14-linux-amd64.tar.gz",
+            "--wasmcloud-mirror",
+            "https://mirror.example.com/wasmcloud",
+            "--allow-unverified",
+        ])?;
+        match up.command {
+            Some(super::UpSubcommand::Fetch(fetch)) => {
+                assert_eq!(
+                    fetch.nats_tarball,
+                    Some(PathBuf::from("/tmp/nats-server-v2.9.14-linux-amd64.tar.gz"))
+                );
+                assert_eq!(
+                    fetch.wasmcloud_mirror.as_deref(),
+                    Some("https://mirror.example.com/wasmcloud")
+                );
+                assert_eq!(fetch.nats_version, super::NATS_SERVER_VERSION);
+                assert!(fetch.allow_unverified);
+            }
+            other => panic!("expected the fetch subcommand, got {:?}", other),
+        }
+
+        let up: UpCommand = Parser::try_parse_from([
+            "up",
+            "--nats-mirror",
+            "https://mirror.example.com/nats",
+            "--wasmcloud-tarball",
+            "/tmp/x86_64-linux.tar.gz",
+        ])?;
+        assert_eq!(
+            up.nats_opts.nats_mirror.as_deref(),
+            Some("https://mirror.example.com/nats")
+        );
+        assert_eq!(
+            up.wasmcloud_opts.wasmcloud_tarball,
+            Some(PathBuf::from("/tmp/x86_64-linux.tar.gz"))
+        );
+
+        // A tarball and a mirror for the same download don't go together
+        assert!(UpCommand::try_parse_from([
+            "up",
+            "--nats-tarball",
+            "/tmp/nats.tar.gz",
+            "--nats-mirror",
+            "https://mirror.example.com/nats",
+        ])
+        .is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_unpinned_wasmcloud_requires_allow_unverified() {
+        let install_dir = tempfile::tempdir().unwrap();
+        let err = super::fetch::ensure_wasmcloud_host(
+            "v0.0.1",
+            None,
+            Some("http://127.0.0.1:9"),
+            false,
+            install_dir.path(),
+            |_| {},
+        )
+        .await
+        .unwrap_err();
+        assert!(err.to_string().contains("--allow-unverified"));
+    }
+
+    #[tokio::test]
+    async fn test_up_labels_and_host_env() -> Result<()> {
+        let up: UpCommand = Parser::try_parse_from([
+            "up",
+            "--label",
+            "zone=us-east-1",
+            "-l",
+            "arch=aarch64",
+            "--host-env",
+            "WASMCLOUD_RPC_TIMEOUT_MS=5000",
+            "--host-env",
+            "ERL_FLAGS=+S 2",
+            "--host-env",
+            "PORT=4040",
+        ])?;
+        // Per host variables are set after the shared ones, and `--host-env` still wins over them
+        let hosts = super::local_hosts(&up, std::path::Path::new("/tmp/downloads"))?;
+        let (host, seed) = &hosts[0];
+        assert_eq!(host.dashboard_port, 4000);
+        let host_env_overrides = up.wasmcloud_opts.host_env.clone();
+        let env = super::configure_host_env(up.nats_opts, up.wasmcloud_opts).await;
+        let process_env = super::host_process_env(&env, host, seed, &host_env_overrides);
+        assert_eq!(process_env.get("PORT"), Some(&"4040".to_string()));
+        assert_eq!(process_env.get("WASMCLOUD_HOST_SEED"), Some(seed));
+        assert_eq!(process_env.get("ERL_FLAGS"), Some(&"+S 2".to_string()));
+
+        assert_eq!(env.get("HOST_zone"), Some(&"us-east-1".to_string()));
+        assert_eq!(env.get("HOST_arch"), Some(&"aarch64".to_string()));
+        assert_eq!(env.get("ERL_FLAGS"), Some(&"+S 2".to_string()));
+        // Passthrough variables win over the ones wash sets itself
+        assert_eq!(
+            env.get("WASMCLOUD_RPC_TIMEOUT_MS"),
+            Some(&"5000".to_string())
+        );
+
+        let missing_value: Result<UpCommand, _> = Parser::try_parse_from(["up", "--label", "zone"]);
+        assert!(missing_value.is_err());
+        let missing_key: Result<UpCommand, _> =
+            Parser::try_parse_from(["up", "--host-env", "=value"]);
+        assert!(missing_key.is_err());
+        Ok(())
+    }
 }
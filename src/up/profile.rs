@@ -0,0 +1,224 @@
+use std::{ffi::OsString, path::PathBuf};
+
+use anyhow::{bail, Context, Result};
+use clap::{ArgAction, ArgMatches, Command, CommandFactory, FromArgMatches};
+use toml::{value::Table, Value};
+
+use crate::{cfg::cfg_dir, Cli, CliCommand};
+
+use super::UpCommand;
+
+/// File holding `wash up` profiles, either in `~/.wash` or in a `.wash` directory in the
+/// current directory
+pub(crate) const UP_CONFIG_FILE: &str = "up.toml";
+/// Flags that select or inspect a profile, so they can't be set by one
+const NON_PROFILE_FLAGS: &[&str] = &["profile", "print-config", "help"];
+/// Value shown by `--print-config` in place of seeds and JWTs
+const REDACTED: &str = "<redacted>";
+
+/// Where profiles are looked up, in the order they're searched
+pub(crate) fn profile_paths() -> Result<Vec<PathBuf>> {
+    Ok(vec![
+        std::env::current_dir()?.join(".wash").join(UP_CONFIG_FILE),
+        cfg_dir()?.join(UP_CONFIG_FILE),
+    ])
+}
+
+/// Loads a named profile, looking in the project-local `.wash/up.toml` before `~/.wash/up.toml`
+pub(crate) fn load_profile(name: &str) -> Result<Table> {
+    let candidates = profile_paths()?;
+    for path in &candidates {
+        if !path.is_file() {
+            continue;
+        }
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let mut profiles: Table = toml::from_str(&contents)
+            .with_context(|| format!("Failed to parse {}", path.display()))?;
+        match profiles.remove(name) {
+            Some(Value::Table(profile)) => return Ok(profile),
+            Some(_) => bail!("Profile '{}' in {} is not a table", name, path.display()),
+            None => {}
+        }
+    }
+    bail!(
+        "Profile '{}' was not found in {}",
+        name,
+        candidates
+            .iter()
+            .map(|p| p.display().to_string())
+            .collect::<Vec<_>>()
+            .join(" or ")
+    )
+}
+
+/// Parses the command line again with the profile's settings as the defaults of the `wash up`
+/// flags, so that flags and environment variables still take precedence over the profile.
+/// Returns the resulting command along with its matches
+pub(crate) fn resolve<I, T>(args: I, profile: Option<&Table>) -> Result<(UpCommand, ArgMatches)>
+where
+    I: IntoIterator<Item = T>,
+    T: Into<OsString> + Clone,
+{
+    let mut command = Cli::command();
+    if let Some(profile) = profile {
+        let defaults = profile_defaults(up_subcommand(&command)?, profile)?;
+        command = command.mut_subcommand("up", |mut up| {
+            for (id, values) in defaults {
+                up = up.mut_arg(id, |arg| arg.default_values(values));
+            }
+            up
+        });
+    }
+    let matches = command.try_get_matches_from(args)?;
+    let up_matches = matches
+        .subcommand_matches("up")
+        .cloned()
+        .context("Profiles can only be used with wash up")?;
+    match Cli::from_arg_matches(&matches)?.command {
+        CliCommand::Up(up) => Ok((up, up_matches)),
+        _ => bail!("Profiles can only be used with wash up"),
+    }
+}
+
+/// Returns the effective `wash up` settings in the format of a profile, with seeds and JWTs
+/// redacted
+pub(crate) fn effective_config(matches: &ArgMatches) -> Result<Table> {
+    let cli = Cli::command();
+    let mut config = Table::new();
+    for arg in up_subcommand(&cli)?.get_arguments() {
+        let flag = match arg.get_long() {
+            Some(flag) if !NON_PROFILE_FLAGS.contains(&flag) => flag,
+            _ => continue,
+        };
+        let values = match matches.get_raw(arg.get_id().as_str()) {
+            Some(values) => values
+                .map(|v| v.to_string_lossy().to_string())
+                .collect::<Vec<_>>(),
+            None => continue,
+        };
+        let value = match (arg.get_action(), values.as_slice()) {
+            (_, []) => continue,
+            _ if flag.ends_with("seed") || flag.ends_with("jwt") => {
+                Value::String(REDACTED.to_string())
+            }
+            (ArgAction::SetTrue, [value]) => Value::Boolean(value == "true"),
+            (ArgAction::Append, values) => {
+                Value::Array(values.iter().cloned().map(Value::String).collect())
+            }
+            (_, [value]) => match value.parse::<i64>() {
+                Ok(number) => Value::Integer(number),
+                Err(_) => Value::String(value.clone()),
+            },
+            (_, values) => Value::Array(values.iter().cloned().map(Value::String).collect()),
+        };
+        config.insert(flag.to_string(), value);
+    }
+    Ok(config)
+}
+
+fn up_subcommand(cli: &Command) -> Result<&Command> {
+    cli.find_subcommand("up")
+        .context("wash up is missing from the CLI definition")
+}
+
+/// Maps the settings of a profile to the IDs of the flags they set and their values. Settings are
+/// named after the long flags of `wash up`, e.g. `nats-port = 4223`
+fn profile_defaults(up: &Command, profile: &Table) -> Result<Vec<(String, Vec<String>)>> {
+    let mut defaults = Vec::new();
+    for (key, value) in profile {
+        let arg = match up
+            .get_arguments()
+            .find(|arg| arg.get_long() == Some(key.as_str()))
+        {
+            Some(arg) if !NON_PROFILE_FLAGS.contains(&key.as_str()) => arg,
+            _ => bail!(
+                "Unknown setting '{}' in profile, settings are named after the flags of wash up, e.g. nats-port",
+                key
+            ),
+        };
+        let values = match (arg.get_action(), value) {
+            (ArgAction::SetTrue, Value::Boolean(flag)) => vec![flag.to_string()],
+            (ArgAction::SetTrue, _) => bail!("Setting '{}' in profile must be true or false", key),
+            (_, Value::Array(values)) => values
+                .iter()
+                .map(|v| profile_value(key, v))
+                .collect::<Result<_>>()?,
+            (_, value) => vec![profile_value(key, value)?],
+        };
+        defaults.push((arg.get_id().as_str().to_string(), values));
+    }
+    Ok(defaults)
+}
+
+fn profile_value(key: &str, value: &Value) -> Result<String> {
+    match value {
+        Value::String(s) => Ok(s.clone()),
+        Value::Integer(i) => Ok(i.to_string()),
+        Value::Float(f) => Ok(f.to_string()),
+        Value::Boolean(b) => Ok(b.to_string()),
+        _ => bail!(
+            "Setting '{}' in profile must be a string, number or boolean",
+            key
+        ),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{effective_config, resolve};
+    use toml::{value::Table, Value};
+
+    fn profile() -> Table {
+        toml::from_str(
+            r#"
+lattice-prefix = "dev"
+nats-port = 4223
+hosts = 2
+host-labels = ["zone=a", "zone=b"]
+allow-latest = true
+host-seed = "SNAP4UVNHVWSBJ5MFL6HWHVOTOYXPFNUHJ5AB5YUWIOCY5FBG6DI3BXQS4"
+"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_resolve_profile() {
+        let (up, matches) = resolve(
+            ["wash", "up", "--profile", "dev", "--lattice-prefix", "cli"],
+            Some(&profile()),
+        )
+        .unwrap();
+        assert_eq!(up.wasmcloud_opts.lattice_prefix, "cli");
+        assert_eq!(up.nats_opts.nats_port, 4223);
+        assert_eq!(up.hosts, 2);
+        assert_eq!(up.host_labels, vec!["zone=a", "zone=b"]);
+        assert!(up.wasmcloud_opts.allow_latest);
+
+        let config = effective_config(&matches).unwrap();
+        assert_eq!(
+            config.get("lattice-prefix"),
+            Some(&Value::String("cli".to_string()))
+        );
+        assert_eq!(config.get("nats-port"), Some(&Value::Integer(4223)));
+        assert_eq!(config.get("allow-latest"), Some(&Value::Boolean(true)));
+        assert_eq!(
+            config.get("host-seed"),
+            Some(&Value::String("<redacted>".to_string()))
+        );
+        assert!(!config.contains_key("profile"));
+    }
+
+    #[test]
+    fn test_invalid_profiles() {
+        let unknown: Table = toml::from_str("nats-prot = 4223").unwrap();
+        assert!(resolve(["wash", "up"], Some(&unknown)).is_err());
+
+        let not_a_flag: Table = toml::from_str("detached = \"yes\"").unwrap();
+        assert!(resolve(["wash", "up"], Some(&not_a_flag)).is_err());
+
+        let nested: Table = toml::from_str("[nats-port]\nvalue = 1").unwrap();
+        assert!(resolve(["wash", "up"], Some(&nested)).is_err());
+    }
+}
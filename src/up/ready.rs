@@ -0,0 +1,177 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::Path,
+    time::Duration,
+};
+
+use anyhow::{bail, Result};
+use serde::Serialize;
+use tokio::{process::Child, time::Instant};
+use wasmcloud_control_interface::Client as CtlClient;
+
+use crate::{
+    ctl::wait::{get_wasmbus_event_info, CloudEventData},
+    util::convert_error,
+};
+
+/// How long to wait for freshly started hosts to join the lattice, unless otherwise specified
+pub(crate) const DEFAULT_HOST_READY_TIMEOUT_MS: u64 = 60_000;
+/// How many lines of a host's log are shown when it fails to start
+pub(crate) const LOG_TAIL_LINES: usize = 20;
+const HOST_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// A host that has joined the lattice, with the labels it reported
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub(crate) struct ReadyHost {
+    pub id: String,
+    pub labels: BTreeMap<String, String>,
+}
+
+/// Waits until every host in `host_ids` has joined the lattice, as seen by a `host_started` or
+/// heartbeat event, or by the host answering an inventory request. Fails when the timeout passes
+/// or when one of `children` exits first, as a host that exits during boot won't come back.
+/// Returns the hosts in the order of `host_ids`
+pub(crate) async fn wait_for_hosts(
+    client: &CtlClient,
+    host_ids: &[String],
+    children: &mut [Child],
+    timeout: Duration,
+    progress: impl Fn(String),
+) -> Result<Vec<ReadyHost>> {
+    let deadline = Instant::now() + timeout;
+    let mut receiver = client.events_receiver().await.map_err(convert_error)?;
+    let mut ready: HashMap<String, ReadyHost> = HashMap::new();
+    let mut poll = tokio::time::interval(HOST_POLL_INTERVAL);
+
+    while ready.len() < host_ids.len() {
+        progress(format!(
+            "Waiting for wasmCloud to start ({}/{} hosts ready)",
+            ready.len(),
+            host_ids.len()
+        ));
+        tokio::select! {
+            _ = poll.tick() => {
+                // The hosts may not be subscribed to the control interface yet, so errors are retried
+                if let Ok(hosts) = client.get_hosts().await.map_err(convert_error) {
+                    for host in hosts.into_iter().filter(|h| host_ids.contains(&h.id)) {
+                        ready.entry(host.id.clone()).or_insert(ReadyHost {
+                            id: host.id,
+                            labels: host.labels.unwrap_or_default().into_iter().collect(),
+                        });
+                    }
+                }
+                for child in children.iter_mut() {
+                    if let Some(status) = child.try_wait()? {
+                        bail!("A wasmCloud host exited with {} before it joined the lattice", status);
+                    }
+                }
+            }
+            Some(event) = receiver.recv() => {
+                if let Some(host) = get_wasmbus_event_info(event)
+                    .ok()
+                    .and_then(|event| ready_host_from_event(&event, host_ids))
+                {
+                    ready.entry(host.id.clone()).or_insert(host);
+                }
+            }
+            _ = tokio::time::sleep_until(deadline) => bail!(
+                "Timed out after {}s waiting for wasmCloud to start, {} of {} host(s) joined the lattice",
+                timeout.as_secs(),
+                ready.len(),
+                host_ids.len()
+            ),
+        }
+    }
+    Ok(host_ids.iter().filter_map(|id| ready.remove(id)).collect())
+}
+
+/// Returns the host an event announces as running, if it is one of `host_ids`
+fn ready_host_from_event(event: &CloudEventData, host_ids: &[String]) -> Option<ReadyHost> {
+    match event.event_type.as_str() {
+        "com.wasmcloud.lattice.host_started" | "com.wasmcloud.lattice.host_heartbeat" => {}
+        _ => return None,
+    }
+    if !host_ids.contains(&event.source) {
+        return None;
+    }
+    let labels = event
+        .data
+        .get("labels")
+        .and_then(|l| l.as_object())
+        .map(|labels| {
+            labels
+                .iter()
+                .filter_map(|(k, v)| Some((k.clone(), v.as_str()?.to_string())))
+                .collect()
+        })
+        .unwrap_or_default();
+    Some(ReadyHost {
+        id: event.source.clone(),
+        labels,
+    })
+}
+
+/// Returns the last `lines` lines of a log file, or a note saying why it couldn't be read
+pub(crate) fn tail_log(path: &Path, lines: usize) -> String {
+    match std::fs::read_to_string(path) {
+        Ok(contents) => {
+            let all = contents.lines().collect::<Vec<_>>();
+            all[all.len().saturating_sub(lines)..].join("\n")
+        }
+        Err(e) => format!("(could not read {}: {})", path.display(), e),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ready_host_from_event, tail_log, ReadyHost};
+    use crate::ctl::wait::CloudEventData;
+    use serde_json::json;
+    use std::{collections::BTreeMap, io::Write};
+
+    const HOST: &str = "NCNGQPQQ3UTHLIR2KGLWALGQBWIKNZCC6TAJDSVRHMDS7VXBJWPRJ6LB";
+
+    #[test]
+    fn test_ready_host_from_event() {
+        let host_ids = vec![HOST.to_string()];
+        let event = CloudEventData {
+            event_type: "com.wasmcloud.lattice.host_started".to_string(),
+            source: HOST.to_string(),
+            data: json!({ "labels": { "zone": "a", "hostcore.os": "linux" } }),
+        };
+        assert_eq!(
+            ready_host_from_event(&event, &host_ids),
+            Some(ReadyHost {
+                id: HOST.to_string(),
+                labels: BTreeMap::from([
+                    ("hostcore.os".to_string(), "linux".to_string()),
+                    ("zone".to_string(), "a".to_string()),
+                ]),
+            })
+        );
+
+        let other_host = CloudEventData {
+            source: "NOTHERHOST".to_string(),
+            ..event
+        };
+        assert_eq!(ready_host_from_event(&other_host, &host_ids), None);
+
+        let other_event = CloudEventData {
+            event_type: "com.wasmcloud.lattice.actor_started".to_string(),
+            source: HOST.to_string(),
+            data: json!({}),
+        };
+        assert_eq!(ready_host_from_event(&other_event, &host_ids), None);
+    }
+
+    #[test]
+    fn test_tail_log() {
+        let mut log = tempfile::NamedTempFile::new().unwrap();
+        for line in 1..=5 {
+            writeln!(log, "line {}", line).unwrap();
+        }
+        assert_eq!(tail_log(log.path(), 2), "line 4\nline 5");
+        assert_eq!(tail_log(log.path(), 10).lines().count(), 5);
+        assert!(tail_log(&log.path().join("missing"), 2).starts_with("(could not read"));
+    }
+}
@@ -0,0 +1,269 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::cfg::cfg_dir;
+
+use super::config::DOWNLOADS_DIR;
+
+/// File in the environment directory that records what `wash up` started, so that `wash down`
+/// can stop it again
+pub(crate) const UP_STATE_FILE: &str = "wash_up.json";
+/// How the environment started without `--name` is referred to
+pub(crate) const DEFAULT_ENV: &str = "default";
+/// Directory in `~/.wash` holding a directory per named `wash up` environment
+pub(crate) const ENVS_DIR: &str = "envs";
+/// Dashboard port of the first host, subsequent hosts use the ports after it
+pub(crate) const DEFAULT_DASHBOARD_PORT: u16 = 4000;
+/// Environment variable the wasmCloud host release uses as the name of its Erlang node. Hosts
+/// on the same machine need distinct node names
+pub(crate) const RELEASE_NODE: &str = "RELEASE_NODE";
+/// Environment variable for the port of the wasmCloud dashboard
+pub(crate) const DASHBOARD_PORT: &str = "PORT";
+/// Prefix of the environment variables that set wasmCloud host labels
+pub(crate) const HOST_LABEL_PREFIX: &str = "HOST_";
+
+/// The processes started by `wash up`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct UpState {
+    /// The `nats-server` binary NATS was started with, unless `wash up` connected to an existing
+    /// NATS server
+    #[serde(default)]
+    pub nats_bin: Option<PathBuf>,
+    /// The `wasmcloud_host` release script the hosts were started with
+    pub wasmcloud_bin: PathBuf,
+    pub hosts: Vec<LocalHost>,
+    /// Lattice prefix the hosts were started with
+    #[serde(default)]
+    pub lattice_prefix: Option<String>,
+    /// Host of the NATS server the hosts connect to
+    #[serde(default)]
+    pub nats_host: Option<String>,
+    /// Port of the NATS server the hosts connect to, which is the one picked by wash for
+    /// `--nats-port auto`
+    #[serde(default)]
+    pub nats_port: Option<u16>,
+}
+
+/// A wasmCloud host started by `wash up`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct LocalHost {
+    /// Erlang node name of the host, or `None` for the release's default node name
+    pub node_name: Option<String>,
+    pub dashboard_port: u16,
+    /// Public key of the host, if its seed was known when it was started
+    pub host_id: Option<String>,
+    #[serde(default)]
+    pub labels: BTreeMap<String, String>,
+    /// Where the host's logs are written, when they aren't written to the terminal
+    pub log_path: PathBuf,
+}
+
+impl LocalHost {
+    /// Environment variables that distinguish this host from the others started with it
+    pub(crate) fn env(&self) -> Vec<(String, String)> {
+        let mut env = vec![(DASHBOARD_PORT.to_string(), self.dashboard_port.to_string())];
+        if let Some(node_name) = &self.node_name {
+            env.push((RELEASE_NODE.to_string(), node_name.clone()));
+        }
+        env.extend(
+            self.labels
+                .iter()
+                .map(|(key, value)| (format!("{}{}", HOST_LABEL_PREFIX, key), value.clone())),
+        );
+        env
+    }
+}
+
+/// Parses `--host-labels`, where each entry holds the labels of one host as `key=value` pairs
+/// separated by `;`, e.g. `zone=a;tier=web`
+pub(crate) fn parse_host_labels(
+    entries: &[String],
+    hosts: usize,
+) -> Result<Vec<BTreeMap<String, String>>> {
+    if entries.len() > hosts {
+        bail!(
+            "{} sets of host labels were given for {} host(s), provide at most one per host",
+            entries.len(),
+            hosts
+        );
+    }
+    let mut labels = Vec::with_capacity(hosts);
+    for entry in entries {
+        let mut host_labels = BTreeMap::new();
+        for label in entry.split(';').filter(|l| !l.trim().is_empty()) {
+            match label.split_once('=') {
+                Some((key, value)) if !key.trim().is_empty() => {
+                    host_labels.insert(key.trim().to_string(), value.trim().to_string());
+                }
+                _ => bail!("Invalid host label '{}', expected key=value", label),
+            }
+        }
+        labels.push(host_labels);
+    }
+    labels.resize(hosts, BTreeMap::new());
+    Ok(labels)
+}
+
+/// Parses the name of a `wash up` environment, which becomes part of a directory and of the
+/// hosts' Erlang node names
+pub(crate) fn parse_env_name(name: &str) -> Result<String> {
+    if name.is_empty()
+        || name == DEFAULT_ENV
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        bail!(
+            "Invalid environment name '{}', use only letters, digits, '-' and '_', and a name other than '{}'",
+            name,
+            DEFAULT_ENV
+        );
+    }
+    Ok(name.to_string())
+}
+
+/// Directory holding the state, logs, NATS config and pid file of a `wash up` environment. The
+/// default environment keeps them in the downloads directory, named environments in
+/// `~/.wash/envs/<name>`. Binaries are always shared through the downloads directory
+pub(crate) fn env_dir(name: Option<&str>) -> Result<PathBuf> {
+    let cfg_dir = cfg_dir()?;
+    Ok(match name {
+        Some(name) => cfg_dir.join(ENVS_DIR).join(name),
+        None => cfg_dir.join(DOWNLOADS_DIR),
+    })
+}
+
+/// Names of the named environments that have been started and not stopped since, sorted
+pub(crate) async fn named_envs() -> Result<Vec<String>> {
+    let envs_dir = cfg_dir()?.join(ENVS_DIR);
+    if !envs_dir.is_dir() {
+        return Ok(Vec::new());
+    }
+    let mut envs = Vec::new();
+    let mut entries = tokio::fs::read_dir(&envs_dir)
+        .await
+        .with_context(|| format!("Failed to read {}", envs_dir.display()))?;
+    while let Some(entry) = entries.next_entry().await? {
+        if entry.path().join(UP_STATE_FILE).is_file() {
+            envs.push(entry.file_name().to_string_lossy().to_string());
+        }
+    }
+    envs.sort();
+    Ok(envs)
+}
+
+/// Reads the state written by the last `wash up`, if there is any
+pub(crate) async fn read_up_state(install_dir: &Path) -> Result<Option<UpState>> {
+    let path = install_dir.join(UP_STATE_FILE);
+    if !path.is_file() {
+        return Ok(None);
+    }
+    let contents = tokio::fs::read(&path)
+        .await
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    parse_up_state(&path, &contents).map(Some)
+}
+
+/// Like [read_up_state], for callers without an async runtime to read it with
+pub(crate) fn read_up_state_blocking(install_dir: &Path) -> Result<Option<UpState>> {
+    let path = install_dir.join(UP_STATE_FILE);
+    if !path.is_file() {
+        return Ok(None);
+    }
+    let contents =
+        std::fs::read(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+    parse_up_state(&path, &contents).map(Some)
+}
+
+fn parse_up_state(path: &Path, contents: &[u8]) -> Result<UpState> {
+    serde_json::from_slice(contents).with_context(|| format!("Failed to parse {}", path.display()))
+}
+
+/// Reads the state of the default environment and every named environment that is running
+pub(crate) async fn read_all_up_states() -> Result<Vec<UpState>> {
+    let mut states = Vec::new();
+    let mut names = vec![None];
+    names.extend(named_envs().await?.into_iter().map(Some));
+    for name in names {
+        if let Some(state) = read_up_state(&env_dir(name.as_deref())?).await? {
+            states.push(state);
+        }
+    }
+    Ok(states)
+}
+
+pub(crate) async fn write_up_state(install_dir: &Path, state: &UpState) -> Result<()> {
+    let path = install_dir.join(UP_STATE_FILE);
+    tokio::fs::write(&path, serde_json::to_vec_pretty(state)?)
+        .await
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+pub(crate) async fn remove_up_state(install_dir: &Path) -> Result<()> {
+    let path = install_dir.join(UP_STATE_FILE);
+    if path.is_file() {
+        tokio::fs::remove_file(&path)
+            .await
+            .with_context(|| format!("Failed to remove {}", path.display()))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{parse_env_name, parse_host_labels, LocalHost};
+    use std::{collections::BTreeMap, path::PathBuf};
+
+    #[test]
+    fn test_parse_host_labels() {
+        let labels =
+            parse_host_labels(&["zone=a;tier=web".to_string(), "zone=b".to_string()], 3).unwrap();
+        assert_eq!(
+            labels,
+            vec![
+                BTreeMap::from([
+                    ("tier".to_string(), "web".to_string()),
+                    ("zone".to_string(), "a".to_string()),
+                ]),
+                BTreeMap::from([("zone".to_string(), "b".to_string())]),
+                BTreeMap::new(),
+            ]
+        );
+
+        assert!(parse_host_labels(&["zone=a".to_string(), "zone=b".to_string()], 1).is_err());
+        assert!(parse_host_labels(&["zone".to_string()], 1).is_err());
+    }
+
+    #[test]
+    fn test_parse_env_name() {
+        assert_eq!(parse_env_name("leaf-a_2").unwrap(), "leaf-a_2");
+        assert!(parse_env_name("").is_err());
+        assert!(parse_env_name("default").is_err());
+        assert!(parse_env_name("../escape").is_err());
+        assert!(parse_env_name("two words").is_err());
+    }
+
+    #[test]
+    fn test_local_host_env() {
+        let host = LocalHost {
+            node_name: Some("wasmcloud_host_2".to_string()),
+            dashboard_port: 4001,
+            host_id: None,
+            labels: BTreeMap::from([("zone".to_string(), "b".to_string())]),
+            log_path: PathBuf::from("wasmcloud_2.log"),
+        };
+        assert_eq!(
+            host.env(),
+            vec![
+                ("PORT".to_string(), "4001".to_string()),
+                ("RELEASE_NODE".to_string(), "wasmcloud_host_2".to_string()),
+                ("HOST_zone".to_string(), "b".to_string()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,250 @@
+use std::{
+    collections::HashMap,
+    fmt::Write,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Result;
+use clap::{Parser, Subcommand};
+use serde::Serialize;
+use serde_json::json;
+use wash_lib::cli::{CommandOutput, OutputKind};
+use wash_lib::start::{
+    available_nats_versions, available_wasmcloud_versions, installed_nats_servers,
+    installed_wasmcloud_versions, remove_nats_server, remove_wasmcloud, NATS_SERVER_PID,
+};
+
+use crate::{appearance::spinner::Spinner, cfg::cfg_dir};
+
+use super::{
+    config::{DOWNLOADS_DIR, NATS_SERVER_VERSION, WASMCLOUD_HOST_VERSION},
+    state::read_all_up_states,
+};
+
+/// How many of the versions available for download are listed
+const AVAILABLE_VERSIONS_SHOWN: usize = 10;
+
+#[derive(Parser, Debug, Clone)]
+pub(crate) struct VersionsCommand {
+    /// Only list installed versions, without looking up the versions available for download
+    #[clap(long = "installed")]
+    pub(crate) installed: bool,
+
+    #[clap(subcommand)]
+    pub(crate) command: Option<VersionsSubcommand>,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub(crate) enum VersionsSubcommand {
+    /// Remove installed versions of NATS and wasmCloud, except for the default versions and the ones a running `wash up` uses
+    #[clap(name = "prune")]
+    Prune(PruneCommand),
+}
+
+#[derive(Parser, Debug, Clone)]
+pub(crate) struct PruneCommand {
+    /// A version of NATS or wasmCloud to keep installed, e.g. `v2.9.14`. Can be repeated
+    #[clap(long = "keep")]
+    pub(crate) keep: Vec<String>,
+}
+
+/// A version of NATS or wasmCloud installed in the downloads directory
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+struct InstalledVersion {
+    version: String,
+    path: PathBuf,
+    /// Whether this is the version `wash up` uses unless told otherwise
+    default: bool,
+    /// Whether a running `wash up` uses this version
+    in_use: bool,
+}
+
+impl InstalledVersion {
+    fn should_prune(&self, keep: &[String]) -> bool {
+        !self.default
+            && !self.in_use
+            && !keep
+                .iter()
+                .any(|v| v.trim_start_matches('v') == self.version.trim_start_matches('v'))
+    }
+
+    fn text(&self) -> String {
+        let notes = [(self.default, "default"), (self.in_use, "in use")]
+            .iter()
+            .filter(|(applies, _)| *applies)
+            .map(|(_, note)| *note)
+            .collect::<Vec<_>>();
+        if notes.is_empty() {
+            format!("{} at {}", self.version, self.path.display())
+        } else {
+            format!(
+                "{} ({}) at {}",
+                self.version,
+                notes.join(", "),
+                self.path.display()
+            )
+        }
+    }
+}
+
+pub(crate) async fn handle_command(
+    command: VersionsCommand,
+    output_kind: OutputKind,
+) -> Result<CommandOutput> {
+    let install_dir = cfg_dir()?.join(DOWNLOADS_DIR);
+    let (nats, wasmcloud) = installed_versions(&install_dir).await?;
+    match command.command {
+        Some(VersionsSubcommand::Prune(cmd)) => {
+            prune(&install_dir, nats, wasmcloud, &cmd.keep).await
+        }
+        None if command.installed => Ok(list_output(nats, wasmcloud, None, None)),
+        None => {
+            let sp = Spinner::new(&output_kind)?;
+            sp.update_spinner_message(" Looking up available versions ...".to_string());
+            let nats_available = available_nats_versions().await;
+            let wasmcloud_available = available_wasmcloud_versions().await;
+            sp.finish_and_clear();
+            Ok(list_output(
+                nats,
+                wasmcloud,
+                Some(nats_available),
+                Some(wasmcloud_available),
+            ))
+        }
+    }
+}
+
+/// Lists the installed versions of NATS and wasmCloud, marking the defaults and those in use
+async fn installed_versions(
+    install_dir: &Path,
+) -> Result<(Vec<InstalledVersion>, Vec<InstalledVersion>)> {
+    let states = read_all_up_states().await?;
+    let nats = installed_nats_servers(install_dir)
+        .await
+        .into_iter()
+        .map(|(version, path)| InstalledVersion {
+            default: same_version(&version, NATS_SERVER_VERSION),
+            // Older versions of wash had NATS write its pid file next to its binary
+            in_use: states.iter().any(|s| s.nats_bin.as_ref() == Some(&path))
+                || path.with_file_name(NATS_SERVER_PID).is_file(),
+            version,
+            path,
+        })
+        .collect();
+    let wasmcloud = installed_wasmcloud_versions(install_dir)
+        .await
+        .into_iter()
+        .map(|(version, path)| InstalledVersion {
+            default: same_version(&version, WASMCLOUD_HOST_VERSION),
+            in_use: states.iter().any(|s| s.wasmcloud_bin == path),
+            version,
+            path,
+        })
+        .collect();
+    Ok((nats, wasmcloud))
+}
+
+fn list_output(
+    nats: Vec<InstalledVersion>,
+    wasmcloud: Vec<InstalledVersion>,
+    nats_available: Option<Result<Vec<String>>>,
+    wasmcloud_available: Option<Result<Vec<String>>>,
+) -> CommandOutput {
+    let mut out_json = HashMap::new();
+    let mut out_text = String::new();
+    for (name, key, installed, available) in [
+        ("NATS", "nats", nats, nats_available),
+        ("wasmCloud", "wasmcloud", wasmcloud, wasmcloud_available),
+    ] {
+        let _ = writeln!(out_text, "{}:", name);
+        if installed.is_empty() {
+            let _ = writeln!(out_text, "  No versions installed");
+        }
+        for version in &installed {
+            let _ = writeln!(out_text, "  {}", version.text());
+        }
+        let mut component = json!({ "installed": installed });
+        match available {
+            Some(Ok(versions)) => {
+                let _ = writeln!(
+                    out_text,
+                    "  Available: {}",
+                    versions
+                        .iter()
+                        .take(AVAILABLE_VERSIONS_SHOWN)
+                        .cloned()
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                );
+                component["available"] = json!(versions);
+            }
+            Some(Err(e)) => {
+                let _ = writeln!(out_text, "  Could not look up available versions: {}", e);
+                component["available_error"] = json!(e.to_string());
+            }
+            None => {}
+        }
+        out_json.insert(key.to_string(), component);
+    }
+    CommandOutput::new(out_text.trim_end(), out_json)
+}
+
+async fn prune(
+    install_dir: &Path,
+    nats: Vec<InstalledVersion>,
+    wasmcloud: Vec<InstalledVersion>,
+    keep: &[String],
+) -> Result<CommandOutput> {
+    let mut removed_nats = Vec::new();
+    for version in nats.into_iter().filter(|v| v.should_prune(keep)) {
+        remove_nats_server(install_dir, &version.path).await?;
+        removed_nats.push(version.version);
+    }
+    let mut removed_wasmcloud = Vec::new();
+    for version in wasmcloud.into_iter().filter(|v| v.should_prune(keep)) {
+        remove_wasmcloud(install_dir, &version.version).await?;
+        removed_wasmcloud.push(version.version);
+    }
+
+    let mut out_json = HashMap::new();
+    out_json.insert("removed_nats".to_string(), json!(removed_nats));
+    out_json.insert("removed_wasmcloud".to_string(), json!(removed_wasmcloud));
+    let out_text = if removed_nats.is_empty() && removed_wasmcloud.is_empty() {
+        "🤔 No versions to remove".to_string()
+    } else {
+        let mut text = String::new();
+        for version in &removed_nats {
+            let _ = writeln!(text, "🗑  Removed NATS {}", version);
+        }
+        for version in &removed_wasmcloud {
+            let _ = writeln!(text, "🗑  Removed wasmCloud {}", version);
+        }
+        text.trim_end().to_string()
+    };
+    Ok(CommandOutput::new(out_text, out_json))
+}
+
+fn same_version(a: &str, b: &str) -> bool {
+    a.trim_start_matches('v') == b.trim_start_matches('v')
+}
+
+#[cfg(test)]
+mod test {
+    use super::InstalledVersion;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_should_prune() {
+        let version = |version: &str, default: bool, in_use: bool| InstalledVersion {
+            version: version.to_string(),
+            path: PathBuf::from("/tmp/downloads").join(version),
+            default,
+            in_use,
+        };
+        let keep = vec!["2.8.4".to_string()];
+        assert!(version("v2.9.0", false, false).should_prune(&keep));
+        assert!(!version("v2.9.14", true, false).should_prune(&keep));
+        assert!(!version("v2.9.10", false, true).should_prune(&keep));
+        assert!(!version("v2.8.4", false, false).should_prune(&keep));
+    }
+}
@@ -1,4 +1,4 @@
-use std::{fs::File, io::Read, path::PathBuf};
+use std::{fs::File, io::Read, path::PathBuf, str::FromStr};
 
 use anyhow::{anyhow, bail, Context, Result};
 use term_table::{Table, TableStyle};
@@ -37,16 +37,62 @@ pub(crate) fn json_str_to_msgpack_bytes(payload: &str) -> Result<Vec<u8>> {
     Ok(payload)
 }
 
-use once_cell::sync::OnceCell;
-static BIN_STR: OnceCell<char> = OnceCell::new();
+/// Transform a yaml string (e.g. "hello: world") into msgpack bytes
+pub(crate) fn yaml_str_to_msgpack_bytes(payload: &str) -> Result<Vec<u8>> {
+    let json = serde_yaml::from_str::<serde_json::Value>(payload)?;
+    let payload = wasmbus_rpc::common::serialize(&json)?;
+    Ok(payload)
+}
+
+/// How binary data is represented when it's converted to json
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub(crate) enum BinaryDisplay {
+    /// An array of bytes ('b')
+    #[default]
+    Bytes,
+    /// A lossy UTF-8 string ('s')
+    String,
+    /// Both of the above, under the `bin` and `str` keys ('2')
+    Both,
+}
+
+impl FromStr for BinaryDisplay {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "b" => Ok(BinaryDisplay::Bytes),
+            "s" => Ok(BinaryDisplay::String),
+            "2" => Ok(BinaryDisplay::Both),
+            _ => bail!("'bin' parameter must be 'b', 's', or '2'"),
+        }
+    }
+}
+
+/// Transform binary data into json, as specified by `bin`
+pub(crate) fn binary_to_json(v: Vec<u8>, bin: BinaryDisplay) -> serde_json::Value {
+    use serde_json::Value as JV;
+    match bin {
+        BinaryDisplay::String => JV::String(String::from_utf8_lossy(&v).into_owned()),
+        BinaryDisplay::Both => serde_json::json!({
+            "str": String::from_utf8_lossy(&v),
+            "bin": v,
+        }),
+        BinaryDisplay::Bytes => JV::Array(v.into_iter().map(JV::from).collect::<Vec<_>>()),
+    }
+}
 
-fn msgpack_to_json(mval: rmpv::Value) -> serde_json::Value {
+fn msgpack_to_json(mval: rmpv::Value, bin: BinaryDisplay) -> serde_json::Value {
     use rmpv::Value as RV;
     use serde_json::Value as JV;
     match mval {
         RV::String(s) => JV::String(s.to_string()),
         RV::Boolean(b) => JV::Bool(b),
-        RV::Array(v) => JV::Array(v.into_iter().map(msgpack_to_json).collect::<Vec<_>>()),
+        RV::Array(v) => JV::Array(
+            v.into_iter()
+                .map(|v| msgpack_to_json(v, bin))
+                .collect::<Vec<_>>(),
+        ),
         RV::F64(f) => JV::from(f),
         RV::F32(f) => JV::from(f),
         RV::Integer(i) => match (i.is_u64(), i.is_i64()) {
@@ -59,19 +105,12 @@ fn msgpack_to_json(mval: rmpv::Value) -> serde_json::Value {
                 .map(|(k, v)| {
                     (
                         k.as_str().unwrap_or_default().to_string(),
-                        msgpack_to_json(v),
+                        msgpack_to_json(v, bin),
                     )
                 })
                 .collect::<serde_json::Map<_, _>>(),
         ),
-        RV::Binary(v) => match BIN_STR.get().unwrap() {
-            's' => JV::String(String::from_utf8_lossy(&v).into_owned()),
-            '2' => serde_json::json!({
-                "str": String::from_utf8_lossy(&v),
-                "bin": v,
-            }),
-            /*'b'|*/ _ => JV::Array(v.into_iter().map(JV::from).collect::<Vec<_>>()),
-        },
+        RV::Binary(v) => binary_to_json(v, bin),
         RV::Ext(i, v) => serde_json::json!({
             "type": i,
             "data": v
@@ -81,14 +120,12 @@ fn msgpack_to_json(mval: rmpv::Value) -> serde_json::Value {
 }
 
 /// transform msgpack bytes into json
-pub(crate) fn msgpack_to_json_val(msg: Vec<u8>, bin_str: char) -> serde_json::Value {
+pub(crate) fn msgpack_to_json_val(msg: Vec<u8>, bin: BinaryDisplay) -> serde_json::Value {
     use bytes::Buf;
 
-    BIN_STR.set(bin_str).unwrap();
-
     let bytes = bytes::Bytes::from(msg);
     if let Ok(v) = rmpv::decode::value::read_value(&mut bytes.reader()) {
-        msgpack_to_json(v)
+        msgpack_to_json(v, bin)
     } else {
         serde_json::json!({ "error": "Could not decode data" })
     }
@@ -191,7 +228,46 @@ pub fn validate_contract_id(contract_id: &str) -> Result<()> {
     }
 }
 
+#[cfg(test)]
 mod test {
+    use super::{msgpack_to_json_val, yaml_str_to_msgpack_bytes, BinaryDisplay};
+
+    #[test]
+    fn test_msgpack_to_json_binary_display() {
+        let mut msg = Vec::new();
+        rmpv::encode::write_value(
+            &mut msg,
+            &rmpv::Value::Map(vec![("data".into(), rmpv::Value::Binary(b"hi".to_vec()))]),
+        )
+        .unwrap();
+
+        // Each conversion picks its own representation, repeated calls must not conflict
+        assert_eq!(
+            msgpack_to_json_val(msg.clone(), BinaryDisplay::Bytes),
+            serde_json::json!({ "data": [104, 105] })
+        );
+        assert_eq!(
+            msgpack_to_json_val(msg.clone(), BinaryDisplay::String),
+            serde_json::json!({ "data": "hi" })
+        );
+        assert_eq!(
+            msgpack_to_json_val(msg, BinaryDisplay::Both),
+            serde_json::json!({ "data": { "str": "hi", "bin": [104, 105] } })
+        );
+        assert!("x".parse::<BinaryDisplay>().is_err());
+    }
+
+    #[test]
+    fn test_yaml_payload_matches_json() {
+        let yaml = yaml_str_to_msgpack_bytes("hello: world\ncount: 3").unwrap();
+        let json =
+            super::json_str_to_msgpack_bytes("{\"hello\": \"world\", \"count\": 3}").unwrap();
+        assert_eq!(
+            msgpack_to_json_val(yaml, BinaryDisplay::Bytes),
+            msgpack_to_json_val(json, BinaryDisplay::Bytes)
+        );
+    }
+
     #[test]
     fn test_safe_base64_parse_option() {
         let base64_option = "config_b64=eyJhZGRyZXNzIjogIjAuMC4wLjA6ODA4MCJ9Cg==".to_string();